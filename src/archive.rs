@@ -0,0 +1,123 @@
+use crate::input::Input;
+use crate::limits::{allow_decompressed, allow_entry, limit_decompressed};
+use crate::report::Report;
+use crate::tar::{for_each_tar_entry, is_tar_header};
+use flate2::read::MultiGzDecoder;
+use std::io::{Cursor, Error, ErrorKind, Read};
+use unrar::Archive;
+
+/// Archives whose entries can be read one by one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveKind {
+    Zip,
+    Rar,
+    Tar,
+    TarGz,
+}
+
+/// Gets kind of archive from magic bytes, zip files are also found by central directory.
+pub fn get_archive_kind(data: &[u8]) -> Option<ArchiveKind> {
+    let header = &data[..data.len().min(512)];
+    if header.starts_with(b"Rar!\x1a\x07") {
+        return Some(ArchiveKind::Rar);
+    }
+    if is_tar_header(header) {
+        return Some(ArchiveKind::Tar);
+    }
+    if header.starts_with(&[0x1F, 0x8B]) {
+        let mut decompressed = Vec::new();
+        // Errors are expected for gzip files shorter than one block.
+        let _ = MultiGzDecoder::new(data)
+            .take(512)
+            .read_to_end(&mut decompressed);
+        return is_tar_header(&decompressed).then_some(ArchiveKind::TarGz);
+    }
+    // Self-extracting archives and jar-like formats have data before zip entries.
+    zip::ZipArchive::new(Cursor::new(data))
+        .ok()
+        .map(|_| ArchiveKind::Zip)
+}
+
+/// Calls function for every file in archive with reader of its decompressed data.
+/// Errors of single entries are printed, error is returned only if archive can't be read.
+pub fn for_each_entry<F>(
+    input: &Input,
+    kind: ArchiveKind,
+    report: &mut Report,
+    mut f: F,
+) -> Result<(), Error>
+where
+    F: FnMut(&str, &mut dyn Read, &mut Report),
+{
+    match kind {
+        ArchiveKind::Zip => {
+            let mut archive = zip::ZipArchive::new(input.reader())?;
+            for i in (0..archive.len()).take_while(|i| allow_entry(*i)) {
+                let mut file = match archive.by_index(i) {
+                    Ok(file) => file,
+                    Err(e) => {
+                        writeln!(report, "Error (most likely encrypted file): {}", e);
+                        continue;
+                    }
+                };
+                if !file.is_dir() {
+                    let name = file.name().to_string();
+                    f(&name, &mut limit_decompressed(&mut file), report);
+                }
+            }
+        }
+        ArchiveKind::Rar => {
+            // Unrar library reads archive by itself, it would get whole file.
+            if input.is_truncated() {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "RAR archive can't be read only up to --max-read",
+                ));
+            }
+            let mut archive = Archive::new(&input.path)
+                .open_for_processing()
+                .map_err(|e| Error::other(e.to_string()))?;
+            let mut index = 0;
+            while let Some(entry) = archive
+                .read_header()
+                .map_err(|e| Error::other(e.to_string()))?
+            {
+                if !allow_entry(index) {
+                    break;
+                }
+                index += 1;
+                let name = entry.entry().filename.to_string_lossy().to_string();
+                archive = if entry.entry().is_directory() {
+                    entry.skip()
+                } else if !allow_decompressed(entry.entry().unpacked_size) {
+                    // Library decompresses whole entry into memory.
+                    writeln!(report, "\"{}\": skipped, over decompression limit", name);
+                    entry.skip()
+                } else {
+                    entry.read().map(|(data, archive)| {
+                        f(&name, &mut limit_decompressed(data.as_slice()), report);
+                        archive
+                    })
+                }
+                .map_err(|e| Error::other(format!("\"{}\": {}", name, e)))?;
+            }
+        }
+        ArchiveKind::Tar | ArchiveKind::TarGz => {
+            let data = input.bytes();
+            let reader: Box<dyn Read> = if kind == ArchiveKind::TarGz {
+                Box::new(limit_decompressed(MultiGzDecoder::new(data)))
+            } else {
+                Box::new(data)
+            };
+            let mut index = 0;
+            for_each_tar_entry(reader, |entry, data| {
+                // Headers of skipped entries are still read, their data is limited.
+                index += 1;
+                if entry.is_file() && allow_entry(index - 1) {
+                    f(&entry.name, data, report);
+                }
+            })?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,1105 @@
+use crate::input::Input;
+use crate::limits::is_timed_out;
+use crate::report::Report;
+use crate::{format_size, Arguments};
+use std::io::{Error, ErrorKind};
+
+/// ID3v1 genres, index is the genre byte.
+const ID3V1_GENRES: [&str; 80] = [
+    "Blues",
+    "Classic Rock",
+    "Country",
+    "Dance",
+    "Disco",
+    "Funk",
+    "Grunge",
+    "Hip-Hop",
+    "Jazz",
+    "Metal",
+    "New Age",
+    "Oldies",
+    "Other",
+    "Pop",
+    "R&B",
+    "Rap",
+    "Reggae",
+    "Rock",
+    "Techno",
+    "Industrial",
+    "Alternative",
+    "Ska",
+    "Death Metal",
+    "Pranks",
+    "Soundtrack",
+    "Euro-Techno",
+    "Ambient",
+    "Trip-Hop",
+    "Vocal",
+    "Jazz+Funk",
+    "Fusion",
+    "Trance",
+    "Classical",
+    "Instrumental",
+    "Acid",
+    "House",
+    "Game",
+    "Sound Clip",
+    "Gospel",
+    "Noise",
+    "AlternRock",
+    "Bass",
+    "Soul",
+    "Punk",
+    "Space",
+    "Meditative",
+    "Instrumental Pop",
+    "Instrumental Rock",
+    "Ethnic",
+    "Gothic",
+    "Darkwave",
+    "Techno-Industrial",
+    "Electronic",
+    "Pop-Folk",
+    "Eurodance",
+    "Dream",
+    "Southern Rock",
+    "Comedy",
+    "Cult",
+    "Gangsta",
+    "Top 40",
+    "Christian Rap",
+    "Pop/Funk",
+    "Jungle",
+    "Native American",
+    "Cabaret",
+    "New Wave",
+    "Psychadelic",
+    "Rave",
+    "Showtunes",
+    "Trailer",
+    "Lo-Fi",
+    "Tribal",
+    "Acid Punk",
+    "Acid Jazz",
+    "Polka",
+    "Retro",
+    "Musical",
+    "Rock & Roll",
+    "Hard Rock",
+];
+
+/// MPEG audio bitrates in kbit/s: [MPEG1 L1, MPEG1 L2, MPEG1 L3, MPEG2 L1, MPEG2 L2/L3].
+const BITRATES: [[u32; 15]; 5] = [
+    [
+        0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+    ],
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+    ],
+    [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ],
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+    ],
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+
+/// One frame of ID3v2 tag.
+pub struct Id3Frame {
+    pub id: String,
+    pub size: usize,
+    /// Decoded value for text, comment, URL and picture frames.
+    pub value: Option<String>,
+}
+
+pub struct Id3Tag {
+    pub version: (u8, u8),
+    pub size: usize,
+    pub frames: Vec<Id3Frame>,
+}
+
+/// Header of single MPEG audio frame.
+#[derive(Clone, Copy)]
+pub struct MpegHeader {
+    /// 1 for MPEG-1, 2 for MPEG-2, 25 for MPEG-2.5.
+    pub version: u8,
+    pub layer: u8,
+    pub bitrate: u32,
+    pub sample_rate: u32,
+    pub channel_mode: u8,
+    pub padding: bool,
+    pub protected: bool,
+}
+
+impl MpegHeader {
+    /// Parses 4-byte frame header, returns None if it is not valid.
+    pub fn parse(bytes: &[u8]) -> Option<MpegHeader> {
+        if bytes.len() < 4 || bytes[0] != 0xFF || bytes[1] & 0xE0 != 0xE0 {
+            return None;
+        }
+        let version = match (bytes[1] >> 3) & 0x03 {
+            0 => 25,
+            2 => 2,
+            3 => 1,
+            _ => return None,
+        };
+        let layer = match (bytes[1] >> 1) & 0x03 {
+            1 => 3,
+            2 => 2,
+            3 => 1,
+            _ => return None,
+        };
+        let bitrate_index = (bytes[2] >> 4) as usize;
+        let rate_index = ((bytes[2] >> 2) & 0x03) as usize;
+        if bitrate_index == 0 || bitrate_index == 15 || rate_index == 3 {
+            return None;
+        }
+        let table = match (version, layer) {
+            (1, l) => l as usize - 1,
+            (_, 1) => 3,
+            _ => 4,
+        };
+        let sample_rate = [44100, 48000, 32000][rate_index]
+            / match version {
+                1 => 1,
+                2 => 2,
+                _ => 4,
+            };
+        Some(MpegHeader {
+            version,
+            layer,
+            bitrate: BITRATES[table][bitrate_index],
+            sample_rate,
+            channel_mode: bytes[3] >> 6,
+            padding: (bytes[2] >> 1) & 0x01 == 1,
+            protected: bytes[1] & 0x01 == 0,
+        })
+    }
+
+    pub fn samples_per_frame(&self) -> u32 {
+        match (self.layer, self.version) {
+            (1, _) => 384,
+            (3, 1) | (2, _) => 1152,
+            _ => 576,
+        }
+    }
+
+    pub fn frame_length(&self) -> usize {
+        let padding = self.padding as u32;
+        if self.layer == 1 {
+            return ((12 * self.bitrate * 1000 / self.sample_rate + padding) * 4) as usize;
+        }
+        let bytes = self.samples_per_frame() / 8;
+        (bytes * self.bitrate * 1000 / self.sample_rate + padding) as usize
+    }
+
+    /// Offset of Xing/Info header from frame start (after side information).
+    fn xing_offset(&self) -> usize {
+        match (self.version, self.channel_mode == 3) {
+            (1, false) => 36,
+            (1, true) => 21,
+            (_, false) => 21,
+            (_, true) => 13,
+        }
+    }
+}
+
+/// Summary of MPEG audio stream.
+pub struct MpegStream {
+    pub first_offset: usize,
+    pub header: MpegHeader,
+    pub frames: u64,
+    pub min_bitrate: u32,
+    pub max_bitrate: u32,
+    pub audio_bytes: u64,
+    /// "Xing", "Info" or "VBRI" with frame count from that header.
+    pub vbr_header: Option<(&'static str, Option<u32>)>,
+    pub encoder: Option<String>,
+}
+
+impl MpegStream {
+    pub fn duration(&self) -> f64 {
+        let frames = match self.vbr_header {
+            Some((_, Some(frames))) => frames as u64,
+            _ => self.frames,
+        };
+        frames as f64 * self.header.samples_per_frame() as f64 / self.header.sample_rate as f64
+    }
+}
+
+/// FLAC STREAMINFO block.
+pub struct StreamInfo {
+    pub min_block: u16,
+    pub max_block: u16,
+    pub sample_rate: u32,
+    pub channels: u8,
+    pub bits_per_sample: u8,
+    pub total_samples: u64,
+    pub md5: [u8; 16],
+}
+
+/// Vorbis comment block used by FLAC, Ogg Vorbis and Opus.
+pub struct VorbisComment {
+    pub vendor: String,
+    pub comments: Vec<(String, String)>,
+}
+
+/// Logical bitstream of Ogg file.
+pub struct OggStream {
+    pub serial: u32,
+    pub codec: String,
+    pub pages: u64,
+    pub last_granule: i64,
+    pub sample_rate: u32,
+    pub pre_skip: u32,
+    pub details: Option<String>,
+    pub comment: Option<VorbisComment>,
+    /// First packets (identification, comment) used to describe the stream.
+    packets: Vec<Vec<u8>>,
+    partial: Vec<u8>,
+}
+
+fn syncsafe(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0, |acc, b| (acc << 7) | (*b & 0x7F) as usize)
+}
+
+fn be_uint(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |acc, b| (acc << 8) | *b as usize)
+}
+
+fn le_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn le_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+/// Decodes ID3v2 text with given encoding byte, multiple values are joined with "; ".
+fn decode_id3_text(encoding: u8, data: &[u8]) -> String {
+    let text = match encoding {
+        1 | 2 => {
+            let mut units: Vec<u16> = data
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            if encoding == 1 && units.first() == Some(&0xFFFE) {
+                units = units.iter().map(|u| u.swap_bytes()).collect();
+            }
+            // BOMs can appear before every value in multi-value frames.
+            units.retain(|u| *u != 0xFEFF);
+            String::from_utf16_lossy(&units)
+        }
+        3 => String::from_utf8_lossy(data).to_string(),
+        _ => data.iter().map(|b| *b as char).collect(),
+    };
+    text.trim_end_matches('\0').replace('\0', "; ")
+}
+
+/// Splits ID3v2 payload at terminator of given encoding, returns (before, after).
+fn split_id3_terminated(encoding: u8, data: &[u8]) -> (&[u8], &[u8]) {
+    if encoding == 1 || encoding == 2 {
+        let mut i = 0;
+        while i + 1 < data.len() {
+            if data[i] == 0 && data[i + 1] == 0 {
+                return (&data[..i], &data[i + 2..]);
+            }
+            i += 2;
+        }
+    } else if let Some(i) = data.iter().position(|b| *b == 0) {
+        return (&data[..i], &data[i + 1..]);
+    }
+    (data, &[])
+}
+
+fn decode_id3_frame(id: &str, payload: &[u8]) -> Option<String> {
+    let (&encoding, rest) = payload.split_first()?;
+    match id {
+        "TXXX" | "WXXX" => {
+            let (description, value) = split_id3_terminated(encoding, rest);
+            let value = if id == "WXXX" {
+                decode_id3_text(0, value)
+            } else {
+                decode_id3_text(encoding, value)
+            };
+            Some(format!(
+                "{}={}",
+                decode_id3_text(encoding, description),
+                value
+            ))
+        }
+        "COMM" | "USLT" => {
+            let (description, text) = split_id3_terminated(encoding, rest.get(3..)?);
+            let description = decode_id3_text(encoding, description);
+            let text = decode_id3_text(encoding, text);
+            if description.is_empty() {
+                Some(text)
+            } else {
+                Some(format!("({}) {}", description, text))
+            }
+        }
+        "APIC" => {
+            let (mime, rest) = split_id3_terminated(0, rest);
+            let (&picture_type, rest) = rest.split_first()?;
+            let (_, picture) = split_id3_terminated(encoding, rest);
+            Some(format!(
+                "{}, type {}, {} bytes",
+                decode_id3_text(0, mime),
+                picture_type,
+                picture.len()
+            ))
+        }
+        _ if id.starts_with('T') => Some(decode_id3_text(encoding, rest)),
+        _ if id.starts_with('W') => Some(decode_id3_text(0, payload)),
+        _ => None,
+    }
+}
+
+/// Removes ID3v2 unsynchronisation (0xFF 0x00 -> 0xFF).
+fn remove_unsynchronisation(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        out.push(data[i]);
+        if data[i] == 0xFF && data.get(i + 1) == Some(&0x00) {
+            i += 1;
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Reads ID3v2 tag at the start of data.
+pub fn get_id3v2(data: &[u8]) -> Option<Id3Tag> {
+    if data.len() < 10 || &data[..3] != b"ID3" {
+        return None;
+    }
+    let version = (data[3], data[4]);
+    let flags = data[5];
+    let size = syncsafe(&data[6..10]);
+    let end = (10 + size).min(data.len());
+    let body = if flags & 0x80 != 0 && version.0 < 4 {
+        remove_unsynchronisation(&data[10..end])
+    } else {
+        data[10..end].to_vec()
+    };
+
+    let mut pos = 0;
+    if flags & 0x40 != 0 && body.len() >= 4 {
+        pos = match version.0 {
+            3 => be_uint(&body[..4]) + 4,
+            _ => syncsafe(&body[..4]),
+        };
+    }
+
+    let (id_length, header_length) = if version.0 == 2 { (3, 6) } else { (4, 10) };
+    let mut frames = Vec::new();
+    while pos + header_length <= body.len() {
+        let id_bytes = &body[pos..pos + id_length];
+        if id_bytes[0] == 0 || !id_bytes.iter().all(|b| b.is_ascii_alphanumeric()) {
+            // Padding
+            break;
+        }
+        let id = String::from_utf8_lossy(id_bytes).to_string();
+        let frame_size = match version.0 {
+            2 => be_uint(&body[pos + 3..pos + 6]),
+            3 => be_uint(&body[pos + 4..pos + 8]),
+            _ => syncsafe(&body[pos + 4..pos + 8]),
+        };
+        let start = pos + header_length;
+        let payload = &body[start.min(body.len())..(start + frame_size).min(body.len())];
+        let format_flags = if version.0 == 2 { 0 } else { body[pos + 9] };
+        let mut payload = payload.to_vec();
+        if version.0 == 4 && format_flags & 0x01 != 0 && payload.len() >= 4 {
+            // Data length indicator
+            payload.drain(..4);
+        }
+        if version.0 == 4 && format_flags & 0x02 != 0 {
+            payload = remove_unsynchronisation(&payload);
+        }
+        // Compressed, encrypted or grouped frames can't be decoded as is.
+        let is_encoded = match version.0 {
+            3 => format_flags & 0xE0 != 0,
+            4 => format_flags & 0x4C != 0,
+            _ => false,
+        };
+        let value = if is_encoded {
+            None
+        } else {
+            decode_id3_frame(&id, &payload)
+        };
+        frames.push(Id3Frame {
+            id,
+            size: frame_size,
+            value,
+        });
+        pos = start + frame_size;
+    }
+    Some(Id3Tag {
+        version,
+        size: size + 10,
+        frames,
+    })
+}
+
+/// Gets human name of common ID3v2 frame ids.
+pub fn id3_frame_name(id: &str) -> Option<&'static str> {
+    Some(match id {
+        "TIT2" | "TT2" => "Title",
+        "TPE1" | "TP1" => "Artist",
+        "TALB" | "TAL" => "Album",
+        "TPE2" | "TP2" => "Album artist",
+        "TRCK" | "TRK" => "Track",
+        "TPOS" | "TPA" => "Disc",
+        "TYER" | "TYE" => "Year",
+        "TDRC" => "Recording date",
+        "TCON" | "TCO" => "Genre",
+        "TCOM" | "TCM" => "Composer",
+        "TENC" | "TEN" => "Encoded by",
+        "TSSE" | "TSS" => "Encoder settings",
+        "TLEN" | "TLE" => "Length (ms)",
+        "TBPM" | "TBP" => "BPM",
+        "TCOP" | "TCR" => "Copyright",
+        "TPUB" | "TPB" => "Publisher",
+        "COMM" | "COM" => "Comment",
+        "USLT" | "ULT" => "Lyrics",
+        "APIC" | "PIC" => "Cover art",
+        "TXXX" | "TXX" => "User text",
+        "WXXX" | "WXX" => "User URL",
+        _ => return None,
+    })
+}
+
+/// Reads ID3v1 tag at the end of data, returns (field, value) pairs.
+pub fn get_id3v1(data: &[u8]) -> Option<Vec<(&'static str, String)>> {
+    if data.len() < 128 {
+        return None;
+    }
+    let tag = &data[data.len() - 128..];
+    if &tag[..3] != b"TAG" {
+        return None;
+    }
+    let text = |bytes: &[u8]| -> String {
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        bytes[..end]
+            .iter()
+            .map(|b| *b as char)
+            .collect::<String>()
+            .trim()
+            .to_string()
+    };
+    let mut fields = vec![
+        ("Title", text(&tag[3..33])),
+        ("Artist", text(&tag[33..63])),
+        ("Album", text(&tag[63..93])),
+        ("Year", text(&tag[93..97])),
+    ];
+    // ID3v1.1 stores track number in last byte of comment.
+    if tag[125] == 0 && tag[126] != 0 {
+        fields.push(("Comment", text(&tag[97..125])));
+        fields.push(("Track", tag[126].to_string()));
+    } else {
+        fields.push(("Comment", text(&tag[97..127])));
+    }
+    let genre = ID3V1_GENRES
+        .get(tag[127] as usize)
+        .map(|g| g.to_string())
+        .unwrap_or(format!("unknown ({})", tag[127]));
+    fields.push(("Genre", genre));
+    fields.retain(|(_, value)| !value.is_empty());
+    Some(fields)
+}
+
+/// Finds first MPEG frame which is followed by another valid frame.
+fn find_mpeg_sync(data: &[u8], from: usize) -> Option<(usize, MpegHeader)> {
+    let mut pos = from;
+    while pos + 4 <= data.len() && !is_timed_out() {
+        if let Some(header) = MpegHeader::parse(&data[pos..]) {
+            let next = pos + header.frame_length();
+            if next + 4 > data.len() || MpegHeader::parse(&data[next..]).is_some() {
+                return Some((pos, header));
+            }
+        }
+        pos += 1;
+    }
+    None
+}
+
+/// Walks all MPEG audio frames after `from` offset.
+pub fn get_mpeg_stream(data: &[u8], from: usize) -> Option<MpegStream> {
+    let (first_offset, header) = find_mpeg_sync(data, from)?;
+    let mut stream = MpegStream {
+        first_offset,
+        header,
+        frames: 0,
+        min_bitrate: u32::MAX,
+        max_bitrate: 0,
+        audio_bytes: 0,
+        vbr_header: None,
+        encoder: None,
+    };
+
+    let frame = &data[first_offset..];
+    let xing = header.xing_offset();
+    // Frame can end right after tag in truncated files, then there are no flags.
+    if let (Some(tag), Some(flags)) = (frame.get(xing..xing + 4), frame.get(xing + 4..xing + 8)) {
+        if tag == b"Xing" || tag == b"Info" {
+            let flags = be_uint(flags);
+            let frames = (flags & 1 != 0)
+                .then(|| frame.get(xing + 8..xing + 12).map(be_uint))
+                .flatten()
+                .map(|f| f as u32);
+            let name = if tag == b"Xing" { "Xing" } else { "Info" };
+            stream.vbr_header = Some((name, frames));
+            // LAME extension goes after optional frames, bytes, TOC and quality fields.
+            let mut lame = xing + 8;
+            for (bit, size) in [(1, 4), (2, 4), (4, 100), (8, 4)] {
+                if flags & bit != 0 {
+                    lame += size;
+                }
+            }
+            if let Some(encoder) = frame.get(lame..lame + 9) {
+                if encoder.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+                    stream.encoder = Some(String::from_utf8_lossy(encoder).trim().to_string());
+                }
+            }
+        }
+    }
+    if frame.get(36..40) == Some(b"VBRI") {
+        let frames = frame.get(50..54).map(|f| be_uint(f) as u32);
+        stream.vbr_header = Some(("VBRI", frames));
+    }
+
+    let mut pos = first_offset;
+    while let Some(current) = data.get(pos..).and_then(MpegHeader::parse) {
+        let length = current.frame_length();
+        if length == 0 || is_timed_out() {
+            break;
+        }
+        stream.frames += 1;
+        stream.audio_bytes += length as u64;
+        stream.min_bitrate = stream.min_bitrate.min(current.bitrate);
+        stream.max_bitrate = stream.max_bitrate.max(current.bitrate);
+        pos += length;
+    }
+    // Xing/Info frame is a valid frame without audio.
+    if stream.vbr_header.is_some() && stream.frames > 0 {
+        stream.frames -= 1;
+    }
+    Some(stream)
+}
+
+/// Reads vorbis comment (little-endian lengths), used by FLAC, Vorbis and Opus.
+pub fn get_vorbis_comment(data: &[u8]) -> Option<VorbisComment> {
+    let vendor_length = le_u32(data, 0)? as usize;
+    let vendor = String::from_utf8_lossy(data.get(4..4 + vendor_length)?).to_string();
+    let mut pos = 4 + vendor_length;
+    let count = le_u32(data, pos)?;
+    pos += 4;
+    let mut comments = Vec::new();
+    for _ in 0..count {
+        let Some(length) = le_u32(data, pos) else {
+            break;
+        };
+        let Some(comment) = data.get(pos + 4..pos + 4 + length as usize) else {
+            break;
+        };
+        let comment = String::from_utf8_lossy(comment);
+        let (key, value) = comment.split_once('=').unwrap_or((&comment, ""));
+        comments.push((key.to_uppercase(), value.to_string()));
+        pos += 4 + length as usize;
+    }
+    Some(VorbisComment { vendor, comments })
+}
+
+fn get_stream_info(block: &[u8]) -> Option<StreamInfo> {
+    if block.len() < 34 {
+        return None;
+    }
+    let packed = be_uint(&block[10..18]) as u64;
+    Some(StreamInfo {
+        min_block: be_uint(&block[0..2]) as u16,
+        max_block: be_uint(&block[2..4]) as u16,
+        sample_rate: (packed >> 44) as u32,
+        channels: ((packed >> 41) & 0x07) as u8 + 1,
+        bits_per_sample: ((packed >> 36) & 0x1F) as u8 + 1,
+        total_samples: packed & 0x0F_FFFF_FFFF,
+        md5: block[18..34].try_into().ok()?,
+    })
+}
+
+fn get_flac_picture(block: &[u8]) -> Option<String> {
+    let picture_type = be_uint(block.get(0..4)?);
+    let mime_length = be_uint(block.get(4..8)?);
+    let mime = String::from_utf8_lossy(block.get(8..8 + mime_length)?).to_string();
+    let mut pos = 8 + mime_length;
+    let description_length = be_uint(block.get(pos..pos + 4)?);
+    pos += 4 + description_length;
+    let width = be_uint(block.get(pos..pos + 4)?);
+    let height = be_uint(block.get(pos + 4..pos + 8)?);
+    let size = be_uint(block.get(pos + 16..pos + 20)?);
+    Some(format!(
+        "{}, type {}, {}x{}, {} bytes",
+        mime, picture_type, width, height, size
+    ))
+}
+
+fn print_vorbis_comment(comment: &VorbisComment, report: &mut Report) {
+    writeln!(report, "# Vendor: {}", comment.vendor);
+    for (key, value) in comment.comments.iter() {
+        if key == "METADATA_BLOCK_PICTURE" {
+            writeln!(report, "{}: {} bytes (base64)", key, value.len());
+        } else {
+            writeln!(report, "{}: {}", key, value);
+        }
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0);
+    let minutes = (total / 60.0).floor();
+    format!("{}:{:06.3}", minutes, total - minutes * 60.0)
+}
+
+fn print_flac_info(data: &[u8], start: usize, report: &mut Report) {
+    writeln!(report, "# Container: FLAC");
+    let mut pos = start + 4;
+    loop {
+        let Some(header) = data.get(pos..pos + 4) else {
+            writeln!(report, "Error: metadata block header is truncated");
+            return;
+        };
+        let is_last = header[0] & 0x80 != 0;
+        let block_type = header[0] & 0x7F;
+        let length = be_uint(&header[1..4]);
+        let block = &data[(pos + 4).min(data.len())..(pos + 4 + length).min(data.len())];
+        let name = match block_type {
+            0 => "STREAMINFO",
+            1 => "PADDING",
+            2 => "APPLICATION",
+            3 => "SEEKTABLE",
+            4 => "VORBIS_COMMENT",
+            5 => "CUESHEET",
+            6 => "PICTURE",
+            _ => "unknown",
+        };
+        writeln!(report, "# Metadata block: {} ({} bytes)", name, length);
+        match block_type {
+            0 => {
+                if let Some(info) = get_stream_info(block) {
+                    writeln!(
+                        report,
+                        "# Stream: {} Hz, {} channel(s), {} bit",
+                        info.sample_rate, info.channels, info.bits_per_sample
+                    );
+                    writeln!(
+                        report,
+                        "# Block size: {}-{} samples",
+                        info.min_block, info.max_block
+                    );
+                    if info.sample_rate > 0 && info.total_samples > 0 {
+                        writeln!(
+                            report,
+                            "# Duration: {} ({} samples)",
+                            format_duration(info.total_samples as f64 / info.sample_rate as f64),
+                            info.total_samples
+                        );
+                    }
+                    let md5: String = info.md5.iter().map(|b| format!("{:02x}", b)).collect();
+                    writeln!(report, "# Audio MD5: {}", md5);
+                }
+            }
+            4 => {
+                if let Some(comment) = get_vorbis_comment(block) {
+                    print_vorbis_comment(&comment, report);
+                }
+            }
+            6 => {
+                if let Some(picture) = get_flac_picture(block) {
+                    writeln!(report, "# Picture: {}", picture);
+                }
+            }
+            _ => (),
+        }
+        pos += 4 + length;
+        if is_last {
+            break;
+        }
+    }
+}
+
+/// Gets name of WAVE format tag.
+fn wave_format_name(tag: u16) -> &'static str {
+    match tag {
+        0x0001 => "PCM",
+        0x0002 => "Microsoft ADPCM",
+        0x0003 => "IEEE float",
+        0x0006 => "A-law",
+        0x0007 => "mu-law",
+        0x0011 => "IMA ADPCM",
+        0x0050 => "MPEG",
+        0x0055 => "MPEG Layer III",
+        0x2000 => "AC-3",
+        0xFFFE => "Extensible",
+        _ => "unknown",
+    }
+}
+
+fn riff_info_name(id: &str) -> &str {
+    match id {
+        "INAM" => "Title",
+        "IART" => "Artist",
+        "IPRD" => "Album",
+        "ICRD" => "Date",
+        "IGNR" => "Genre",
+        "ICMT" => "Comment",
+        "ISFT" => "Software",
+        "ITRK" | "IPRT" => "Track",
+        "ICOP" => "Copyright",
+        "IENG" => "Engineer",
+        _ => id,
+    }
+}
+
+fn print_wav_info(args: &Arguments, data: &[u8], report: &mut Report) {
+    writeln!(report, "# Container: RIFF WAVE");
+    let mut byte_rate = 0;
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let id = String::from_utf8_lossy(&data[pos..pos + 4]).to_string();
+        let size = le_u32(data, pos + 4).unwrap_or(0) as usize;
+        let chunk = &data[(pos + 8).min(data.len())..(pos + 8 + size).min(data.len())];
+        writeln!(
+            report,
+            "# Chunk \"{}\" at offset {} ({})",
+            id,
+            pos,
+            format_size(args, size as u64)
+        );
+        match id.as_str() {
+            "fmt " if chunk.len() >= 16 => {
+                let mut tag = le_u16(chunk, 0).unwrap_or(0);
+                // Extensible format stores real format in first bytes of sub-format GUID.
+                if tag == 0xFFFE {
+                    tag = le_u16(chunk, 24).unwrap_or(tag);
+                }
+                byte_rate = le_u32(chunk, 8).unwrap_or(0);
+                writeln!(
+                    report,
+                    "# Format: {} (0x{:04X})",
+                    wave_format_name(tag),
+                    tag
+                );
+                writeln!(
+                    report,
+                    "# Stream: {} Hz, {} channel(s), {} bit, {} bytes/s, block align {}",
+                    le_u32(chunk, 4).unwrap_or(0),
+                    le_u16(chunk, 2).unwrap_or(0),
+                    le_u16(chunk, 14).unwrap_or(0),
+                    byte_rate,
+                    le_u16(chunk, 12).unwrap_or(0)
+                );
+            }
+            "data" if byte_rate > 0 => {
+                writeln!(
+                    report,
+                    "# Duration: {}",
+                    format_duration(size as f64 / byte_rate as f64)
+                );
+            }
+            "LIST" if chunk.starts_with(b"INFO") => {
+                let mut sub = 4;
+                while sub + 8 <= chunk.len() {
+                    let sub_id = String::from_utf8_lossy(&chunk[sub..sub + 4]).to_string();
+                    let sub_size = le_u32(chunk, sub + 4).unwrap_or(0) as usize;
+                    let value = &chunk[sub + 8..(sub + 8 + sub_size).min(chunk.len())];
+                    let value = String::from_utf8_lossy(value);
+                    writeln!(
+                        report,
+                        "{}: {}",
+                        riff_info_name(&sub_id),
+                        value.trim_end_matches('\0')
+                    );
+                    sub += 8 + sub_size + sub_size % 2;
+                }
+            }
+            _ => (),
+        }
+        // Chunks are padded to even size.
+        pos += 8 + size + size % 2;
+    }
+}
+
+/// Walks Ogg pages, groups them by logical stream and reads codec headers.
+pub fn get_ogg_streams(data: &[u8]) -> Result<(Vec<OggStream>, u64), Error> {
+    let mut streams: Vec<OggStream> = Vec::new();
+    let mut pages = 0;
+    let mut pos = 0;
+    while pos + 27 <= data.len() && !is_timed_out() {
+        if &data[pos..pos + 4] != b"OggS" {
+            if pages == 0 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "missing OggS capture pattern",
+                ));
+            }
+            // Lost page sync, report what was read so far.
+            break;
+        }
+        let granule = i64::from_le_bytes(data[pos + 6..pos + 14].try_into().unwrap());
+        let serial = le_u32(data, pos + 14).unwrap_or(0);
+        let segment_count = data[pos + 26] as usize;
+        let Some(lacing) = data.get(pos + 27..pos + 27 + segment_count) else {
+            break;
+        };
+        let mut body = pos + 27 + segment_count;
+        pages += 1;
+
+        let index = match streams.iter().position(|s| s.serial == serial) {
+            Some(index) => index,
+            None => {
+                streams.push(OggStream {
+                    serial,
+                    codec: "unknown".to_string(),
+                    pages: 0,
+                    last_granule: 0,
+                    sample_rate: 0,
+                    pre_skip: 0,
+                    details: None,
+                    comment: None,
+                    packets: Vec::new(),
+                    partial: Vec::new(),
+                });
+                streams.len() - 1
+            }
+        };
+        let stream = &mut streams[index];
+        stream.pages += 1;
+        if granule >= 0 {
+            stream.last_granule = granule;
+        }
+        for value in lacing {
+            let segment = &data[body.min(data.len())..(body + *value as usize).min(data.len())];
+            body += *value as usize;
+            // Only first two packets (identification and comment headers) are needed.
+            if stream.packets.len() >= 2 {
+                continue;
+            }
+            stream.partial.extend_from_slice(segment);
+            if *value < 255 {
+                let packet = std::mem::take(&mut stream.partial);
+                stream.packets.push(packet);
+            }
+        }
+        pos = body;
+    }
+
+    for stream in streams.iter_mut() {
+        let Some(head) = stream.packets.first() else {
+            continue;
+        };
+        if head.starts_with(b"OpusHead") && head.len() >= 19 {
+            stream.codec = "Opus".to_string();
+            stream.sample_rate = 48000;
+            stream.pre_skip = le_u16(head, 10).unwrap_or(0) as u32;
+            stream.details = Some(format!(
+                "version {}, {} channel(s), pre-skip {}, input rate {} Hz, gain {} dB, mapping family {}",
+                head[8],
+                head[9],
+                stream.pre_skip,
+                le_u32(head, 12).unwrap_or(0),
+                i16::from_le_bytes([head[16], head[17]]) as f32 / 256.0,
+                head[18]
+            ));
+            stream.comment = stream
+                .packets
+                .get(1)
+                .filter(|p| p.starts_with(b"OpusTags"))
+                .and_then(|p| get_vorbis_comment(&p[8..]));
+        } else if head.starts_with(b"\x01vorbis") && head.len() >= 28 {
+            stream.codec = "Vorbis".to_string();
+            stream.sample_rate = le_u32(head, 12).unwrap_or(0);
+            let bitrate = |o: usize| i32::from_le_bytes(head[o..o + 4].try_into().unwrap());
+            stream.details = Some(format!(
+                "{} channel(s), {} Hz, bitrate max/nominal/min {}/{}/{}",
+                head[11],
+                stream.sample_rate,
+                bitrate(16),
+                bitrate(20),
+                bitrate(24)
+            ));
+            stream.comment = stream
+                .packets
+                .get(1)
+                .filter(|p| p.starts_with(b"\x03vorbis"))
+                .and_then(|p| get_vorbis_comment(&p[7..]));
+        } else if head.starts_with(b"\x7FFLAC") && head.len() >= 51 {
+            stream.codec = "FLAC".to_string();
+            if let Some(info) = get_stream_info(&head[17..]) {
+                stream.sample_rate = info.sample_rate;
+                stream.details = Some(format!(
+                    "{} channel(s), {} Hz, {} bit",
+                    info.channels, info.sample_rate, info.bits_per_sample
+                ));
+            }
+        } else if head.starts_with(b"Speex   ") {
+            stream.codec = "Speex".to_string();
+            stream.sample_rate = le_u32(head, 36).unwrap_or(0);
+        } else if head.starts_with(b"\x80theora") {
+            stream.codec = "Theora".to_string();
+        }
+    }
+    Ok((streams, pages))
+}
+
+fn print_ogg_info(data: &[u8], report: &mut Report) {
+    writeln!(report, "# Container: Ogg");
+    let (streams, pages) = match get_ogg_streams(data) {
+        Ok(result) => result,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
+    writeln!(report, "# Pages: {}", pages);
+    writeln!(report, "# Logical streams: {}", streams.len());
+    for stream in streams.iter() {
+        writeln!(
+            report,
+            "# Stream 0x{:08X}: {} ({} pages)",
+            stream.serial, stream.codec, stream.pages
+        );
+        if let Some(details) = &stream.details {
+            writeln!(report, "# Header: {}", details);
+        }
+        if stream.sample_rate > 0 {
+            let samples = (stream.last_granule - stream.pre_skip as i64).max(0);
+            writeln!(
+                report,
+                "# Duration: {}",
+                format_duration(samples as f64 / stream.sample_rate as f64)
+            );
+        }
+        if let Some(comment) = &stream.comment {
+            print_vorbis_comment(comment, report);
+        }
+    }
+}
+
+fn print_mpeg_info(args: &Arguments, data: &[u8], from: usize, report: &mut Report) {
+    let Some(stream) = get_mpeg_stream(data, from) else {
+        writeln!(report, "# No MPEG audio frames found");
+        return;
+    };
+    let header = stream.header;
+    let version = match header.version {
+        25 => "2.5".to_string(),
+        v => v.to_string(),
+    };
+    let channel_mode =
+        ["Stereo", "Joint stereo", "Dual channel", "Mono"][header.channel_mode as usize];
+    writeln!(
+        report,
+        "# Container: MPEG-{} Audio Layer {}",
+        version,
+        "I".repeat(header.layer as usize)
+    );
+    writeln!(report, "# First frame at offset: {}", stream.first_offset);
+    writeln!(
+        report,
+        "# Stream: {} Hz, {}, CRC {}",
+        header.sample_rate,
+        channel_mode,
+        if header.protected {
+            "present"
+        } else {
+            "absent"
+        }
+    );
+    match stream.vbr_header {
+        Some((name, frames)) => {
+            write!(report, "# {} header", name);
+            if let Some(frames) = frames {
+                write!(report, ": {} frames", frames);
+            }
+            writeln!(report);
+        }
+        None => writeln!(report, "# No Xing/VBRI header"),
+    }
+    if let Some(encoder) = &stream.encoder {
+        writeln!(report, "# Encoder: {}", encoder);
+    }
+    let is_vbr = stream.min_bitrate != stream.max_bitrate;
+    let duration = stream.duration();
+    if is_vbr {
+        let average = if duration > 0.0 {
+            (stream.audio_bytes as f64 * 8.0 / duration / 1000.0).round() as u32
+        } else {
+            0
+        };
+        writeln!(
+            report,
+            "# Bitrate: VBR {}-{} kbit/s (average {} kbit/s)",
+            stream.min_bitrate, stream.max_bitrate, average
+        );
+    } else {
+        writeln!(report, "# Bitrate: CBR {} kbit/s", header.bitrate);
+    }
+    writeln!(report, "# Frames: {}", stream.frames);
+    writeln!(
+        report,
+        "# Audio data: {}",
+        format_size(args, stream.audio_bytes)
+    );
+    writeln!(report, "# Duration: {}", format_duration(duration));
+}
+
+/// Gets specified audio info about file.
+pub fn get_audio_info(args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## Audio information");
+    let data = input.bytes();
+
+    let mut start = 0;
+    if let Some(tag) = get_id3v2(data) {
+        writeln!(
+            report,
+            "# ID3v2.{}.{} tag: {} frame(s), size {}",
+            tag.version.0,
+            tag.version.1,
+            tag.frames.len(),
+            format_size(args, tag.size as u64)
+        );
+        for frame in tag.frames.iter() {
+            match (id3_frame_name(&frame.id), &frame.value) {
+                (Some(name), Some(value)) => writeln!(report, "# {}: {}", name, value),
+                (None, Some(value)) => writeln!(report, "{}: {}", frame.id, value),
+                (_, None) => writeln!(report, "{}: {} bytes", frame.id, frame.size),
+            }
+        }
+        start = tag.size;
+    }
+    if let Some(fields) = get_id3v1(data) {
+        writeln!(report, "# ID3v1 tag:");
+        for (field, value) in fields.iter() {
+            writeln!(report, "{}: {}", field, value);
+        }
+    }
+
+    let body = &data[start.min(data.len())..];
+    if body.starts_with(b"fLaC") {
+        print_flac_info(data, start, report);
+    } else if body.starts_with(b"RIFF") && body.get(8..12) == Some(b"WAVE") {
+        print_wav_info(args, body, report);
+    } else if body.starts_with(b"OggS") {
+        print_ogg_info(body, report);
+    } else {
+        print_mpeg_info(args, data, start, report);
+    }
+}
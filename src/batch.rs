@@ -0,0 +1,104 @@
+use crate::report::collect_report;
+use crate::Arguments;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// Adds regular files in directory and its subdirectories, sorted by name.
+/// Symbolic links to directories are not followed, so loops are not possible.
+fn walk_dir(dir: &Path, files: &mut Vec<PathBuf>) {
+    let mut entries: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(e) => {
+            eprintln!("Could not read directory {}: {}", dir.display(), e);
+            return;
+        }
+    };
+    entries.sort();
+    for path in entries {
+        let is_link = fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink());
+        if path.is_dir() && !is_link {
+            walk_dir(&path, files);
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+}
+
+/// Gets files to analyze, directories are replaced by files in them.
+pub fn collect_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            walk_dir(path, &mut files);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files
+}
+
+/// Prints report of file under its name, one-line output and checksum lines are printed as they are.
+fn print_report(args: &Arguments, path: &Path, report: &str) {
+    if args.line.is_some() || args.hash_sums {
+        print!("{}", report);
+        return;
+    }
+    println!("## File: {}", path.display());
+    print!("{}", report);
+    println!();
+}
+
+/// Analyzes files by pool of threads, reports are printed whole,
+/// in order of files or as soon as they are done.
+pub fn analyze_files(args: &Arguments, files: &[PathBuf], jobs: usize, ordered: bool) {
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    // Progress is shown only to user watching terminal.
+    let progress = std::io::stderr().is_terminal();
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(files.len()) {
+            let (next, sender) = (&next, sender.clone());
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = files.get(index) else {
+                    break;
+                };
+                let mut file_args = args.clone();
+                file_args.file_path = path.clone();
+                let report = collect_report(|report| crate::get_info(&file_args, report));
+                if sender.send((index, report)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut next_printed = 0;
+        for (done, (index, report)) in receiver.into_iter().enumerate() {
+            if progress {
+                eprint!("\r\x1b[K");
+            }
+            if ordered {
+                pending.insert(index, report);
+                while let Some(report) = pending.remove(&next_printed) {
+                    print_report(args, &files[next_printed], &report);
+                    next_printed += 1;
+                }
+            } else {
+                print_report(args, &files[index], &report);
+            }
+            if progress {
+                eprint!("[{}/{}] {}", done + 1, files.len(), files[index].display());
+            }
+        }
+        if progress {
+            eprint!("\r\x1b[K");
+        }
+    });
+}
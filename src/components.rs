@@ -1,11 +1,16 @@
+use crate::datetime::TimeZone;
+use crate::limits::Limits;
 use serde::{Deserialize, Deserializer};
 use serde_derive::Deserialize;
-use std::{path::PathBuf, str::FromStr};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 use strum_macros::{EnumString, IntoStaticStr};
 
 /// The difference with file-format lib is that we need as much accurate representation of types as possible,
 /// whereas in file_format categories used for quick choice of formats needed for application (why do you need other file's backups for regular app?).
-#[derive(Debug, PartialEq, EnumString, IntoStaticStr)]
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, IntoStaticStr)]
 #[strum(ascii_case_insensitive)]
 pub enum Category {
     /// Files and directories stored in a single, possibly compressed, archive .
@@ -106,13 +111,180 @@ pub struct ExtensionVec {
     pub extensions: Vec<Extension>,
 }
 
+/// Hash algorithms which can be computed for file and archive entries.
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+    Blake3,
+    Crc32,
+    Ssdeep,
+    Tlsh,
+}
+
+/// Sections of report, selected by `--sections`.
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum Section {
+    /// Name, size, times, owner and attributes of file.
+    General,
+    /// Detected type: extension, its category, name and media type.
+    Type,
+    /// Description of type, other media types and further reading.
+    Extension,
+    /// Listing of archives like zip, rar or jar.
+    Archive,
+    /// Metadata of other formats, like tags of audio or headers of executables.
+    Metadata,
+    /// Hashes of file and archive entries, algorithms are selected by `--hash`.
+    Hashes,
+    /// Entropy of file and archive entries.
+    Entropy,
+}
+
+/// Sections printed when none are selected.
+pub const DEFAULT_SECTIONS: [Section; 5] = [
+    Section::General,
+    Section::Type,
+    Section::Archive,
+    Section::Metadata,
+    Section::Hashes,
+];
+
+impl Section {
+    /// Gets keys of fields noted by section, metadata of formats and entropy have none.
+    pub fn keys(self) -> &'static [&'static str] {
+        match self {
+            Section::General => &[
+                "name", "source", "size", "inode", "device", "links", "mode", "owner", "group",
+                "created", "modified", "accessed", "changed",
+            ],
+            Section::Type => &["extension", "category", "name", "mime"],
+            Section::Extension => &["mimes", "description", "further-reading"],
+            Section::Archive => &["compressed-size"],
+            Section::Hashes => &[
+                "md5", "sha1", "sha256", "sha512", "blake3", "crc32", "ssdeep", "tlsh",
+            ],
+            Section::Metadata | Section::Entropy => &[],
+        }
+    }
+
+    /// Gets section of field named like `general.size`, unknown keys are rejected.
+    pub fn of_field(field: &str) -> Option<Section> {
+        let (section, key) = field.split_once('.')?;
+        Section::from_str(section)
+            .ok()
+            .filter(|section| section.keys().contains(&key))
+    }
+
+    /// Checks field named like `general.size`, error tells which fields are known.
+    pub fn check_field(field: &str) -> Result<Section, String> {
+        if let Some(section) = Section::of_field(field) {
+            return Ok(section);
+        }
+        let section = field
+            .split_once('.')
+            .and_then(|(section, _)| Section::from_str(section).ok());
+        match section {
+            Some(section) if section.keys().is_empty() => {
+                Err(format!("section {} has no fields", <&str>::from(section)))
+            }
+            Some(section) => Err(format!(
+                "unknown field, fields of {} are {}",
+                <&str>::from(section),
+                section.keys().join(", ")
+            )),
+            None => Err("expected section.field, e.g. general.size".to_string()),
+        }
+    }
+}
+
+/// One-line output like `file`, printed instead of report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineMode {
+    /// Description of type, like `Android Package Kit (ZIP container)`.
+    Description,
+    /// Media type, like `application/zip`.
+    MimeType,
+    /// Charset, like `utf-8`, or `binary`.
+    MimeEncoding,
+    /// Media type with charset, like `text/plain; charset=utf-8`.
+    Mime,
+}
+
+/// Units of printed sizes.
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum SizeUnits {
+    /// `1234567`, plain number for scripts.
+    Plain,
+    /// `1,234,567 B`
+    Bytes,
+    /// `1.2 MB`, powers of 1000.
+    Si,
+    /// `1.2 MiB`, powers of 1024.
+    Iec,
+}
+
+/// Styles of printed timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum TimeFormat {
+    /// `2024-01-02 03:04:05 CET`
+    Default,
+    /// `2024-01-02T03:04:05+01:00`, `Z` for UTC.
+    Rfc3339,
+    /// `2024-01-02T03:04:05+00:00`, offset is always numeric.
+    Iso8601,
+    /// Seconds since 1970-01-01 UTC.
+    Unix,
+    /// `3 days ago`
+    Relative,
+}
+
+#[derive(Clone)]
 pub struct Arguments {
     pub file_path: PathBuf,
     pub extensions_path: PathBuf,
     pub gui: bool,
     pub is_debug: bool,
-    pub is_human: bool,
-    pub only_general: bool,
-    pub ignore_general: bool,
-    pub extension_info: bool,
+    pub units: SizeUnits,
+    /// Decimal places of sizes in SI and IEC units.
+    pub size_precision: usize,
+    pub sections: Vec<Section>,
+    /// Fields from `--fields`, like `general.size`, only their values are printed.
+    pub fields: Vec<String>,
+    /// One-line output from `--brief`, `--mime-type` or `--mime-encoding`.
+    pub line: Option<LineMode>,
+    /// Name of file is left out of one-line output.
+    pub brief: bool,
+    pub entropy_block_size: usize,
+    pub hashes: Vec<HashAlgorithm>,
+    pub hash_sums: bool,
+    pub time_format: TimeFormat,
+    pub time_zone: TimeZone,
+    /// Name hint from `--name`, used for extension lookups.
+    pub name: Option<String>,
+    /// Original path of stdin (`-`) or pipe, `file_path` then leads to its spooled copy.
+    pub stream_path: Option<PathBuf>,
+    /// Limits of analysis against hostile inputs.
+    pub limits: Limits,
+}
+
+impl Arguments {
+    pub fn has_section(&self, section: Section) -> bool {
+        self.sections.contains(&section)
+    }
+
+    /// Path whose name and extension are used for lookups, name hint overrides real path.
+    pub fn name_path(&self) -> &Path {
+        match (&self.name, &self.stream_path) {
+            (Some(name), _) => Path::new(name),
+            (None, Some(stream_path)) => stream_path,
+            (None, None) => &self.file_path,
+        }
+    }
 }
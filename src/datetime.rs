@@ -0,0 +1,289 @@
+use crate::components::TimeFormat;
+use crate::Arguments;
+use std::io::{Error, ErrorKind};
+use std::time::{SystemTime, UNIX_EPOCH};
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// Time with nanosecond precision, seconds are counted from Unix epoch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timestamp {
+    pub seconds: i64,
+    pub nanoseconds: u32,
+}
+
+impl Timestamp {
+    pub fn from_seconds(seconds: i64) -> Timestamp {
+        Timestamp {
+            seconds,
+            nanoseconds: 0,
+        }
+    }
+
+    pub fn from_system_time(time: SystemTime) -> Timestamp {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => Timestamp {
+                seconds: duration.as_secs() as i64,
+                nanoseconds: duration.subsec_nanos(),
+            },
+            // Before epoch, nanoseconds still count forward.
+            Err(e) => {
+                let duration = e.duration();
+                let carry = (duration.subsec_nanos() > 0) as i64;
+                Timestamp {
+                    seconds: -(duration.as_secs() as i64) - carry,
+                    nanoseconds: (1_000_000_000 - duration.subsec_nanos()) % 1_000_000_000,
+                }
+            }
+        }
+    }
+
+    pub fn now() -> Timestamp {
+        Timestamp::from_system_time(SystemTime::now())
+    }
+}
+
+/// Time zone of printed timestamps, rules are read from system tz database by `jiff`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeZone {
+    pub name: String,
+    zone: jiff::tz::TimeZone,
+}
+
+fn invalid(e: jiff::Error) -> Error {
+    Error::new(ErrorKind::InvalidData, e.to_string())
+}
+
+impl TimeZone {
+    pub fn utc() -> TimeZone {
+        TimeZone {
+            name: "UTC".to_string(),
+            zone: jiff::tz::TimeZone::UTC,
+        }
+    }
+
+    /// Gets zone by `--tz` value: `local`, `UTC` or IANA name.
+    pub fn from_name(name: &str) -> Result<TimeZone, Error> {
+        if name.eq_ignore_ascii_case("utc") {
+            Ok(TimeZone::utc())
+        } else if name.eq_ignore_ascii_case("local") {
+            // Zone of system from `TZ` variable or `/etc/localtime`.
+            let zone = jiff::tz::TimeZone::try_system().map_err(invalid)?;
+            Ok(TimeZone {
+                name: zone.iana_name().unwrap_or("local").to_string(),
+                zone,
+            })
+        } else {
+            let zone = jiff::tz::db().get(name).map_err(|_| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("time zone {} was not found", name),
+                )
+            })?;
+            Ok(TimeZone {
+                name: name.to_string(),
+                zone,
+            })
+        }
+    }
+}
+
+fn format_fraction(nanoseconds: u32) -> String {
+    if nanoseconds == 0 {
+        String::new()
+    } else {
+        format!(".{:09}", nanoseconds)
+    }
+}
+
+fn format_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.unsigned_abs();
+    let mut text = format!("{}{:02}:{:02}", sign, offset / 3600, offset / 60 % 60);
+    if !offset.is_multiple_of(60) {
+        text += &format!(":{:02}", offset % 60);
+    }
+    text
+}
+
+fn format_date_time(time: PrimitiveDateTime, separator: char) -> String {
+    format!(
+        "{:0>4}-{:0>2}-{:0>2}{}{:0>2}:{:0>2}:{:0>2}",
+        time.year(),
+        time.month() as u8,
+        time.day(),
+        separator,
+        time.hour(),
+        time.minute(),
+        time.second()
+    )
+}
+
+/// Formats difference to now, like `3 days ago` or `in 2 hours`.
+pub fn format_relative(seconds: i64, now: i64) -> String {
+    let difference = now - seconds;
+    let distance = difference.unsigned_abs();
+    if distance < 10 {
+        return "just now".to_string();
+    }
+    let (count, unit) = [
+        (365 * SECONDS_PER_DAY as u64, "year"),
+        (30 * SECONDS_PER_DAY as u64, "month"),
+        (SECONDS_PER_DAY as u64, "day"),
+        (3600, "hour"),
+        (60, "minute"),
+        (1, "second"),
+    ]
+    .into_iter()
+    .find(|(length, _)| distance >= *length)
+    .map(|(length, unit)| (distance / length, unit))
+    .unwrap_or((distance, "second"));
+    let plural = if count == 1 { "" } else { "s" };
+    if difference > 0 {
+        format!("{} {}{} ago", count, unit, plural)
+    } else {
+        format!("in {} {}{}", count, unit, plural)
+    }
+}
+
+/// Formats point in time in chosen style and time zone.
+pub fn format_timestamp(args: &Arguments, timestamp: Timestamp) -> String {
+    let fraction = format_fraction(timestamp.nanoseconds);
+    match args.time_format {
+        TimeFormat::Unix => return format!("{}{}", timestamp.seconds, fraction),
+        TimeFormat::Relative => {
+            return format_relative(timestamp.seconds, Timestamp::now().seconds)
+        }
+        _ => (),
+    }
+    // Times out of range of `jiff` use the nearest one.
+    let instant =
+        jiff::Timestamp::from_second(timestamp.seconds).unwrap_or(if timestamp.seconds < 0 {
+            jiff::Timestamp::MIN
+        } else {
+            jiff::Timestamp::MAX
+        });
+    let info = args.time_zone.zone.to_offset_info(instant);
+    let offset = info.offset().seconds();
+    let local = UtcOffset::from_whole_seconds(offset)
+        .ok()
+        .and_then(|offset| {
+            let time = OffsetDateTime::from_unix_timestamp(timestamp.seconds).ok()?;
+            time.checked_to_offset(offset)
+        });
+    let Some(local) = local else {
+        return format!("{} seconds since epoch", timestamp.seconds);
+    };
+    let local = PrimitiveDateTime::new(local.date(), local.time());
+    match args.time_format {
+        TimeFormat::Rfc3339 if offset == 0 => {
+            format!("{}{}Z", format_date_time(local, 'T'), fraction)
+        }
+        TimeFormat::Rfc3339 | TimeFormat::Iso8601 => format!(
+            "{}{}{}",
+            format_date_time(local, 'T'),
+            fraction,
+            format_offset(offset)
+        ),
+        _ => format!(
+            "{}{} {}",
+            format_date_time(local, ' '),
+            fraction,
+            info.abbreviation()
+        ),
+    }
+}
+
+/// Formats date and time with known offset, converted to chosen time zone.
+pub fn format_offset_datetime(args: &Arguments, time: OffsetDateTime) -> String {
+    format_timestamp(
+        args,
+        Timestamp {
+            seconds: time.unix_timestamp(),
+            nanoseconds: time.nanosecond(),
+        },
+    )
+}
+
+/// Formats wall clock time without time zone, like DOS times in zip files or EXIF dates.
+/// It can't be converted to other zone, so it's only labeled.
+pub fn format_local_datetime(args: &Arguments, time: PrimitiveDateTime) -> String {
+    let separator = match args.time_format {
+        TimeFormat::Rfc3339 | TimeFormat::Iso8601 => 'T',
+        _ => ' ',
+    };
+    format!(
+        "{} (local time, zone unknown)",
+        format_date_time(time, separator)
+    )
+}
+
+/// Parses ISO 8601 date and time like `2024-01-02T03:04:05.5+01:00`, offset is optional.
+pub fn parse_iso8601(text: &str) -> Option<(PrimitiveDateTime, Option<UtcOffset>)> {
+    let text = text.trim();
+    let number = |from: usize, length: usize| -> Option<u32> {
+        let part = text.get(from..from + length)?;
+        part.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| part.parse().ok())?
+    };
+    let date = Date::from_calendar_date(
+        number(0, 4)? as i32,
+        Month::try_from(number(5, 2)? as u8).ok()?,
+        number(8, 2)? as u8,
+    )
+    .ok()?;
+    if text.get(4..5)? != "-" || text.get(7..8)? != "-" {
+        return None;
+    }
+    if text.len() == 10 {
+        return Some((date.midnight(), None));
+    }
+    if !matches!(text.get(10..11)?, "T" | " ") {
+        return None;
+    }
+    let hour = number(11, 2)? as u8;
+    let minute = number(14, 2)? as u8;
+    let (second, mut rest) = match text.get(16..17) {
+        Some(":") => (number(17, 2)? as u8, &text[19..]),
+        _ => (0, &text[16..]),
+    };
+    // Fraction of second is dropped.
+    if let Some(fraction) = rest.strip_prefix(['.', ',']) {
+        rest = fraction.trim_start_matches(|c: char| c.is_ascii_digit());
+    }
+    let time = time::Time::from_hms(hour, minute, second).ok()?;
+    let offset = match rest {
+        "" => None,
+        "Z" | "z" => Some(UtcOffset::UTC),
+        _ => {
+            // Offsets are written like +01:00, +0100 or +01.
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let digits: String = rest[1..].chars().filter(|c| *c != ':').collect();
+            if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let hours: i32 = digits[..2].parse().ok()?;
+            let minutes: i32 = digits.get(2..).unwrap_or("0").parse().unwrap_or(0);
+            Some(UtcOffset::from_whole_seconds(sign * (hours * 3600 + minutes * 60)).ok()?)
+        }
+    };
+    Some((PrimitiveDateTime::new(date, time), offset))
+}
+
+/// Formats parsed date and time, converted if its offset is known and labeled otherwise.
+pub fn format_parsed_datetime(
+    args: &Arguments,
+    time: PrimitiveDateTime,
+    offset: Option<UtcOffset>,
+) -> String {
+    match offset {
+        Some(offset) => format_offset_datetime(args, time.assume_offset(offset)),
+        None => format_local_datetime(args, time),
+    }
+}
@@ -0,0 +1,792 @@
+use crate::input::Input;
+use crate::report::Report;
+use crate::{format_size, Arguments};
+use std::io::{Error, ErrorKind};
+
+pub const ELF_MAGIC: [u8; 4] = [0x7F, b'E', b'L', b'F'];
+
+const ET_REL: u16 = 1;
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+const ET_CORE: u16 = 4;
+
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const PT_INTERP: u32 = 3;
+const PT_NOTE: u32 = 4;
+const PT_GNU_STACK: u32 = 0x6474E551;
+const PT_GNU_RELRO: u32 = 0x6474E552;
+const PF_X: u32 = 1;
+
+const SHT_SYMTAB: u32 = 2;
+const SHT_DYNAMIC: u32 = 6;
+const SHT_NOTE: u32 = 7;
+const SHT_DYNSYM: u32 = 11;
+
+const DT_NEEDED: u64 = 1;
+const DT_STRTAB: u64 = 5;
+const DT_SONAME: u64 = 14;
+const DT_RPATH: u64 = 15;
+const DT_BIND_NOW: u64 = 24;
+const DT_RUNPATH: u64 = 29;
+const DT_FLAGS: u64 = 30;
+const DT_FLAGS_1: u64 = 0x6FFFFFFB;
+const DF_BIND_NOW: u64 = 0x8;
+const DF_1_NOW: u64 = 0x1;
+const DF_1_PIE: u64 = 0x08000000;
+
+pub struct Segment {
+    pub kind: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub vaddr: u64,
+    pub file_size: u64,
+    pub memory_size: u64,
+    pub align: u64,
+}
+
+pub struct Section {
+    pub name: String,
+    pub kind: u32,
+    pub flags: u64,
+    pub addr: u64,
+    pub offset: u64,
+    pub size: u64,
+    pub link: u32,
+    pub align: u64,
+    pub entry_size: u64,
+}
+
+#[derive(Default)]
+pub struct ElfInfo {
+    pub is_64: bool,
+    pub big_endian: bool,
+    pub os_abi: u8,
+    pub abi_version: u8,
+    pub kind: u16,
+    pub machine: u16,
+    pub entry: u64,
+    pub flags: u32,
+    pub segments: Vec<Segment>,
+    pub sections: Vec<Section>,
+    pub interpreter: Option<String>,
+    pub needed: Vec<String>,
+    pub soname: Option<String>,
+    pub rpath: Option<String>,
+    pub runpath: Option<String>,
+    pub bind_now: bool,
+    pub pie_flag: bool,
+    pub build_id: Option<String>,
+    /// (OS, version) from GNU ABI tag note.
+    pub abi_tag: Option<(&'static str, String)>,
+    pub debug_link: Option<String>,
+    pub symbols: Option<usize>,
+    pub dynamic_symbols: Option<usize>,
+    pub has_canary: bool,
+    /// Names of _FORTIFY_SOURCE checked functions, like __memcpy_chk.
+    pub fortified: Vec<String>,
+}
+
+/// Reads integers with endianness and word size of file.
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+    is_64: bool,
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, offset: u64) -> Option<[u8; N]> {
+        let offset = usize::try_from(offset).ok()?;
+        self.data
+            .get(offset..offset.checked_add(N)?)?
+            .try_into()
+            .ok()
+    }
+
+    fn u16(&self, offset: u64) -> Option<u16> {
+        let bytes = self.bytes(offset)?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: u64) -> Option<u32> {
+        let bytes = self.bytes(offset)?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn u64(&self, offset: u64) -> Option<u64> {
+        let bytes = self.bytes(offset)?;
+        Some(if self.big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        })
+    }
+
+    /// Reads address sized word (4 bytes in ELF32, 8 in ELF64).
+    fn word(&self, offset: u64) -> Option<u64> {
+        if self.is_64 {
+            self.u64(offset)
+        } else {
+            self.u32(offset).map(|w| w as u64)
+        }
+    }
+
+    /// Reads null terminated string.
+    fn string(&self, offset: u64) -> Option<String> {
+        let offset = usize::try_from(offset).ok()?;
+        let rest = self.data.get(offset..)?;
+        let end = rest.iter().position(|b| *b == 0).unwrap_or(rest.len());
+        Some(String::from_utf8_lossy(&rest[..end]).to_string())
+    }
+
+    fn range(&self, offset: u64, size: u64) -> Option<&[u8]> {
+        let start = usize::try_from(offset).ok()?;
+        let end = start.checked_add(usize::try_from(size).ok()?)?;
+        self.data.get(start..end)
+    }
+}
+
+pub fn machine_name(machine: u16) -> &'static str {
+    match machine {
+        0 => "none",
+        2 => "SPARC",
+        3 => "Intel 80386",
+        4 => "Motorola 68000",
+        8 => "MIPS",
+        18 => "SPARC32PLUS",
+        20 => "PowerPC",
+        21 => "PowerPC64",
+        22 => "IBM S/390",
+        40 => "ARM",
+        42 => "SuperH",
+        43 => "SPARC V9",
+        50 => "IA-64",
+        62 => "AMD x86-64",
+        83 => "Atmel AVR",
+        94 => "Xtensa",
+        183 => "AArch64",
+        243 => "RISC-V",
+        247 => "Linux BPF",
+        258 => "LoongArch",
+        _ => "unknown",
+    }
+}
+
+pub fn os_abi_name(os_abi: u8) -> &'static str {
+    match os_abi {
+        0 => "UNIX System V",
+        1 => "HP-UX",
+        2 => "NetBSD",
+        3 => "Linux (GNU)",
+        6 => "Solaris",
+        7 => "AIX",
+        8 => "IRIX",
+        9 => "FreeBSD",
+        10 => "Tru64",
+        12 => "OpenBSD",
+        97 => "ARM EABI",
+        255 => "Standalone",
+        _ => "unknown",
+    }
+}
+
+pub fn type_name(kind: u16) -> &'static str {
+    match kind {
+        ET_REL => "relocatable object",
+        ET_EXEC => "executable",
+        ET_DYN => "shared object",
+        ET_CORE => "core dump",
+        _ => "unknown",
+    }
+}
+
+fn segment_type_name(kind: u32) -> String {
+    match kind {
+        0 => "NULL",
+        PT_LOAD => "LOAD",
+        PT_DYNAMIC => "DYNAMIC",
+        PT_INTERP => "INTERP",
+        PT_NOTE => "NOTE",
+        5 => "SHLIB",
+        6 => "PHDR",
+        7 => "TLS",
+        0x6474E550 => "GNU_EH_FRAME",
+        PT_GNU_STACK => "GNU_STACK",
+        PT_GNU_RELRO => "GNU_RELRO",
+        0x6474E553 => "GNU_PROPERTY",
+        _ => return format!("0x{:x}", kind),
+    }
+    .to_string()
+}
+
+fn section_type_name(kind: u32) -> String {
+    match kind {
+        0 => "NULL",
+        1 => "PROGBITS",
+        SHT_SYMTAB => "SYMTAB",
+        3 => "STRTAB",
+        4 => "RELA",
+        5 => "HASH",
+        SHT_DYNAMIC => "DYNAMIC",
+        SHT_NOTE => "NOTE",
+        8 => "NOBITS",
+        9 => "REL",
+        SHT_DYNSYM => "DYNSYM",
+        14 => "INIT_ARRAY",
+        15 => "FINI_ARRAY",
+        16 => "PREINIT_ARRAY",
+        17 => "GROUP",
+        18 => "SYMTAB_SHNDX",
+        0x6FFFFFF5 => "GNU_ATTRIBUTES",
+        0x6FFFFFF6 => "GNU_HASH",
+        0x6FFFFFFD => "VERDEF",
+        0x6FFFFFFE => "VERNEED",
+        0x6FFFFFFF => "VERSYM",
+        _ => return format!("0x{:x}", kind),
+    }
+    .to_string()
+}
+
+/// Formats segment flags like "R-X".
+fn segment_flags(flags: u32) -> String {
+    format!(
+        "{}{}{}",
+        if flags & 4 != 0 { 'R' } else { '-' },
+        if flags & 2 != 0 { 'W' } else { '-' },
+        if flags & PF_X != 0 { 'X' } else { '-' }
+    )
+}
+
+fn get_segment(reader: &Reader, at: u64) -> Option<Segment> {
+    // Offsets of fields below don't overflow.
+    at.checked_add(64)?;
+    if reader.is_64 {
+        Some(Segment {
+            kind: reader.u32(at)?,
+            flags: reader.u32(at + 4)?,
+            offset: reader.u64(at + 8)?,
+            vaddr: reader.u64(at + 16)?,
+            file_size: reader.u64(at + 32)?,
+            memory_size: reader.u64(at + 40)?,
+            align: reader.u64(at + 48)?,
+        })
+    } else {
+        Some(Segment {
+            kind: reader.u32(at)?,
+            offset: reader.u32(at + 4)? as u64,
+            vaddr: reader.u32(at + 8)? as u64,
+            file_size: reader.u32(at + 16)? as u64,
+            memory_size: reader.u32(at + 20)? as u64,
+            flags: reader.u32(at + 24)?,
+            align: reader.u32(at + 28)? as u64,
+        })
+    }
+}
+
+fn get_section(reader: &Reader, at: u64) -> Option<Section> {
+    at.checked_add(64)?;
+    if reader.is_64 {
+        Some(Section {
+            name: reader.u32(at)?.to_string(),
+            kind: reader.u32(at + 4)?,
+            flags: reader.u64(at + 8)?,
+            addr: reader.u64(at + 16)?,
+            offset: reader.u64(at + 24)?,
+            size: reader.u64(at + 32)?,
+            link: reader.u32(at + 40)?,
+            align: reader.u64(at + 48)?,
+            entry_size: reader.u64(at + 56)?,
+        })
+    } else {
+        Some(Section {
+            name: reader.u32(at)?.to_string(),
+            kind: reader.u32(at + 4)?,
+            flags: reader.u32(at + 8)? as u64,
+            addr: reader.u32(at + 12)? as u64,
+            offset: reader.u32(at + 16)? as u64,
+            size: reader.u32(at + 20)? as u64,
+            link: reader.u32(at + 24)?,
+            align: reader.u32(at + 32)? as u64,
+            entry_size: reader.u32(at + 36)? as u64,
+        })
+    }
+}
+
+/// Reads section headers and resolves their names from section name string table.
+fn get_sections(
+    reader: &Reader,
+    offset: u64,
+    mut count: u64,
+    entry_size: u16,
+    mut names_index: u64,
+) -> Vec<Section> {
+    if offset == 0 {
+        return Vec::new();
+    }
+    // With too many sections real values are stored in the first section header.
+    if let Some(first) = get_section(reader, offset) {
+        if count == 0 {
+            count = first.size;
+        }
+        if names_index == 0xFFFF {
+            names_index = first.link as u64;
+        }
+    }
+    let mut sections = Vec::new();
+    for i in 0..count.min(65536) {
+        match get_section(reader, offset.saturating_add(i * entry_size as u64)) {
+            Some(section) => sections.push(section),
+            None => break,
+        }
+    }
+    let names_offset = sections.get(names_index as usize).map(|s| s.offset);
+    for section in sections.iter_mut() {
+        let index: u64 = section.name.parse().unwrap_or(0);
+        section.name = names_offset
+            .and_then(|o| reader.string(o.checked_add(index)?))
+            .unwrap_or_default();
+    }
+    sections
+}
+
+/// Converts virtual address to file offset using LOAD segments.
+fn vaddr_to_offset(segments: &[Segment], vaddr: u64) -> Option<u64> {
+    segments
+        .iter()
+        .filter(|s| s.kind == PT_LOAD)
+        .find(|s| vaddr >= s.vaddr && vaddr - s.vaddr < s.file_size)
+        .and_then(|s| s.offset.checked_add(vaddr - s.vaddr))
+}
+
+fn get_dynamic(reader: &Reader, info: &mut ElfInfo) {
+    let dynamic_section = info.sections.iter().find(|s| s.kind == SHT_DYNAMIC);
+    let (offset, size) = match info.segments.iter().find(|s| s.kind == PT_DYNAMIC) {
+        Some(segment) => (segment.offset, segment.file_size),
+        None => match dynamic_section {
+            Some(section) => (section.offset, section.size),
+            None => return,
+        },
+    };
+    let entry_size = if reader.is_64 { 16 } else { 8 };
+    let half = entry_size / 2;
+    let mut entries = Vec::new();
+    for i in 0..size / entry_size {
+        let at = offset.saturating_add(i * entry_size);
+        let (Some(tag), Some(value)) = (reader.word(at), reader.word(at.saturating_add(half)))
+        else {
+            break;
+        };
+        if tag == 0 {
+            break;
+        }
+        entries.push((tag, value));
+    }
+
+    // String table from section link is reliable, DT_STRTAB holds only its address.
+    let string_table = dynamic_section
+        .and_then(|s| info.sections.get(s.link as usize))
+        .map(|s| s.offset)
+        .or_else(|| {
+            let address = entries.iter().find(|(tag, _)| *tag == DT_STRTAB)?.1;
+            vaddr_to_offset(&info.segments, address)
+        });
+    let string = |value: u64| string_table.and_then(|o| reader.string(o.checked_add(value)?));
+    for (tag, value) in entries {
+        match tag {
+            DT_NEEDED => info.needed.extend(string(value)),
+            DT_SONAME => info.soname = string(value),
+            DT_RPATH => info.rpath = string(value),
+            DT_RUNPATH => info.runpath = string(value),
+            DT_BIND_NOW => info.bind_now = true,
+            DT_FLAGS if value & DF_BIND_NOW != 0 => info.bind_now = true,
+            DT_FLAGS_1 => {
+                info.bind_now |= value & DF_1_NOW != 0;
+                info.pie_flag = value & DF_1_PIE != 0;
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Reads GNU notes (build id and ABI tag) from note sections, or segments if there are no sections.
+fn get_notes(reader: &Reader, info: &mut ElfInfo) {
+    let mut areas: Vec<(u64, u64, u64)> = info
+        .sections
+        .iter()
+        .filter(|s| s.kind == SHT_NOTE)
+        .map(|s| (s.offset, s.size, s.align))
+        .collect();
+    if areas.is_empty() {
+        areas = info
+            .segments
+            .iter()
+            .filter(|s| s.kind == PT_NOTE)
+            .map(|s| (s.offset, s.file_size, s.align))
+            .collect();
+    }
+    for (offset, size, align) in areas {
+        let align = if align == 8 { 8 } else { 4 };
+        let end = offset.saturating_add(size);
+        let mut at = offset;
+        while at
+            .checked_add(12)
+            .is_some_and(|header_end| header_end <= end)
+        {
+            let (Some(name_size), Some(desc_size), Some(kind)) =
+                (reader.u32(at), reader.u32(at + 4), reader.u32(at + 8))
+            else {
+                break;
+            };
+            let name_at = at + 12;
+            let Some(desc_at) = name_at.checked_add((name_size as u64).next_multiple_of(align))
+            else {
+                break;
+            };
+            let name = reader.range(name_at, name_size as u64).unwrap_or_default();
+            let desc = reader.range(desc_at, desc_size as u64).unwrap_or_default();
+            if name == b"GNU\0" {
+                if kind == 3 {
+                    info.build_id = Some(desc.iter().map(|b| format!("{:02x}", b)).collect());
+                } else if kind == 1 && desc.len() >= 16 {
+                    let word = |i: u64| reader.u32(desc_at + i * 4).unwrap_or(0);
+                    let os = match word(0) {
+                        0 => "Linux",
+                        1 => "GNU Hurd",
+                        2 => "Solaris",
+                        3 => "FreeBSD",
+                        _ => "unknown",
+                    };
+                    info.abi_tag = Some((os, format!("{}.{}.{}", word(1), word(2), word(3))));
+                }
+            }
+            let Some(next) = desc_at.checked_add((desc_size as u64).next_multiple_of(align)) else {
+                break;
+            };
+            at = next;
+        }
+    }
+}
+
+/// Counts symbols and looks for stack protector and fortify functions.
+fn get_symbols(reader: &Reader, info: &mut ElfInfo) {
+    let entry_size = if reader.is_64 { 24 } else { 16 };
+    for section in info.sections.iter() {
+        if section.kind != SHT_SYMTAB && section.kind != SHT_DYNSYM {
+            continue;
+        }
+        let count = section.size / entry_size;
+        if section.kind == SHT_SYMTAB {
+            info.symbols = Some(count as usize);
+        } else {
+            info.dynamic_symbols = Some(count as usize);
+        }
+        let Some(names) = info.sections.get(section.link as usize) else {
+            continue;
+        };
+        for i in 0..count.min(1 << 20) {
+            let Some(name) = reader
+                .u32(section.offset.saturating_add(i * entry_size))
+                .and_then(|n| reader.string(names.offset.checked_add(n as u64)?))
+            else {
+                break;
+            };
+            if name.starts_with("__stack_chk_") || name == "__intel_security_cookie" {
+                info.has_canary = true;
+            } else if name.starts_with("__")
+                && name.ends_with("_chk")
+                && !info.fortified.contains(&name)
+            {
+                info.fortified.push(name);
+            }
+        }
+    }
+    info.fortified.sort();
+}
+
+/// Gets ELF headers, dynamic linking info, notes and symbols.
+pub fn get_elf_structure(data: &[u8]) -> Result<ElfInfo, Error> {
+    if !data.starts_with(&ELF_MAGIC) || data.len() < 52 {
+        return Err(Error::new(ErrorKind::InvalidData, "not an ELF file"));
+    }
+    let is_64 = match data[4] {
+        1 => false,
+        2 => true,
+        _ => return Err(Error::new(ErrorKind::InvalidData, "unknown ELF class")),
+    };
+    let big_endian = match data[5] {
+        1 => false,
+        2 => true,
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "unknown ELF data encoding",
+            ))
+        }
+    };
+    let reader = Reader {
+        data,
+        big_endian,
+        is_64,
+    };
+    let truncated = || Error::new(ErrorKind::UnexpectedEof, "truncated ELF header");
+    let mut info = ElfInfo {
+        is_64,
+        big_endian,
+        os_abi: data[7],
+        abi_version: data[8],
+        kind: reader.u16(16).ok_or_else(truncated)?,
+        machine: reader.u16(18).ok_or_else(truncated)?,
+        entry: reader.word(24).ok_or_else(truncated)?,
+        ..Default::default()
+    };
+    // Offsets of remaining header fields differ only by size of three address fields.
+    let (program_offset, section_offset, rest) = if is_64 {
+        (reader.u64(32), reader.u64(40), 48)
+    } else {
+        (
+            reader.u32(28).map(|o| o as u64),
+            reader.u32(32).map(|o| o as u64),
+            36,
+        )
+    };
+    let (program_offset, section_offset) = (
+        program_offset.ok_or_else(truncated)?,
+        section_offset.ok_or_else(truncated)?,
+    );
+    info.flags = reader.u32(rest).ok_or_else(truncated)?;
+    let program_entry_size = reader.u16(rest + 6).ok_or_else(truncated)?;
+    let program_count = reader.u16(rest + 8).ok_or_else(truncated)?;
+    let section_entry_size = reader.u16(rest + 10).ok_or_else(truncated)?;
+    let section_count = reader.u16(rest + 12).ok_or_else(truncated)?;
+    let names_index = reader.u16(rest + 14).ok_or_else(truncated)?;
+
+    if program_offset != 0 {
+        info.segments = (0..program_count as u64)
+            .map_while(|i| {
+                get_segment(
+                    &reader,
+                    program_offset.saturating_add(i * program_entry_size as u64),
+                )
+            })
+            .collect();
+    }
+    info.sections = get_sections(
+        &reader,
+        section_offset,
+        section_count as u64,
+        section_entry_size,
+        names_index as u64,
+    );
+
+    info.interpreter = info
+        .segments
+        .iter()
+        .find(|s| s.kind == PT_INTERP)
+        .and_then(|s| reader.string(s.offset));
+    if info.kind != ET_CORE {
+        get_dynamic(&reader, &mut info);
+        get_symbols(&reader, &mut info);
+    }
+    get_notes(&reader, &mut info);
+    if let Some(section) = info.sections.iter().find(|s| s.name == ".gnu_debuglink") {
+        info.debug_link = reader.string(section.offset);
+    }
+    Ok(info)
+}
+
+/// Gets more specific extension of extensionless or versioned (libfoo.so.1) ELF file.
+pub fn get_elf_extension(args: &Arguments, input: &Input) -> Option<String> {
+    let header = input.bytes().get(..18)?;
+    if !header.starts_with(&ELF_MAGIC) {
+        return None;
+    }
+    let kind = if header[5] == 2 {
+        u16::from_be_bytes([header[16], header[17]])
+    } else {
+        u16::from_le_bytes([header[16], header[17]])
+    };
+    let file_name = args.name_path().file_name()?.to_string_lossy();
+    Some(
+        match kind {
+            ET_REL => "o",
+            ET_DYN if file_name.contains(".so.") => "so",
+            _ => "elf",
+        }
+        .to_string(),
+    )
+}
+
+/// Gets specified ELF info about file.
+pub fn get_elf_info(args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## ELF information");
+    let data = input.bytes();
+    let info = match get_elf_structure(data) {
+        Ok(info) => info,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
+
+    writeln!(
+        report,
+        "# Class: {}, {} endian",
+        if info.is_64 { "ELF64" } else { "ELF32" },
+        if info.big_endian { "big" } else { "little" }
+    );
+    writeln!(
+        report,
+        "# OS/ABI: {} (ABI version {})",
+        os_abi_name(info.os_abi),
+        info.abi_version
+    );
+    writeln!(report, "# Type: {}", type_name(info.kind));
+    writeln!(
+        report,
+        "# Machine: {} ({})",
+        machine_name(info.machine),
+        info.machine
+    );
+    if info.flags != 0 {
+        writeln!(report, "# Flags: 0x{:x}", info.flags);
+    }
+    writeln!(report, "# Entry point: 0x{:x}", info.entry);
+    if let Some(interpreter) = &info.interpreter {
+        writeln!(report, "# Interpreter: {}", interpreter);
+    }
+    if let Some(soname) = &info.soname {
+        writeln!(report, "# SONAME: {}", soname);
+    }
+    if let Some(rpath) = &info.rpath {
+        writeln!(report, "# RPATH: {}", rpath);
+    }
+    if let Some(runpath) = &info.runpath {
+        writeln!(report, "# RUNPATH: {}", runpath);
+    }
+    if !info.needed.is_empty() {
+        writeln!(report, "# Needed libraries: {}", info.needed.len());
+        for library in info.needed.iter() {
+            writeln!(report, "{}", library);
+        }
+    }
+    if let Some(build_id) = &info.build_id {
+        writeln!(report, "# Build ID: {}", build_id);
+    }
+    if let Some((os, version)) = &info.abi_tag {
+        writeln!(report, "# ABI tag: {} {}", os, version);
+    }
+
+    if info.kind != ET_CORE && info.kind != ET_REL {
+        print_hardening(&info, report);
+    }
+    if info.kind != ET_CORE {
+        write!(report, "# Symbol table: ");
+        match info.symbols {
+            Some(count) => writeln!(report, "{} symbol(s)", count),
+            None => writeln!(report, "none (stripped)"),
+        }
+        if let Some(count) = info.dynamic_symbols {
+            writeln!(report, "# Dynamic symbols: {}", count);
+        }
+        let debug = info
+            .sections
+            .iter()
+            .any(|s| s.name.starts_with(".debug_") || s.name.starts_with(".zdebug_"));
+        writeln!(report, "# Debug info: {}", if debug { "yes" } else { "no" });
+        if let Some(debug_link) = &info.debug_link {
+            writeln!(report, "# Separate debug file: {}", debug_link);
+        }
+    }
+
+    writeln!(report, "# Program headers: {}", info.segments.len());
+    for segment in info.segments.iter() {
+        writeln!(
+            report,
+            "{} {} offset 0x{:x}, address 0x{:x}, file size {}, memory size {}",
+            segment_type_name(segment.kind),
+            segment_flags(segment.flags),
+            segment.offset,
+            segment.vaddr,
+            format_size(args, segment.file_size),
+            format_size(args, segment.memory_size)
+        );
+    }
+    writeln!(report, "# Sections: {}", info.sections.len());
+    for (i, section) in info.sections.iter().enumerate().skip(1) {
+        write!(
+            report,
+            "[{}] {} {}, size {}",
+            i,
+            if section.name.is_empty() {
+                "?"
+            } else {
+                &section.name
+            },
+            section_type_name(section.kind),
+            format_size(args, section.size)
+        );
+        if section.addr != 0 {
+            write!(report, ", address 0x{:x}", section.addr);
+        }
+        // SHF_WRITE, SHF_ALLOC, SHF_EXECINSTR
+        if section.flags & 0x7 != 0 {
+            write!(
+                report,
+                ", flags {}{}{}",
+                if section.flags & 2 != 0 { "A" } else { "" },
+                if section.flags & 1 != 0 { "W" } else { "" },
+                if section.flags & 4 != 0 { "X" } else { "" }
+            );
+        }
+        if section.entry_size != 0 {
+            write!(report, ", entry size {}", section.entry_size);
+        }
+        writeln!(report);
+    }
+}
+
+fn print_hardening(info: &ElfInfo, report: &mut Report) {
+    let pie = match info.kind {
+        ET_DYN if info.interpreter.is_some() || info.pie_flag => "yes",
+        ET_DYN => "no (shared library)",
+        _ => "no",
+    };
+    writeln!(report, "# PIE: {}", pie);
+    let relro = match (
+        info.segments.iter().any(|s| s.kind == PT_GNU_RELRO),
+        info.bind_now,
+    ) {
+        (true, true) => "full",
+        (true, false) => "partial",
+        (false, _) => "none",
+    };
+    writeln!(report, "# RELRO: {}", relro);
+    let nx = match info.segments.iter().find(|s| s.kind == PT_GNU_STACK) {
+        Some(stack) if stack.flags & PF_X != 0 => "disabled (executable stack)",
+        Some(_) => "enabled",
+        None => "unknown (no GNU_STACK header)",
+    };
+    writeln!(report, "# NX stack: {}", nx);
+    let canary = match (info.symbols.or(info.dynamic_symbols), info.has_canary) {
+        (_, true) => "yes",
+        (Some(_), false) => "no",
+        (None, false) => "unknown (no symbols)",
+    };
+    writeln!(report, "# Stack canary: {}", canary);
+    if !info.fortified.is_empty() {
+        writeln!(
+            report,
+            "# Fortified functions: {} ({})",
+            info.fortified.len(),
+            info.fortified.join(", ")
+        );
+    }
+}
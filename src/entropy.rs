@@ -0,0 +1,286 @@
+use crate::archive::{for_each_entry, ArchiveKind};
+use crate::input::Input;
+use crate::limits::is_timed_out;
+use crate::report::Report;
+use crate::{format_size, Arguments};
+use std::io::{Error, Read};
+
+/// Characters per line of entropy profile.
+const PROFILE_WIDTH: usize = 64;
+/// Width of the longest histogram bar.
+const HISTOGRAM_WIDTH: f64 = 40.0;
+
+/// Likely content of region, guessed from its entropy and printable ratio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegionKind {
+    Padding,
+    Text,
+    Data,
+    Compressed,
+}
+
+impl RegionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RegionKind::Padding => "padding",
+            RegionKind::Text => "text",
+            RegionKind::Data => "data",
+            RegionKind::Compressed => "compressed or encrypted",
+        }
+    }
+}
+
+pub struct Region {
+    pub offset: u64,
+    pub length: u64,
+    pub kind: RegionKind,
+}
+
+pub struct EntropyInfo {
+    pub size: u64,
+    pub entropy: f64,
+    pub histogram: [u64; 256],
+    pub block_size: usize,
+    /// Entropy of each block, last one can be shorter.
+    pub blocks: Vec<f64>,
+    /// Consecutive blocks of the same kind merged together.
+    pub regions: Vec<Region>,
+}
+
+impl EntropyInfo {
+    /// Gets share of printable ASCII bytes (with tabs and line breaks).
+    pub fn printable_ratio(&self) -> f64 {
+        if self.size == 0 {
+            return 0.0;
+        }
+        let printable: u64 = (0..=255u8)
+            .filter(|b| is_printable(*b))
+            .map(|b| self.histogram[b as usize])
+            .sum();
+        printable as f64 / self.size as f64
+    }
+
+    pub fn kind(&self) -> RegionKind {
+        get_kind(&self.histogram, self.size)
+    }
+}
+
+fn is_printable(byte: u8) -> bool {
+    matches!(byte, 0x20..=0x7E | b'\t' | b'\n' | b'\r')
+}
+
+fn get_counts_entropy(counts: &[u64; 256], total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    counts
+        .iter()
+        .filter(|c| **c > 0)
+        .map(|c| {
+            let p = *c as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn get_counts(data: &[u8]) -> [u64; 256] {
+    let mut counts = [0u64; 256];
+    for b in data {
+        counts[*b as usize] += 1;
+    }
+    counts
+}
+
+/// Gets Shannon entropy of data in bits per byte (0 to 8).
+pub fn get_entropy(data: &[u8]) -> f64 {
+    get_counts_entropy(&get_counts(data), data.len() as u64)
+}
+
+/// Guesses kind of content from byte counts.
+pub fn get_kind(counts: &[u64; 256], total: u64) -> RegionKind {
+    let entropy = get_counts_entropy(counts, total);
+    if entropy < 1.0 {
+        return RegionKind::Padding;
+    }
+    let printable: u64 = (0..=255u8)
+        .filter(|b| is_printable(*b))
+        .map(|b| counts[b as usize])
+        .sum();
+    if printable as f64 >= total as f64 * 0.9 {
+        return RegionKind::Text;
+    }
+    // Short blocks can't reach 8 bits, compare with highest possible entropy instead.
+    let max_entropy = (total.min(256) as f64).log2();
+    if entropy >= max_entropy * 0.94 {
+        RegionKind::Compressed
+    } else {
+        RegionKind::Data
+    }
+}
+
+/// Gets entropy, histogram and block profile of data read in blocks.
+pub fn get_entropy_structure<R: Read>(
+    mut reader: R,
+    block_size: usize,
+) -> Result<EntropyInfo, Error> {
+    let mut info = EntropyInfo {
+        size: 0,
+        entropy: 0.0,
+        histogram: [0; 256],
+        block_size,
+        blocks: Vec::new(),
+        regions: Vec::new(),
+    };
+    let mut block = Vec::with_capacity(block_size);
+    // Profile of read part is kept when time is over.
+    while !is_timed_out() {
+        block.clear();
+        reader
+            .by_ref()
+            .take(block_size as u64)
+            .read_to_end(&mut block)?;
+        if block.is_empty() {
+            break;
+        }
+        let counts = get_counts(&block);
+        for (total, count) in info.histogram.iter_mut().zip(counts.iter()) {
+            *total += count;
+        }
+        info.blocks
+            .push(get_counts_entropy(&counts, block.len() as u64));
+        let kind = get_kind(&counts, block.len() as u64);
+        match info.regions.last_mut() {
+            Some(region) if region.kind == kind => region.length += block.len() as u64,
+            _ => info.regions.push(Region {
+                offset: info.size,
+                length: block.len() as u64,
+                kind,
+            }),
+        }
+        info.size += block.len() as u64;
+    }
+    info.entropy = get_counts_entropy(&info.histogram, info.size);
+    Ok(info)
+}
+
+/// Gets one-line entropy summary of archive entry.
+fn format_entry(args: &Arguments, name: &str, info: &EntropyInfo) -> String {
+    format!(
+        "\"{}\" ({}): {:.3} bits per byte, {:.2}% printable, {}",
+        name,
+        format_size(args, info.size),
+        info.entropy,
+        info.printable_ratio() * 100.0,
+        info.kind().name()
+    )
+}
+
+/// Gets entropy and byte distribution info about file.
+pub fn get_entropy_info(args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## Entropy information");
+    let info = match get_entropy_structure(input.bytes(), args.entropy_block_size) {
+        Ok(info) => info,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
+    writeln!(
+        report,
+        "# Entropy: {:.3} bits per byte ({})",
+        info.entropy,
+        info.kind().name()
+    );
+    writeln!(
+        report,
+        "# Printable: {:.2}%",
+        info.printable_ratio() * 100.0
+    );
+    if info.size == 0 {
+        return;
+    }
+
+    let mut common: Vec<(usize, u64)> = info
+        .histogram
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    common.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    write!(report, "# Most common bytes:");
+    for (byte, count) in common.iter().take(5) {
+        write!(
+            report,
+            " 0x{:02X} ({:.2}%)",
+            byte,
+            *count as f64 / info.size as f64 * 100.0
+        );
+    }
+    writeln!(report);
+    writeln!(report, "# Byte histogram:");
+    let buckets: Vec<u64> = info
+        .histogram
+        .chunks(16)
+        .map(|chunk| chunk.iter().sum())
+        .collect();
+    let largest = *buckets.iter().max().unwrap_or(&1) as f64;
+    for (i, bucket) in buckets.iter().enumerate() {
+        writeln!(
+            report,
+            "0x{:02X}-0x{:02X} {:>6.2}% {}",
+            i * 16,
+            i * 16 + 15,
+            *bucket as f64 / info.size as f64 * 100.0,
+            "#".repeat((*bucket as f64 / largest * HISTOGRAM_WIDTH).round() as usize)
+        );
+    }
+
+    writeln!(
+        report,
+        "# Entropy profile ({} per block, floor of bits per byte):",
+        format_size(args, info.block_size as u64)
+    );
+    let profile: Vec<char> = info
+        .blocks
+        .iter()
+        .map(|entropy| char::from(b'0' + (*entropy as u8).min(8)))
+        .collect();
+    for line in profile.chunks(PROFILE_WIDTH) {
+        writeln!(report, "{}", line.iter().collect::<String>());
+    }
+    writeln!(report, "# Regions:");
+    for region in info.regions.iter() {
+        writeln!(
+            report,
+            "0x{:08X}-0x{:08X} ({}): {}",
+            region.offset,
+            region.offset + region.length - 1,
+            format_size(args, region.length),
+            region.kind.name()
+        );
+    }
+}
+
+/// Gets entropy of every file in archive.
+pub fn get_entries_entropy_info(
+    args: &Arguments,
+    input: &Input,
+    kind: ArchiveKind,
+    report: &mut Report,
+) {
+    writeln!(report, "## Archive entries entropy");
+    let result =
+        for_each_entry(
+            input,
+            kind,
+            report,
+            |name, data, report| match get_entropy_structure(data, args.entropy_block_size) {
+                Ok(info) => writeln!(report, "{}", format_entry(args, name, &info)),
+                Err(e) => writeln!(report, "\"{}\": Error: {}", name, e),
+            },
+        );
+    if let Err(e) = result {
+        writeln!(report, "Error: {}", e);
+    }
+}
@@ -0,0 +1,569 @@
+use crate::batch::collect_files;
+use crate::components::HashAlgorithm;
+use crate::hash::get_hashes;
+use std::fs::File;
+use std::io::{BufReader, Error};
+use std::path::{Path, PathBuf};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const SPAMSUM_LENGTH: usize = 64;
+const MIN_BLOCK_SIZE: u64 = 3;
+const ROLLING_WINDOW: usize = 7;
+const NUM_BLOCKHASHES: usize = 31;
+const HASH_PRIME: u32 = 0x01000193;
+const HASH_INIT: u32 = 0x28021967;
+
+/// Pearson hashing permutation used by TLSH.
+const TLSH_TABLE: [u8; 256] = [
+    1, 87, 49, 12, 176, 178, 102, 166, 121, 193, 6, 84, 249, 230, 44, 163, 14, 197, 213, 181, 161,
+    85, 218, 80, 64, 239, 24, 226, 236, 142, 38, 200, 110, 177, 104, 103, 141, 253, 255, 50, 77,
+    101, 81, 18, 45, 96, 31, 222, 25, 107, 190, 70, 86, 237, 240, 34, 72, 242, 20, 214, 244, 227,
+    149, 235, 97, 234, 57, 22, 60, 250, 82, 175, 208, 5, 127, 199, 111, 62, 135, 248, 174, 169,
+    211, 58, 66, 154, 106, 195, 245, 171, 17, 187, 182, 179, 0, 243, 132, 56, 148, 75, 128, 133,
+    158, 100, 130, 126, 91, 13, 153, 246, 216, 219, 119, 68, 223, 78, 83, 88, 201, 99, 122, 11, 92,
+    32, 136, 114, 52, 10, 138, 30, 48, 183, 156, 35, 61, 26, 143, 74, 251, 94, 129, 162, 63, 152,
+    170, 7, 115, 167, 241, 206, 3, 150, 55, 59, 151, 220, 90, 53, 23, 131, 125, 173, 15, 238, 79,
+    95, 89, 16, 105, 137, 225, 224, 217, 160, 37, 123, 118, 73, 2, 157, 46, 116, 9, 145, 134, 228,
+    207, 212, 202, 215, 69, 229, 27, 188, 67, 124, 168, 252, 42, 4, 29, 108, 21, 247, 19, 205, 39,
+    203, 233, 40, 186, 147, 198, 192, 155, 33, 164, 191, 98, 204, 165, 180, 117, 76, 140, 36, 210,
+    172, 41, 54, 159, 8, 185, 232, 113, 196, 231, 47, 146, 120, 51, 65, 28, 144, 254, 221, 93, 189,
+    194, 139, 112, 43, 71, 109, 184, 209,
+];
+const TLSH_BUCKETS: usize = 128;
+const TLSH_CODE_SIZE: usize = TLSH_BUCKETS / 4;
+const TLSH_MIN_LENGTH: u64 = 50;
+
+fn block_size(index: usize) -> u64 {
+    MIN_BLOCK_SIZE << index
+}
+
+/// Rolling hash over last 7 bytes, decides where piece of input ends.
+#[derive(Default)]
+struct Roll {
+    window: [u8; ROLLING_WINDOW],
+    h1: u32,
+    h2: u32,
+    h3: u32,
+    n: usize,
+}
+
+impl Roll {
+    fn update(&mut self, c: u8) {
+        self.h2 = self.h2.wrapping_sub(self.h1);
+        self.h2 = self.h2.wrapping_add(ROLLING_WINDOW as u32 * c as u32);
+        self.h1 = self.h1.wrapping_add(c as u32);
+        self.h1 = self
+            .h1
+            .wrapping_sub(self.window[self.n % ROLLING_WINDOW] as u32);
+        self.window[self.n % ROLLING_WINDOW] = c;
+        self.n += 1;
+        self.h3 = (self.h3 << 5) ^ c as u32;
+    }
+
+    fn sum(&self) -> u32 {
+        self.h1.wrapping_add(self.h2).wrapping_add(self.h3)
+    }
+}
+
+#[derive(Clone)]
+struct BlockHash {
+    h: u32,
+    half_h: u32,
+    digest: Vec<u8>,
+    half_digest: Option<u8>,
+    /// Character triggered after digest was full.
+    last: Option<u8>,
+}
+
+impl BlockHash {
+    fn new() -> BlockHash {
+        BlockHash {
+            h: HASH_INIT,
+            half_h: HASH_INIT,
+            digest: Vec::with_capacity(SPAMSUM_LENGTH),
+            half_digest: None,
+            last: None,
+        }
+    }
+}
+
+/// Context triggered piecewise hash compatible with ssdeep,
+/// all block sizes are computed at once so input is read only once.
+pub struct Ssdeep {
+    roll: Roll,
+    blocks: Vec<BlockHash>,
+    start: usize,
+    size: u64,
+}
+
+impl Default for Ssdeep {
+    fn default() -> Self {
+        Ssdeep {
+            roll: Roll::default(),
+            blocks: vec![BlockHash::new()],
+            start: 0,
+            size: 0,
+        }
+    }
+}
+
+impl Ssdeep {
+    pub fn update(&mut self, data: &[u8]) {
+        self.size += data.len() as u64;
+        for c in data {
+            self.step(*c);
+        }
+    }
+
+    fn step(&mut self, c: u8) {
+        self.roll.update(c);
+        let h = self.roll.sum() as u64;
+        for block in self.blocks[self.start..].iter_mut() {
+            block.h = block.h.wrapping_mul(HASH_PRIME) ^ c as u32;
+            block.half_h = block.half_h.wrapping_mul(HASH_PRIME) ^ c as u32;
+        }
+        let mut i = self.start;
+        // Block sizes double, so if smaller one isn't triggered, bigger won't be either.
+        while i < self.blocks.len() && h % block_size(i) == block_size(i) - 1 {
+            if self.blocks[i].digest.is_empty() && self.blocks.len() < NUM_BLOCKHASHES {
+                let mut next = BlockHash::new();
+                let last = self.blocks.last().unwrap();
+                (next.h, next.half_h) = (last.h, last.half_h);
+                self.blocks.push(next);
+            }
+            let block = &mut self.blocks[i];
+            let character = BASE64[(block.h % 64) as usize];
+            let half_character = BASE64[(block.half_h % 64) as usize];
+            if block.digest.len() < SPAMSUM_LENGTH - 1 {
+                block.digest.push(character);
+                block.h = HASH_INIT;
+                if block.digest.len() < SPAMSUM_LENGTH / 2 {
+                    block.half_h = HASH_INIT;
+                    block.half_digest = None;
+                } else {
+                    block.half_digest = Some(half_character);
+                }
+            } else {
+                // Last character keeps being replaced when digest is full.
+                block.last = Some(character);
+                block.half_digest = Some(half_character);
+                self.try_reduce();
+            }
+            i += 1;
+        }
+    }
+
+    /// Drops smallest block size when it can't be chosen anymore.
+    fn try_reduce(&mut self) {
+        if self.blocks.len() - self.start < 2
+            || block_size(self.start) * SPAMSUM_LENGTH as u64 >= self.size
+            || self.blocks[self.start + 1].digest.len() < SPAMSUM_LENGTH / 2
+        {
+            return;
+        }
+        self.start += 1;
+    }
+
+    pub fn finalize(self) -> String {
+        let h = self.roll.sum();
+        let mut index = self.start;
+        while block_size(index) * (SPAMSUM_LENGTH as u64) < self.size && index < NUM_BLOCKHASHES - 1
+        {
+            index += 1;
+        }
+        index = index.min(self.blocks.len() - 1);
+        while index > self.start && self.blocks[index].digest.len() < SPAMSUM_LENGTH / 2 {
+            index -= 1;
+        }
+
+        let block = &self.blocks[index];
+        let mut result = format!("{}:", block_size(index));
+        result.push_str(&String::from_utf8_lossy(&block.digest));
+        if h != 0 {
+            result.push(BASE64[(block.h % 64) as usize] as char);
+        } else if let Some(last) = block.last {
+            result.push(last as char);
+        }
+        result.push(':');
+        if let Some(next) = self.blocks.get(index + 1) {
+            let length = next.digest.len().min(SPAMSUM_LENGTH / 2 - 1);
+            result.push_str(&String::from_utf8_lossy(&next.digest[..length]));
+            if h != 0 {
+                result.push(BASE64[(next.half_h % 64) as usize] as char);
+            } else if let Some(half) = next.half_digest {
+                result.push(half as char);
+            }
+        } else if h != 0 && index == 0 {
+            result.push(BASE64[(block.h % 64) as usize] as char);
+        }
+        result
+    }
+}
+
+/// Removes runs of more than 3 same characters, they carry little information.
+fn eliminate_sequences(text: &str) -> Vec<u8> {
+    let mut result: Vec<u8> = Vec::with_capacity(text.len());
+    for c in text.bytes() {
+        let length = result.len();
+        if length < 3 || result[length - 3..].iter().any(|p| *p != c) {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Edit distance where substitution costs as much as removal and insertion.
+fn edit_distance(a: &[u8], b: &[u8]) -> u32 {
+    let mut previous: Vec<u32> = (0..=b.len() as u32).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i as u32 + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 2 };
+            current.push(
+                (previous[j + 1] + 1)
+                    .min(current[j] + 1)
+                    .min(previous[j] + cost),
+            );
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn score_strings(a: &[u8], b: &[u8], block_size: u64) -> u32 {
+    if a.len() > SPAMSUM_LENGTH || b.len() > SPAMSUM_LENGTH {
+        return 0;
+    }
+    // Strings without common piece of rolling window size are considered unrelated.
+    let common = a
+        .windows(ROLLING_WINDOW)
+        .any(|wa| b.windows(ROLLING_WINDOW).any(|wb| wa == wb));
+    if !common {
+        return 0;
+    }
+    let mut score = edit_distance(a, b) * SPAMSUM_LENGTH as u32 / (a.len() + b.len()) as u32;
+    score = 100 * score / SPAMSUM_LENGTH as u32;
+    if score >= 100 {
+        return 0;
+    }
+    score = 100 - score;
+    // Small block sizes can't tell short similar pieces apart.
+    let cap_limit = (99 + ROLLING_WINDOW as u64) / ROLLING_WINDOW as u64 * MIN_BLOCK_SIZE;
+    if block_size >= cap_limit {
+        return score;
+    }
+    let cap = (block_size / MIN_BLOCK_SIZE) as u32 * a.len().min(b.len()) as u32;
+    score.min(cap)
+}
+
+fn parse_ssdeep(hash: &str) -> Option<(u64, Vec<u8>, Vec<u8>)> {
+    let mut parts = hash.splitn(3, ':');
+    let block_size = parts.next()?.parse().ok()?;
+    let first = eliminate_sequences(parts.next()?);
+    // Hash can be followed by file name like in ssdeep output.
+    let second = eliminate_sequences(parts.next()?.split(',').next()?);
+    Some((block_size, first, second))
+}
+
+/// Gets ssdeep similarity score of two hashes (0 to 100), None if hash is invalid.
+pub fn compare_ssdeep(a: &str, b: &str) -> Option<u32> {
+    let (size_a, first_a, second_a) = parse_ssdeep(a)?;
+    let (size_b, first_b, second_b) = parse_ssdeep(b)?;
+    let score = if size_a == size_b {
+        if first_a == first_b {
+            return Some(100);
+        }
+        score_strings(&first_a, &first_b, size_a).max(score_strings(
+            &second_a,
+            &second_b,
+            size_a * 2,
+        ))
+    } else if size_a == size_b * 2 {
+        score_strings(&first_a, &second_b, size_a)
+    } else if size_b == size_a * 2 {
+        score_strings(&second_a, &first_b, size_b)
+    } else {
+        0
+    };
+    Some(score)
+}
+
+fn tlsh_mapping(salt: u8, i: u8, j: u8, k: u8) -> u8 {
+    let h = TLSH_TABLE[salt as usize];
+    let h = TLSH_TABLE[(h ^ i) as usize];
+    let h = TLSH_TABLE[(h ^ j) as usize];
+    TLSH_TABLE[(h ^ k) as usize]
+}
+
+/// Trend Micro locality sensitive hash with 128 buckets and 1 byte checksum.
+pub struct Tlsh {
+    buckets: [u32; 256],
+    window: [u8; 5],
+    checksum: u8,
+    size: u64,
+}
+
+impl Default for Tlsh {
+    fn default() -> Self {
+        Tlsh {
+            buckets: [0; 256],
+            window: [0; 5],
+            checksum: 0,
+            size: 0,
+        }
+    }
+}
+
+impl Tlsh {
+    pub fn update(&mut self, data: &[u8]) {
+        for c in data {
+            let j = (self.size % 5) as usize;
+            self.window[j] = *c;
+            if self.size >= 4 {
+                let w = |back: usize| self.window[(j + 5 - back) % 5];
+                let (w0, w1, w2, w3, w4) = (w(0), w(1), w(2), w(3), w(4));
+                self.checksum = tlsh_mapping(0, w0, w1, self.checksum);
+                for (salt, a, b) in [
+                    (2, w1, w2),
+                    (3, w1, w3),
+                    (5, w2, w3),
+                    (7, w2, w4),
+                    (11, w1, w4),
+                    (13, w3, w4),
+                ] {
+                    self.buckets[tlsh_mapping(salt, w0, a, b) as usize] += 1;
+                }
+            }
+            self.size += 1;
+        }
+    }
+
+    /// Gets length part of hash, logarithmic so files of similar size have close values.
+    fn length_value(size: u64) -> u8 {
+        let size = size as f64;
+        let value = if size <= 656.0 {
+            size.ln() / 1.5f64.ln()
+        } else if size <= 3199.0 {
+            size.ln() / 1.3f64.ln() - 8.72777
+        } else {
+            size.ln() / 1.1f64.ln() - 62.5472
+        };
+        (value.floor() as u64 & 0xFF) as u8
+    }
+
+    /// Gets hash as `T1` and 70 hex digits, `TNULL` for short or too uniform input.
+    pub fn finalize(self) -> String {
+        let buckets = &self.buckets[..TLSH_BUCKETS];
+        let nonzero = buckets.iter().filter(|b| **b > 0).count();
+        if self.size < TLSH_MIN_LENGTH || nonzero <= TLSH_BUCKETS / 2 {
+            return "TNULL".to_string();
+        }
+        let mut sorted = buckets.to_vec();
+        sorted.sort_unstable();
+        let (q1, q2, q3) = (
+            sorted[TLSH_BUCKETS / 4 - 1],
+            sorted[TLSH_BUCKETS / 2 - 1],
+            sorted[TLSH_BUCKETS * 3 / 4 - 1],
+        );
+        if q3 == 0 {
+            return "TNULL".to_string();
+        }
+        let q1_ratio = ((q1 as f32 * 100.0) / q3 as f32) as u32 % 16;
+        let q2_ratio = ((q2 as f32 * 100.0) / q3 as f32) as u32 % 16;
+        let swap = |b: u8| b.rotate_left(4);
+        let mut hash = format!(
+            "T1{:02X}{:02X}{:X}{:X}",
+            swap(self.checksum),
+            swap(Tlsh::length_value(self.size)),
+            q1_ratio,
+            q2_ratio
+        );
+        for i in (0..TLSH_CODE_SIZE).rev() {
+            let mut code = 0u8;
+            for (j, bucket) in buckets[i * 4..i * 4 + 4].iter().enumerate() {
+                let quartile = if *bucket > q3 {
+                    3
+                } else if *bucket > q2 {
+                    2
+                } else if *bucket > q1 {
+                    1
+                } else {
+                    0
+                };
+                code |= quartile << (j * 2);
+            }
+            hash.push_str(&format!("{:02X}", code));
+        }
+        hash
+    }
+}
+
+fn parse_tlsh(hash: &str) -> Option<Vec<u8>> {
+    let hex = hash.strip_prefix("T1").unwrap_or(hash);
+    if hex.len() != 70 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Distance of values on ring of given size.
+fn mod_diff(a: u32, b: u32, size: u32) -> u32 {
+    let diff = a.abs_diff(b);
+    diff.min(size - diff)
+}
+
+/// Gets TLSH distance of two hashes, 0 means same, under 100 usually similar.
+pub fn compare_tlsh(a: &str, b: &str) -> Option<u32> {
+    let a = parse_tlsh(a)?;
+    let b = parse_tlsh(b)?;
+    let mut diff = 0;
+    // Checksum, length and quartile ratios are stored with swapped nibbles.
+    let swap = |b: u8| b.rotate_left(4);
+    let length_diff = mod_diff(swap(a[1]) as u32, swap(b[1]) as u32, 256);
+    diff += if length_diff <= 1 {
+        length_diff
+    } else {
+        length_diff * 12
+    };
+    for shift in [4, 0] {
+        let ratio_diff = mod_diff(
+            (a[2] >> shift & 0xF) as u32,
+            (b[2] >> shift & 0xF) as u32,
+            16,
+        );
+        diff += if ratio_diff <= 1 {
+            ratio_diff
+        } else {
+            (ratio_diff - 1) * 12
+        };
+    }
+    if a[0] != b[0] {
+        diff += 1;
+    }
+    for (x, y) in a[3..].iter().zip(b[3..].iter()) {
+        for shift in [0, 2, 4, 6] {
+            let pair_diff = (x >> shift & 3).abs_diff(y >> shift & 3) as u32;
+            diff += if pair_diff == 3 { 6 } else { pair_diff };
+        }
+    }
+    Some(diff)
+}
+
+fn get_fuzzy_hashes(path: &Path) -> Result<(String, String), Error> {
+    let file = File::open(path)?;
+    let hashes = get_hashes(
+        BufReader::new(file),
+        &[HashAlgorithm::Ssdeep, HashAlgorithm::Tlsh],
+    )?;
+    Ok((hashes[0].1.clone(), hashes[1].1.clone()))
+}
+
+/// Is pair similar enough with threshold being minimal ssdeep score or maximal TLSH distance.
+fn is_similar(algorithm: HashAlgorithm, score: Option<u32>, threshold: u32) -> bool {
+    match (algorithm, score) {
+        (HashAlgorithm::Tlsh, Some(distance)) => distance <= threshold,
+        (_, Some(score)) => score >= threshold && score > 0,
+        (_, None) => false,
+    }
+}
+
+/// Compares two files, or clusters more files by fuzzy hash similarity.
+/// Files in directories which can't be hashed are skipped, but given files must be hashed.
+pub fn compare(paths: &[PathBuf], algorithm: HashAlgorithm, threshold: u32) -> Result<(), Error> {
+    let mut hashes: Vec<(PathBuf, String, String)> = Vec::new();
+    for path in collect_files(paths) {
+        match get_fuzzy_hashes(&path) {
+            Ok((ssdeep, tlsh)) => hashes.push((path, ssdeep, tlsh)),
+            Err(e) if paths.contains(&path) => {
+                return Err(Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+            }
+            Err(e) => eprintln!("Error: {}: {}", path.display(), e),
+        }
+    }
+    let score = |a: usize, b: usize| match algorithm {
+        HashAlgorithm::Tlsh => compare_tlsh(&hashes[a].2, &hashes[b].2),
+        _ => compare_ssdeep(&hashes[a].1, &hashes[b].1),
+    };
+
+    if hashes.len() == 2 && paths.len() == 2 {
+        println!("## Comparison");
+        for (path, ssdeep, tlsh) in hashes.iter() {
+            println!("{}: {} {}", path.display(), ssdeep, tlsh);
+        }
+        match compare_ssdeep(&hashes[0].1, &hashes[1].1) {
+            Some(score) => println!(
+                "# ssdeep score: {} (0 to 100, higher is more similar)",
+                score
+            ),
+            None => println!("# ssdeep score: invalid hash"),
+        }
+        match compare_tlsh(&hashes[0].2, &hashes[1].2) {
+            Some(distance) => println!(
+                "# TLSH distance: {} (0 is same, lower is more similar)",
+                distance
+            ),
+            None => println!("# TLSH distance: file is too short or too uniform"),
+        }
+        return Ok(());
+    }
+
+    // Single-linkage clustering, files are joined when any pair in clusters is similar.
+    let mut cluster: Vec<usize> = (0..hashes.len()).collect();
+    let mut pairs = Vec::new();
+    for a in 0..hashes.len() {
+        for b in a + 1..hashes.len() {
+            let pair_score = score(a, b);
+            if is_similar(algorithm, pair_score, threshold) {
+                pairs.push((a, b, pair_score.unwrap_or(0)));
+                let (from, to) = (cluster[b], cluster[a]);
+                for c in cluster.iter_mut() {
+                    if *c == from {
+                        *c = to;
+                    }
+                }
+            }
+        }
+    }
+    match algorithm {
+        HashAlgorithm::Tlsh => println!(
+            "## Clusters of {} files (TLSH distance <= {})",
+            hashes.len(),
+            threshold
+        ),
+        _ => println!(
+            "## Clusters of {} files (ssdeep score >= {})",
+            hashes.len(),
+            threshold
+        ),
+    }
+    let mut roots: Vec<usize> = cluster.clone();
+    roots.sort_unstable();
+    roots.dedup();
+    let mut number = 0;
+    let mut unclustered = 0;
+    for root in roots {
+        let members: Vec<usize> = (0..hashes.len()).filter(|i| cluster[*i] == root).collect();
+        if members.len() < 2 {
+            unclustered += 1;
+            continue;
+        }
+        number += 1;
+        println!("# Cluster {} ({} files):", number, members.len());
+        for member in members.iter() {
+            println!("{}", hashes[*member].0.display());
+        }
+        for (a, b, pair_score) in pairs.iter().filter(|(a, _, _)| cluster[*a] == root) {
+            println!(
+                "{} <-> {}: {}",
+                hashes[*a].0.display(),
+                hashes[*b].0.display(),
+                pair_score
+            );
+        }
+    }
+    println!("# Files without similar file: {}", unclustered);
+    Ok(())
+}
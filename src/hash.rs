@@ -0,0 +1,213 @@
+use crate::archive::{for_each_entry, ArchiveKind};
+use crate::components::{HashAlgorithm, Section};
+use crate::fuzzy::{Ssdeep, Tlsh};
+use crate::input::Input;
+use crate::limits::is_timed_out;
+use crate::report::Report;
+use crate::Arguments;
+use sha1::Digest;
+use std::io::{Error, ErrorKind, Read};
+
+/// Size of chunks read from file.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Digest of part of file would look valid, like digest of timed out reading.
+const TRUNCATED: &str = "file is read only up to --max-read, digest of its part is not computed";
+
+/// Gets display name of hash algorithm.
+pub fn hash_name(algorithm: HashAlgorithm) -> &'static str {
+    match algorithm {
+        HashAlgorithm::Md5 => "MD5",
+        HashAlgorithm::Sha1 => "SHA-1",
+        HashAlgorithm::Sha256 => "SHA-256",
+        HashAlgorithm::Sha512 => "SHA-512",
+        HashAlgorithm::Blake3 => "BLAKE3",
+        HashAlgorithm::Crc32 => "CRC32",
+        HashAlgorithm::Ssdeep => "ssdeep",
+        HashAlgorithm::Tlsh => "TLSH",
+    }
+}
+
+enum Hasher {
+    Md5(md5::Md5),
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+    Sha512(sha2::Sha512),
+    Blake3(Box<blake3::Hasher>),
+    Crc32(crc32fast::Hasher),
+    Ssdeep(Box<Ssdeep>),
+    Tlsh(Box<Tlsh>),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Hasher {
+        match algorithm {
+            HashAlgorithm::Md5 => Hasher::Md5(md5::Md5::new()),
+            HashAlgorithm::Sha1 => Hasher::Sha1(sha1::Sha1::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+            HashAlgorithm::Sha512 => Hasher::Sha512(sha2::Sha512::new()),
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::default()),
+            HashAlgorithm::Crc32 => Hasher::Crc32(crc32fast::Hasher::new()),
+            HashAlgorithm::Ssdeep => Hasher::Ssdeep(Box::default()),
+            HashAlgorithm::Tlsh => Hasher::Tlsh(Box::default()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(hasher) => hasher.update(data),
+            Hasher::Sha1(hasher) => hasher.update(data),
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Sha512(hasher) => hasher.update(data),
+            Hasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            Hasher::Crc32(hasher) => hasher.update(data),
+            Hasher::Ssdeep(hasher) => hasher.update(data),
+            Hasher::Tlsh(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Gets digest as lowercase hex, fuzzy hashes have their own text format.
+    fn finalize(self) -> String {
+        let digest = match self {
+            Hasher::Md5(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha1(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha512(hasher) => hasher.finalize().to_vec(),
+            Hasher::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+            Hasher::Crc32(hasher) => hasher.finalize().to_be_bytes().to_vec(),
+            Hasher::Ssdeep(hasher) => return hasher.finalize(),
+            Hasher::Tlsh(hasher) => return hasher.finalize(),
+        };
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Gets digests of data, all algorithms are computed in one pass.
+pub fn get_hashes<R: Read>(
+    mut reader: R,
+    algorithms: &[HashAlgorithm],
+) -> Result<Vec<(HashAlgorithm, String)>, Error> {
+    let mut hashers: Vec<Hasher> = algorithms.iter().map(|a| Hasher::new(*a)).collect();
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    loop {
+        // Digest of part of data would look valid, so it's an error.
+        if is_timed_out() {
+            return Err(Error::new(ErrorKind::TimedOut, "timed out"));
+        }
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for hasher in hashers.iter_mut() {
+            hasher.update(&chunk[..read]);
+        }
+    }
+    Ok(algorithms
+        .iter()
+        .zip(hashers)
+        .map(|(algorithm, hasher)| (*algorithm, hasher.finalize()))
+        .collect())
+}
+
+/// Formats checksum line like sha256sum does, names with backslash or newline are escaped.
+pub fn format_sum_line(hash: &str, name: &str) -> String {
+    if name.contains(['\\', '\n', '\r']) {
+        let escaped = name
+            .replace('\\', "\\\\")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r");
+        format!("\\{}  {}", hash, escaped)
+    } else {
+        format!("{}  {}", hash, name)
+    }
+}
+
+/// Gets requested hashes of file.
+pub fn get_hash_info(args: &Arguments, input: &Input, report: &mut Report) {
+    if input.is_truncated() {
+        writeln!(report, "Error: {}", TRUNCATED);
+        return;
+    }
+    let hashes = match get_hashes(input.bytes(), &args.hashes) {
+        Ok(hashes) => hashes,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
+    for (algorithm, hash) in hashes {
+        report.print_field(
+            Section::Hashes,
+            algorithm.into(),
+            hash_name(algorithm),
+            hash,
+        );
+    }
+}
+
+/// Gets requested hashes of every file in archive (of decompressed content).
+pub fn get_entries_hash_info(
+    args: &Arguments,
+    input: &Input,
+    kind: ArchiveKind,
+    report: &mut Report,
+) {
+    writeln!(report, "## Archive entries hashes");
+    let result = for_each_entry(input, kind, report, |name, data, report| {
+        match get_hashes(data, &args.hashes) {
+            Ok(hashes) => {
+                for (algorithm, hash) in hashes {
+                    writeln!(report, "\"{}\" {}: {}", name, hash_name(algorithm), hash);
+                }
+            }
+            Err(e) => writeln!(report, "\"{}\": Error: {}", name, e),
+        }
+    });
+    if let Err(e) = result {
+        writeln!(report, "Error: {}", e);
+    }
+}
+
+/// Prints only checksum line of file, so it can be checked by sha256sum -c.
+/// Archive entries are left out, they are not files which could be checked.
+/// Errors and notes about limits are printed to stderr.
+pub fn get_sums_info(args: &Arguments, report: &mut Report) {
+    let path = args.stream_path.as_ref().unwrap_or(&args.file_path);
+    crate::limits::start(args.limits);
+    let mut input = match Input::open(&args.file_path) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            return;
+        }
+    };
+    if let Some(max_read) = args.limits.max_read {
+        if input.truncate(max_read) {
+            eprintln!("{}: {}", path.display(), TRUNCATED);
+            return;
+        }
+    }
+    match get_hashes(input.bytes(), &args.hashes) {
+        Ok(hashes) => {
+            for (_, hash) in hashes {
+                writeln!(
+                    report,
+                    "{}",
+                    format_sum_line(&hash, &path.to_string_lossy())
+                );
+            }
+        }
+        Err(e) => eprintln!("{}: {}", path.display(), e),
+    }
+    for limit in crate::limits::get_reached() {
+        eprintln!(
+            "{}: truncated, limit {} reached",
+            path.display(),
+            crate::limits::describe(&args.limits, limit)
+        );
+    }
+}
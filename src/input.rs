@@ -0,0 +1,80 @@
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{Cursor, Error, Read};
+use std::path::{Path, PathBuf};
+
+/// Data of analyzed file.
+enum Contents {
+    Mapped(Mmap),
+    Buffered(Vec<u8>),
+}
+
+/// Analyzed file, opened once and shared by detection and all analyzers.
+/// It's memory-mapped when possible, read into memory otherwise.
+pub struct Input {
+    pub path: PathBuf,
+    contents: Contents,
+    /// Length of data given to analyzers, it's shorter than file when truncated.
+    length: usize,
+}
+
+impl Input {
+    pub fn open(path: &Path) -> Result<Input, Error> {
+        let mut file = File::open(path)?;
+        // Mapping fails for some special files and filesystems, files in /proc look empty.
+        // Other processes can still change file, parsers handle it like corrupted data
+        // (only truncating it while mapped makes reads fail with SIGBUS).
+        let contents = match unsafe { Mmap::map(&file) } {
+            Ok(mmap) if !mmap.is_empty() => Contents::Mapped(mmap),
+            _ => {
+                let mut data = Vec::new();
+                file.read_to_end(&mut data)?;
+                Contents::Buffered(data)
+            }
+        };
+        let length = match &contents {
+            Contents::Mapped(mmap) => mmap.len(),
+            Contents::Buffered(data) => data.len(),
+        };
+        Ok(Input {
+            path: path.to_path_buf(),
+            contents,
+            length,
+        })
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        match &self.contents {
+            Contents::Mapped(mmap) => &mmap[..self.length],
+            Contents::Buffered(data) => &data[..self.length],
+        }
+    }
+
+    /// Gives only first bytes of file to analyzers, returns whether anything was cut off.
+    pub fn truncate(&mut self, length: u64) -> bool {
+        let truncated = (length as usize) < self.length;
+        self.length = self.length.min(length as usize);
+        truncated
+    }
+
+    /// Is data given to analyzers shorter than file.
+    pub fn is_truncated(&self) -> bool {
+        match &self.contents {
+            Contents::Mapped(mmap) => self.length < mmap.len(),
+            Contents::Buffered(data) => self.length < data.len(),
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.bytes().len() as u64
+    }
+
+    /// Gets reader with seeking, for parsers like zip.
+    pub fn reader(&self) -> Cursor<&[u8]> {
+        Cursor::new(self.bytes())
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.contents, Contents::Mapped(_))
+    }
+}
@@ -0,0 +1,648 @@
+use crate::datetime::{format_timestamp, Timestamp};
+use crate::input::Input;
+use crate::limits::{allow_depth, is_timed_out};
+use crate::report::Report;
+use crate::{format_size, get_extension_from_id, Arguments};
+use std::io::{Error, ErrorKind};
+
+/// Seconds between 1904-01-01 (QuickTime epoch) and 1970-01-01.
+const MAC_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+/// Boxes that contain only other boxes.
+const CONTAINERS: [&[u8; 4]; 19] = [
+    b"moov", b"trak", b"mdia", b"minf", b"stbl", b"dinf", b"edts", b"udta", b"mvex", b"moof",
+    b"traf", b"mfra", b"ilst", b"iprp", b"ipco", b"sinf", b"schi", b"tref", b"gmhd",
+];
+
+const VISUAL_ENTRIES: [&[u8; 4]; 12] = [
+    b"avc1", b"avc3", b"hvc1", b"hev1", b"av01", b"vp08", b"vp09", b"mp4v", b"encv", b"jpeg",
+    b"dvh1", b"dvhe",
+];
+
+const AUDIO_ENTRIES: [&[u8; 4]; 10] = [
+    b"mp4a", b"ac-3", b"ec-3", b"Opus", b"fLaC", b"alac", b"enca", b"samr", b"sowt", b"twos",
+];
+
+/// Box of ISO base media file.
+pub struct BmffBox {
+    pub kind: [u8; 4],
+    pub offset: usize,
+    pub size: usize,
+    /// Length of box header (8, 16 for large size, +16 for uuid).
+    pub header: usize,
+    pub children: Vec<BmffBox>,
+    /// Short description of box contents if known.
+    pub summary: Option<String>,
+}
+
+impl BmffBox {
+    pub fn kind(&self) -> String {
+        fourcc(&self.kind)
+    }
+
+    fn payload<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        let start = (self.offset + self.header).min(data.len());
+        let end = (self.offset + self.size).min(data.len());
+        &data[start..end.max(start)]
+    }
+
+    fn child(&self, kind: &[u8; 4]) -> Option<&BmffBox> {
+        self.children.iter().find(|c| &c.kind == kind)
+    }
+
+    /// Finds box by path of kinds, e.g. `[b"mdia", b"minf"]`.
+    fn find(&self, path: &[&[u8; 4]]) -> Option<&BmffBox> {
+        let mut current = self;
+        for kind in path {
+            current = current.child(kind)?;
+        }
+        Some(current)
+    }
+}
+
+pub struct Track {
+    pub id: u32,
+    pub handler: String,
+    pub codec: Option<String>,
+    pub duration: Option<f64>,
+    pub dimensions: Option<(u32, u32)>,
+    pub language: Option<String>,
+    pub enabled: bool,
+}
+
+/// Converts four bytes to string, bytes are read as Latin-1 (for "©nam" and alike).
+pub fn fourcc(kind: &[u8]) -> String {
+    kind.iter().map(|b| *b as char).collect()
+}
+
+fn be_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn be_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn be_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// Formats time counted from 1904-01-01 UTC, zero means unset.
+fn format_mac_time(args: &Arguments, seconds: u64) -> String {
+    if seconds == 0 {
+        return "not set".to_string();
+    }
+    format_timestamp(
+        args,
+        Timestamp::from_seconds(seconds as i64 - MAC_EPOCH_OFFSET),
+    )
+}
+
+/// Gets creation and modification time of mvhd/mdhd box.
+fn format_header_times(args: &Arguments, data: &[u8], item: &BmffBox) -> Option<String> {
+    if !matches!(&item.kind, b"mvhd" | b"mdhd") {
+        return None;
+    }
+    let (created, modified, _, _, _) = get_header_times(item.payload(data))?;
+    Some(format!(
+        "created {}, modified {}",
+        format_mac_time(args, created),
+        format_mac_time(args, modified)
+    ))
+}
+
+/// Reads (creation, modification, timescale, duration) of mvhd/mdhd and the offset after them.
+fn get_header_times(payload: &[u8]) -> Option<(u64, u64, u32, u64, usize)> {
+    if payload.first()? == &1 {
+        Some((
+            be_u64(payload, 4)?,
+            be_u64(payload, 12)?,
+            be_u32(payload, 20)?,
+            be_u64(payload, 24)?,
+            32,
+        ))
+    } else {
+        Some((
+            be_u32(payload, 4)? as u64,
+            be_u32(payload, 8)? as u64,
+            be_u32(payload, 12)?,
+            be_u32(payload, 16)? as u64,
+            20,
+        ))
+    }
+}
+
+fn get_language(packed: u16) -> String {
+    (0..3)
+        .rev()
+        .map(|i| (((packed >> (i * 5)) & 0x1F) as u8 + 0x60) as char)
+        .collect()
+}
+
+/// Reads tkhd box: (track id, duration in movie timescale, width, height, flags).
+fn get_track_header(payload: &[u8]) -> Option<(u32, u64, u32, u32, u32)> {
+    let flags = be_u32(payload, 0)? & 0x00FF_FFFF;
+    let (id, duration, size) = if payload[0] == 1 {
+        (be_u32(payload, 20)?, be_u64(payload, 28)?, 88)
+    } else {
+        (be_u32(payload, 12)?, be_u32(payload, 20)? as u64, 76)
+    };
+    Some((
+        id,
+        duration,
+        be_u32(payload, size)? >> 16,
+        be_u32(payload, size + 4)? >> 16,
+        flags,
+    ))
+}
+
+/// Offset of child boxes inside payload, None if box is not a container.
+fn children_offset(kind: &[u8; 4], payload: &[u8]) -> Option<usize> {
+    if CONTAINERS.contains(&kind) {
+        return Some(0);
+    }
+    match kind {
+        // Full box with entry count.
+        b"stsd" | b"dref" => Some(8),
+        // QuickTime meta is a plain box, ISO meta is a full box.
+        b"meta" => match payload.get(4..8) {
+            Some(b"hdlr") => Some(0),
+            _ => Some(4),
+        },
+        b"iinf" => Some(if payload.first() == Some(&0) { 6 } else { 8 }),
+        _ if VISUAL_ENTRIES.contains(&kind) => Some(78),
+        _ if AUDIO_ENTRIES.contains(&kind) => {
+            // QuickTime sound description version 1 and 2 have longer headers.
+            match be_u16(payload, 8) {
+                Some(1) => Some(44),
+                Some(2) => Some(64),
+                _ => Some(28),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Describes well-known leaf boxes.
+fn describe_box(kind: &[u8; 4], payload: &[u8]) -> Option<String> {
+    match kind {
+        b"ftyp" | b"styp" => {
+            let brands: Vec<String> = payload.get(8..)?.chunks_exact(4).map(fourcc).collect();
+            Some(format!(
+                "major {}, minor {}, compatible {}",
+                fourcc(payload.get(..4)?),
+                be_u32(payload, 4)?,
+                brands.join(" ")
+            ))
+        }
+        b"mvhd" | b"mdhd" => {
+            // Times are added when printing, in chosen format.
+            let (_, _, timescale, duration, after) = get_header_times(payload)?;
+            let mut summary = format!(
+                "duration {:.3} s (timescale {})",
+                duration as f64 / timescale.max(1) as f64,
+                timescale
+            );
+            if kind == b"mdhd" {
+                summary += &format!(", language {}", get_language(be_u16(payload, after)?));
+            }
+            Some(summary)
+        }
+        b"tkhd" => {
+            let (id, duration, width, height, flags) = get_track_header(payload)?;
+            Some(format!(
+                "track {}, duration {}, {}x{}, flags 0x{:X}",
+                id, duration, width, height, flags
+            ))
+        }
+        b"hdlr" => {
+            let name = payload.get(24..).unwrap_or(&[]);
+            let name = String::from_utf8_lossy(name);
+            Some(format!(
+                "{} \"{}\"",
+                fourcc(payload.get(8..12)?),
+                name.trim_matches('\0')
+            ))
+        }
+        b"avcC" => Some(format!(
+            "profile {}, level {}",
+            payload.get(1)?,
+            *payload.get(3)? as f32 / 10.0
+        )),
+        b"hvcC" => Some(format!(
+            "profile {}, level {}",
+            payload.get(1)? & 0x1F,
+            *payload.get(12)? as f32 / 30.0
+        )),
+        b"ispe" => Some(format!("{}x{}", be_u32(payload, 4)?, be_u32(payload, 8)?)),
+        b"pitm" => Some(format!("primary item {}", be_u16(payload, 4)?)),
+        b"infe" if payload.first()? >= &2 => Some(format!(
+            "item {}, type {}",
+            be_u16(payload, 4)?,
+            fourcc(payload.get(8..12)?)
+        )),
+        b"mehd" => Some(format!("fragment duration {}", be_u32(payload, 4)?)),
+        b"mfhd" => Some(format!("sequence {}", be_u32(payload, 4)?)),
+        _ if VISUAL_ENTRIES.contains(&kind) => {
+            Some(format!("{}x{}", be_u16(payload, 24)?, be_u16(payload, 26)?))
+        }
+        _ if AUDIO_ENTRIES.contains(&kind) => Some(format!(
+            "{} channel(s), {} bit, {} Hz",
+            be_u16(payload, 16)?,
+            be_u16(payload, 18)?,
+            be_u32(payload, 24)? >> 16
+        )),
+        _ => None,
+    }
+}
+
+/// Parses boxes between `start` and `end` offsets of data.
+pub fn get_boxes(data: &[u8], start: usize, end: usize, depth: usize) -> Vec<BmffBox> {
+    let mut boxes = Vec::new();
+    let mut pos = start;
+    while pos + 8 <= end && !is_timed_out() {
+        let mut size = be_u32(data, pos).unwrap_or(0) as usize;
+        let kind: [u8; 4] = data[pos + 4..pos + 8].try_into().unwrap();
+        let mut header = 8;
+        if size == 1 {
+            size = be_u64(data, pos + 8).unwrap_or(0) as usize;
+            header = 16;
+        } else if size == 0 {
+            // Box extends to the end of file.
+            size = end - pos;
+        }
+        if &kind == b"uuid" {
+            header += 16;
+        }
+        // Large size comes from file, so it's compared without overflowing.
+        if size < header || size > end - pos {
+            // Broken or truncated box, keep it so it shows in the tree.
+            size = size.max(header).min(end - pos);
+        }
+        let mut item = BmffBox {
+            kind,
+            offset: pos,
+            size,
+            header,
+            children: Vec::new(),
+            summary: None,
+        };
+        let payload = item.payload(data);
+        item.summary = describe_box(&kind, payload);
+        if let Some(offset) = children_offset(&kind, payload) {
+            let children_start = pos + header + offset;
+            if children_start < pos + size && allow_depth(depth) {
+                item.children = get_boxes(data, children_start, pos + size, depth + 1);
+            }
+        }
+        boxes.push(item);
+        if size == 0 {
+            break;
+        }
+        pos += size;
+    }
+    boxes
+}
+
+/// Gets (major brand, minor version, compatible brands) from ftyp box.
+pub fn get_brands(data: &[u8]) -> Option<(String, u32, Vec<String>)> {
+    if data.get(4..8)? != b"ftyp" {
+        return None;
+    }
+    let size = (be_u32(data, 0)? as usize).min(data.len());
+    Some((
+        fourcc(data.get(8..12)?),
+        be_u32(data, 12)?,
+        data.get(16..size)?.chunks_exact(4).map(fourcc).collect(),
+    ))
+}
+
+/// Gets extension id of ISO base media file by its brands.
+pub fn get_bmff_id(major: &str, compatible: &[String]) -> String {
+    let has = |brand: &str| major == brand || compatible.iter().any(|b| b == brand);
+    if has("avif") || has("avis") {
+        "avif"
+    } else if has("heic") || has("heix") || has("heim") || has("heis") || has("hevc") {
+        "heic"
+    } else if has("mif1") || has("msf1") {
+        "heif"
+    } else if major == "qt  " {
+        "mov"
+    } else if major.starts_with("M4A") {
+        "m4a"
+    } else if major.starts_with("3g") {
+        "3gp"
+    } else {
+        "mp4"
+    }
+    .to_string()
+}
+
+/// Is ISO base media file one of its more specific variants (HEIC, AVIF, MOV...).
+/// Returns extension found by brands of ftyp box.
+pub fn get_complex_bmff_extension(args: &Arguments, input: &Input) -> Result<String, Error> {
+    let header = &input.bytes()[..input.bytes().len().min(4096)];
+    match get_brands(header) {
+        Some((major, _, compatible)) => {
+            get_extension_from_id(args, get_bmff_id(&major, &compatible))
+        }
+        None => Err(Error::new(ErrorKind::InvalidData, "no ftyp box")),
+    }
+}
+
+/// Collects track summaries from moov box.
+pub fn get_tracks(data: &[u8], moov: &BmffBox) -> Vec<Track> {
+    let mut tracks = Vec::new();
+    for trak in moov.children.iter().filter(|c| &c.kind == b"trak") {
+        let header = trak
+            .child(b"tkhd")
+            .and_then(|b| get_track_header(b.payload(data)));
+        let mdhd = trak.find(&[b"mdia", b"mdhd"]).map(|b| b.payload(data));
+        let times = mdhd.and_then(get_header_times);
+        let entry = trak
+            .find(&[b"mdia", b"minf", b"stbl", b"stsd"])
+            .and_then(|b| b.children.first());
+        let dimensions = match (header, entry) {
+            (Some((_, _, w, h, _)), _) if w > 0 && h > 0 => Some((w, h)),
+            (_, Some(entry)) if VISUAL_ENTRIES.contains(&&entry.kind) => {
+                let payload = entry.payload(data);
+                Some((
+                    be_u16(payload, 24).unwrap_or(0) as u32,
+                    be_u16(payload, 26).unwrap_or(0) as u32,
+                ))
+            }
+            _ => None,
+        };
+        tracks.push(Track {
+            id: header.map(|h| h.0).unwrap_or(0),
+            handler: trak
+                .find(&[b"mdia", b"hdlr"])
+                .and_then(|b| b.payload(data).get(8..12).map(fourcc))
+                .unwrap_or("????".to_string()),
+            codec: entry.map(|e| e.kind()),
+            duration: times.map(|(_, _, scale, duration, _)| duration as f64 / scale.max(1) as f64),
+            dimensions,
+            language: match (mdhd, times) {
+                (Some(mdhd), Some((_, _, _, _, after))) => be_u16(mdhd, after).map(get_language),
+                _ => None,
+            },
+            enabled: header.map(|h| h.4 & 1 != 0).unwrap_or(false),
+        });
+    }
+    tracks
+}
+
+/// Decodes value of iTunes "data" box.
+fn get_data_value(item: &BmffBox, data: &[u8], key: &str) -> Option<String> {
+    let payload = item.child(b"data")?.payload(data);
+    let kind = be_u32(payload, 0)? & 0x00FF_FFFF;
+    let value = payload.get(8..)?;
+    Some(match kind {
+        1 => String::from_utf8_lossy(value).to_string(),
+        13 => format!("JPEG image, {} bytes", value.len()),
+        14 => format!("PNG image, {} bytes", value.len()),
+        21 | 22 => value
+            .iter()
+            .fold(0i64, |acc, b| (acc << 8) | *b as i64)
+            .to_string(),
+        // Track and disc numbers are stored as binary (pad, number, total).
+        0 if (key == "trkn" || key == "disk") && value.len() >= 6 => {
+            format!("{}/{}", be_u16(value, 2)?, be_u16(value, 4)?)
+        }
+        _ => format!("{} bytes of type {}", value.len(), kind),
+    })
+}
+
+/// Gets human name of common iTunes and QuickTime tag keys.
+fn tag_name(key: &str) -> &str {
+    match key {
+        "©nam" => "Title",
+        "©ART" => "Artist",
+        "aART" => "Album artist",
+        "©alb" => "Album",
+        "©day" => "Date",
+        "©gen" | "gnre" => "Genre",
+        "©too" => "Encoder",
+        "©cmt" => "Comment",
+        "©wrt" => "Composer",
+        "©xyz" | "com.apple.quicktime.location.ISO6709" => "GPS location",
+        "com.apple.quicktime.make" | "©mak" => "Make",
+        "com.apple.quicktime.model" | "©mod" => "Model",
+        "com.apple.quicktime.software" | "©swr" => "Software",
+        "com.apple.quicktime.creationdate" => "Creation date",
+        "trkn" => "Track",
+        "disk" => "Disc",
+        "covr" => "Cover art",
+        "cprt" | "©cpy" => "Copyright",
+        _ => key,
+    }
+}
+
+/// Reads tags from meta box: iTunes ilst items, with mdta keys if present.
+fn get_meta_tags(meta: &BmffBox, data: &[u8], tags: &mut Vec<(String, String)>) {
+    let mut keys: Vec<String> = Vec::new();
+    if let Some(keys_box) = meta.child(b"keys") {
+        let payload = keys_box.payload(data);
+        let mut pos = 8;
+        while let Some(size) = be_u32(payload, pos) {
+            let Some(key) = payload.get(pos + 8..pos + size as usize) else {
+                break;
+            };
+            keys.push(String::from_utf8_lossy(key).to_string());
+            pos += (size as usize).max(8);
+        }
+    }
+    let Some(ilst) = meta.child(b"ilst") else {
+        return;
+    };
+    for item in ilst.children.iter() {
+        // Items are not containers anywhere else, so their data boxes are read here.
+        let item = BmffBox {
+            kind: item.kind,
+            offset: item.offset,
+            size: item.size,
+            header: item.header,
+            children: get_boxes(data, item.offset + item.header, item.offset + item.size, 16),
+            summary: None,
+        };
+        let key = if &item.kind == b"----" {
+            // Freeform item: mean, name and data boxes.
+            let name = item
+                .child(b"name")
+                .map(|b| b.payload(data).get(4..).unwrap_or(&[]));
+            String::from_utf8_lossy(name.unwrap_or(b"----")).to_string()
+        } else if !keys.is_empty() && item.kind[0] == 0 {
+            let index = u32::from_be_bytes(item.kind) as usize;
+            keys.get(index.wrapping_sub(1))
+                .cloned()
+                .unwrap_or(item.kind())
+        } else {
+            item.kind()
+        };
+        if let Some(value) = get_data_value(&item, data, &key) {
+            tags.push((tag_name(&key).to_string(), value));
+        }
+    }
+}
+
+/// Collects iTunes ilst tags and QuickTime user data strings (like ©xyz GPS location).
+pub fn get_tags(data: &[u8], moov: &BmffBox) -> Vec<(String, String)> {
+    let mut tags = Vec::new();
+    if let Some(udta) = moov.child(b"udta") {
+        for item in udta.children.iter() {
+            if item.kind[0] != 0xA9 {
+                continue;
+            }
+            // QuickTime text: 16-bit length, 16-bit language, string.
+            let payload = item.payload(data);
+            let length = be_u16(payload, 0).unwrap_or(0) as usize;
+            if let Some(text) = payload.get(4..4 + length) {
+                let key = item.kind();
+                tags.push((
+                    tag_name(&key).to_string(),
+                    String::from_utf8_lossy(text).to_string(),
+                ));
+            }
+        }
+        if let Some(meta) = udta.child(b"meta") {
+            get_meta_tags(meta, data, &mut tags);
+        }
+    }
+    if let Some(meta) = moov.child(b"meta") {
+        get_meta_tags(meta, data, &mut tags);
+    }
+    tags
+}
+
+fn print_tree(args: &Arguments, data: &[u8], boxes: &[BmffBox], depth: usize, report: &mut Report) {
+    for item in boxes.iter() {
+        write!(
+            report,
+            "{}{} (offset {}, size {})",
+            "  ".repeat(depth),
+            item.kind(),
+            item.offset,
+            format_size(args, item.size as u64)
+        );
+        if let Some(summary) = &item.summary {
+            write!(report, ": {}", summary);
+        }
+        if let Some(times) = format_header_times(args, data, item) {
+            write!(report, ", {}", times);
+        }
+        writeln!(report);
+        print_tree(args, data, &item.children, depth + 1, report);
+    }
+}
+
+/// Gets specified ISO base media (MP4/MOV/HEIF) info about file.
+pub fn get_isobmff_info(args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## ISO base media information");
+    let data = input.bytes();
+    let boxes = get_boxes(data, 0, data.len(), 0);
+    if boxes.is_empty() {
+        writeln!(report, "Error: no boxes found");
+        return;
+    }
+
+    match get_brands(data) {
+        Some((major, minor, compatible)) => {
+            writeln!(report, "# Major brand: {} (minor version {})", major, minor);
+            writeln!(report, "# Compatible brands: {}", compatible.join(" "));
+            writeln!(
+                report,
+                "# Detected type: {}",
+                get_bmff_id(&major, &compatible)
+            );
+        }
+        None => writeln!(report, "# No ftyp box (old QuickTime file?)"),
+    }
+
+    let top: Vec<&[u8; 4]> = boxes.iter().map(|b| &b.kind).collect();
+    let moov = top.iter().position(|k| k == &b"moov");
+    let mdat = top.iter().position(|k| k == &b"mdat");
+    let fragments = top.iter().filter(|k| k == &&b"moof").count();
+    let moov_box = moov.map(|i| &boxes[i]);
+    if fragments > 0 || moov_box.and_then(|m| m.child(b"mvex")).is_some() {
+        writeln!(
+            report,
+            "# Layout: fragmented ({} movie fragment(s))",
+            fragments
+        );
+    } else {
+        match (moov, mdat) {
+            (Some(moov), Some(mdat)) if moov < mdat => {
+                writeln!(report, "# Layout: faststart (moov before mdat)")
+            }
+            (Some(_), Some(_)) => {
+                writeln!(
+                    report,
+                    "# Layout: moov after mdat (not optimized for streaming)"
+                )
+            }
+            _ => (),
+        }
+    }
+
+    if let Some(moov) = moov_box {
+        if let Some(mvhd) = moov.child(b"mvhd") {
+            if let Some(summary) = &mvhd.summary {
+                write!(report, "# Movie: {}", summary);
+                if let Some(times) = format_header_times(args, data, mvhd) {
+                    write!(report, ", {}", times);
+                }
+                writeln!(report);
+            }
+        }
+        for track in get_tracks(data, moov).iter() {
+            write!(
+                report,
+                "# Track {}: {} ({})",
+                track.id,
+                track.handler,
+                track.codec.as_deref().unwrap_or("no sample entry")
+            );
+            if let Some((width, height)) = track.dimensions {
+                write!(report, ", {}x{}", width, height);
+            }
+            if let Some(duration) = track.duration {
+                write!(report, ", {:.3} s", duration);
+            }
+            if let Some(language) = &track.language {
+                write!(report, ", language {}", language);
+            }
+            writeln!(report, "{}", if track.enabled { "" } else { ", disabled" });
+        }
+        for (key, value) in get_tags(data, moov).iter() {
+            writeln!(report, "# {}: {}", key, value);
+        }
+    }
+
+    // HEIF images keep items in top-level meta box.
+    if let Some(meta) = boxes.iter().find(|b| &b.kind == b"meta") {
+        if let Some(primary) = meta.child(b"pitm").and_then(|b| b.summary.as_ref()) {
+            writeln!(report, "# Image: {}", primary);
+        }
+        if let Some(iinf) = meta.child(b"iinf") {
+            writeln!(report, "# Items: {}", iinf.children.len());
+        }
+        if let Some(ipco) = meta.find(&[b"iprp", b"ipco"]) {
+            for ispe in ipco.children.iter().filter(|b| &b.kind == b"ispe") {
+                writeln!(
+                    report,
+                    "# Image size: {}",
+                    ispe.summary.as_deref().unwrap_or("unknown")
+                );
+            }
+        }
+    }
+
+    writeln!(report, "# Box tree:");
+    print_tree(args, data, &boxes, 0, report);
+}
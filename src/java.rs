@@ -0,0 +1,448 @@
+use crate::input::Input;
+use crate::limits::{allow_entry, limit_decompressed};
+use crate::report::Report;
+use crate::Arguments;
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Read, Seek};
+
+const CONSTANT_UTF8: u8 = 1;
+const CONSTANT_CLASS: u8 = 7;
+const CONSTANT_MODULE: u8 = 19;
+
+/// Entry of constant pool, only entries needed for names are kept.
+#[derive(Clone)]
+enum Constant {
+    Utf8(String),
+    /// Index of name
+    Class(u16),
+    /// Index of name
+    Module(u16),
+    Other,
+}
+
+#[derive(Default)]
+pub struct ClassInfo {
+    pub minor: u16,
+    pub major: u16,
+    pub access_flags: u16,
+    pub name: String,
+    pub super_name: Option<String>,
+    pub interfaces: Vec<String>,
+    pub fields: usize,
+    pub methods: usize,
+    pub source_file: Option<String>,
+    /// Names of class attributes, like Record or PermittedSubclasses.
+    pub attributes: Vec<String>,
+    /// Name of module from module-info.class
+    pub module: Option<String>,
+}
+
+/// Summary of classes in Java archive.
+#[derive(Default)]
+pub struct JarInfo {
+    pub manifest: Vec<(String, String)>,
+    pub classes: usize,
+    /// Class file major version and number of classes using it.
+    pub versions: BTreeMap<u16, usize>,
+    /// Multi-release version directory and number of classes in it.
+    pub release_versions: BTreeMap<u16, usize>,
+    pub module: Option<String>,
+    pub nested_archives: Vec<String>,
+    pub main_class_found: Option<bool>,
+    pub unreadable_classes: usize,
+}
+
+/// Gets Java release of class file major version.
+pub fn java_release(major: u16) -> String {
+    match major {
+        45 => "1.1".to_string(),
+        46..=48 => format!("1.{}", major - 44),
+        49.. => (major - 44).to_string(),
+        _ => "unknown".to_string(),
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn bytes(&mut self, length: usize) -> Option<&[u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(length)?)?;
+        self.pos += length;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+fn get_constant_pool(reader: &mut Reader) -> Option<Vec<Constant>> {
+    let count = reader.u16()? as usize;
+    // Index 0 is unused.
+    let mut pool = vec![Constant::Other];
+    while pool.len() < count {
+        let tag = reader.u8()?;
+        let constant = match tag {
+            CONSTANT_UTF8 => {
+                let length = reader.u16()? as usize;
+                // Modified UTF-8 differs only for null and supplementary characters.
+                Constant::Utf8(String::from_utf8_lossy(reader.bytes(length)?).to_string())
+            }
+            CONSTANT_CLASS => Constant::Class(reader.u16()?),
+            CONSTANT_MODULE => Constant::Module(reader.u16()?),
+            3 | 4 => {
+                reader.bytes(4)?;
+                Constant::Other
+            }
+            // Long and double take two entries.
+            5 | 6 => {
+                reader.bytes(8)?;
+                pool.push(Constant::Other);
+                Constant::Other
+            }
+            8 | 16 | 20 => {
+                reader.bytes(2)?;
+                Constant::Other
+            }
+            9 | 10 | 11 | 12 | 17 | 18 => {
+                reader.bytes(4)?;
+                Constant::Other
+            }
+            15 => {
+                reader.bytes(3)?;
+                Constant::Other
+            }
+            _ => return None,
+        };
+        pool.push(constant);
+    }
+    Some(pool)
+}
+
+fn get_utf8(pool: &[Constant], index: u16) -> Option<String> {
+    match pool.get(index as usize)? {
+        Constant::Utf8(text) => Some(text.clone()),
+        _ => None,
+    }
+}
+
+/// Gets class or module name, internal form with slashes is converted to dots.
+fn get_class_name(pool: &[Constant], index: u16) -> Option<String> {
+    match pool.get(index as usize)? {
+        Constant::Class(name) | Constant::Module(name) => {
+            get_utf8(pool, *name).map(|n| n.replace('/', "."))
+        }
+        _ => None,
+    }
+}
+
+/// Skips fields or methods, returns their count.
+fn skip_members(reader: &mut Reader) -> Option<usize> {
+    let count = reader.u16()? as usize;
+    for _ in 0..count {
+        reader.bytes(6)?;
+        for _ in 0..reader.u16()? {
+            reader.bytes(2)?;
+            let length = reader.u32()? as usize;
+            reader.bytes(length)?;
+        }
+    }
+    Some(count)
+}
+
+/// Gets version, names, flags and attributes of class file.
+pub fn get_class_structure(data: &[u8]) -> Result<ClassInfo, Error> {
+    if !data.starts_with(&[0xCA, 0xFE, 0xBA, 0xBE]) {
+        return Err(Error::new(ErrorKind::InvalidData, "missing CAFEBABE magic"));
+    }
+    let truncated = || Error::new(ErrorKind::UnexpectedEof, "truncated or invalid class file");
+    let mut reader = Reader { data, pos: 4 };
+    let mut info = ClassInfo {
+        minor: reader.u16().ok_or_else(truncated)?,
+        major: reader.u16().ok_or_else(truncated)?,
+        ..Default::default()
+    };
+    let pool = get_constant_pool(&mut reader).ok_or_else(truncated)?;
+    info.access_flags = reader.u16().ok_or_else(truncated)?;
+    info.name = get_class_name(&pool, reader.u16().ok_or_else(truncated)?).unwrap_or_default();
+    info.super_name = get_class_name(&pool, reader.u16().ok_or_else(truncated)?);
+    for _ in 0..reader.u16().ok_or_else(truncated)? {
+        let index = reader.u16().ok_or_else(truncated)?;
+        info.interfaces
+            .push(get_class_name(&pool, index).unwrap_or_default());
+    }
+    info.fields = skip_members(&mut reader).ok_or_else(truncated)?;
+    info.methods = skip_members(&mut reader).ok_or_else(truncated)?;
+    for _ in 0..reader.u16().ok_or_else(truncated)? {
+        let name = get_utf8(&pool, reader.u16().ok_or_else(truncated)?).unwrap_or_default();
+        let length = reader.u32().ok_or_else(truncated)? as usize;
+        let payload = reader.bytes(length).ok_or_else(truncated)?;
+        let index = || payload.get(..2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+        match name.as_str() {
+            "SourceFile" => info.source_file = index().and_then(|i| get_utf8(&pool, i)),
+            "Module" => info.module = index().and_then(|i| get_class_name(&pool, i)),
+            _ => (),
+        }
+        info.attributes.push(name);
+    }
+    Ok(info)
+}
+
+/// Gets names of set class access flags.
+pub fn get_access_flags(flags: u16) -> Vec<&'static str> {
+    [
+        (0x0001, "public"),
+        (0x0010, "final"),
+        (0x0020, "super"),
+        (0x0200, "interface"),
+        (0x0400, "abstract"),
+        (0x1000, "synthetic"),
+        (0x2000, "annotation"),
+        (0x4000, "enum"),
+        (0x8000, "module"),
+    ]
+    .iter()
+    .filter(|(flag, _)| flags & flag != 0)
+    .map(|(_, name)| *name)
+    .collect()
+}
+
+/// Gets main attributes of manifest, joining continuation lines.
+pub fn get_manifest_entries(manifest: &str) -> Vec<(String, String)> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for line in manifest.lines() {
+        if let Some(continuation) = line.strip_prefix(' ') {
+            if let Some((_, value)) = entries.last_mut() {
+                value.push_str(continuation);
+            }
+            continue;
+        }
+        // Empty line ends main section, per-entry sections follow.
+        if line.trim().is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            entries.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    entries
+}
+
+/// Gets class version summary, manifest and layout of Java archive.
+pub fn get_jar_structure<R: Read + Seek>(reader: R) -> Result<JarInfo, Error> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    let mut info = JarInfo::default();
+    let mut class_names = Vec::new();
+    for i in (0..archive.len()).take_while(|i| allow_entry(*i)) {
+        let Ok(mut file) = archive.by_index(i) else {
+            continue;
+        };
+        let name = file.name().to_string();
+        if name.eq_ignore_ascii_case("META-INF/MANIFEST.MF") {
+            let mut manifest = String::new();
+            if limit_decompressed(&mut file)
+                .read_to_string(&mut manifest)
+                .is_ok()
+            {
+                info.manifest = get_manifest_entries(&manifest);
+            }
+            continue;
+        }
+        if [".jar", ".war", ".rar"].iter().any(|e| name.ends_with(e)) {
+            info.nested_archives.push(name);
+            continue;
+        }
+        if !name.ends_with(".class") {
+            continue;
+        }
+        let release = name
+            .strip_prefix("META-INF/versions/")
+            .and_then(|rest| rest.split('/').next())
+            .and_then(|version| version.parse::<u16>().ok());
+        let mut header = [0u8; 8];
+        if name.ends_with("module-info.class") {
+            let mut data = Vec::new();
+            if let Ok(class) = limit_decompressed(&mut file)
+                .read_to_end(&mut data)
+                .and_then(|_| get_class_structure(&data))
+            {
+                info.module = info.module.or(class.module);
+            }
+            match data.get(..8) {
+                Some(start) => header.copy_from_slice(start),
+                None => {
+                    info.unreadable_classes += 1;
+                    continue;
+                }
+            }
+        } else if file.read_exact(&mut header).is_err() {
+            info.unreadable_classes += 1;
+            continue;
+        }
+        if header[..4] != [0xCA, 0xFE, 0xBA, 0xBE] {
+            info.unreadable_classes += 1;
+            continue;
+        }
+        let major = u16::from_be_bytes([header[6], header[7]]);
+        match release {
+            Some(release) => *info.release_versions.entry(release).or_insert(0) += 1,
+            None => {
+                info.classes += 1;
+                *info.versions.entry(major).or_insert(0) += 1;
+            }
+        }
+        class_names.push(name);
+    }
+    if let Some((_, main_class)) = info.manifest.iter().find(|(k, _)| k == "Main-Class") {
+        let path = format!("{}.class", main_class.replace('.', "/"));
+        // Web archives keep classes in WEB-INF/classes.
+        info.main_class_found = Some(
+            class_names
+                .iter()
+                .any(|name| name == &path || name.ends_with(&format!("/{}", path))),
+        );
+    }
+    Ok(info)
+}
+
+/// Gets specified Java class info about file.
+pub fn get_class_info(_args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## Java class information");
+    let data = input.bytes();
+    let info = match get_class_structure(data) {
+        Ok(info) => info,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
+    write!(
+        report,
+        "# Version: {}.{} (Java {}",
+        info.major,
+        info.minor,
+        java_release(info.major)
+    );
+    if info.minor == 0xFFFF {
+        write!(report, ", preview features");
+    }
+    writeln!(report, ")");
+    match &info.module {
+        Some(module) => writeln!(report, "# Module: {}", module),
+        None => writeln!(report, "# Class: {}", info.name),
+    }
+    writeln!(
+        report,
+        "# Access flags: {}",
+        get_access_flags(info.access_flags).join(", ")
+    );
+    if let Some(super_name) = &info.super_name {
+        writeln!(report, "# Superclass: {}", super_name);
+    }
+    if !info.interfaces.is_empty() {
+        writeln!(report, "# Interfaces: {}", info.interfaces.join(", "));
+    }
+    if info.attributes.iter().any(|a| a == "Record") {
+        writeln!(report, "# Record class");
+    }
+    if info.attributes.iter().any(|a| a == "PermittedSubclasses") {
+        writeln!(report, "# Sealed class");
+    }
+    writeln!(
+        report,
+        "# Fields: {}, methods: {}",
+        info.fields, info.methods
+    );
+    if let Some(source_file) = &info.source_file {
+        writeln!(report, "# Source file: {}", source_file);
+    }
+    writeln!(report, "# Attributes: {}", info.attributes.join(", "));
+}
+
+/// Gets specified Java archive info about file.
+pub fn get_jar_info(_args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## Java archive information");
+    let info = match get_jar_structure(input.reader()) {
+        Ok(info) => info,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
+    if info.manifest.is_empty() {
+        writeln!(report, "# Manifest: none");
+    } else {
+        writeln!(report, "# Manifest:");
+        for (key, value) in info.manifest.iter() {
+            writeln!(report, "{}: {}", key, value);
+        }
+    }
+    match info.main_class_found {
+        Some(true) => writeln!(report, "# Main class: found in archive"),
+        Some(false) => writeln!(report, "# WARNING: Main-Class is not in archive"),
+        None => (),
+    }
+    writeln!(report, "# Classes: {}", info.classes);
+    for (major, count) in info.versions.iter() {
+        writeln!(
+            report,
+            "Java {} ({}): {}",
+            java_release(*major),
+            major,
+            count
+        );
+    }
+    if let Some(major) = info.versions.keys().max() {
+        writeln!(
+            report,
+            "# Minimal Java version: {} (class file version {})",
+            java_release(*major),
+            major
+        );
+    }
+    if !info.release_versions.is_empty() {
+        let declared = info
+            .manifest
+            .iter()
+            .any(|(k, v)| k == "Multi-Release" && v.eq_ignore_ascii_case("true"));
+        writeln!(report, "# Multi-release:");
+        for (release, count) in info.release_versions.iter() {
+            writeln!(report, "META-INF/versions/{}: {} classes", release, count);
+        }
+        if !declared {
+            writeln!(
+                report,
+                "# WARNING: META-INF/versions is present but Multi-Release is not set in manifest"
+            );
+        }
+    }
+    match &info.module {
+        Some(module) => writeln!(report, "# Module: {} (module-info.class)", module),
+        None => writeln!(report, "# Module: none (classpath or automatic module)"),
+    }
+    if info.unreadable_classes > 0 {
+        writeln!(
+            report,
+            "# Unreadable class files: {}",
+            info.unreadable_classes
+        );
+    }
+    if !info.nested_archives.is_empty() {
+        writeln!(report, "# Nested archives: {}", info.nested_archives.len());
+        for archive in info.nested_archives.iter() {
+            writeln!(report, "{}", archive);
+        }
+    }
+}
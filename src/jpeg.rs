@@ -0,0 +1,827 @@
+use crate::datetime::format_parsed_datetime;
+use crate::input::Input;
+use crate::report::Report;
+use crate::{format_size, Arguments};
+use exif::{DateTime, Exif, Field, In, Reader, Tag, Value};
+use std::io::{Error, ErrorKind};
+use time::{PrimitiveDateTime, UtcOffset};
+
+/// Maps zigzag position of a quantization table to natural (row-major) position.
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// Standard luminance quantization table (JPEG spec, Annex K), natural order.
+const STD_LUMINANCE: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+/// Standard chrominance quantization table (JPEG spec, Annex K), natural order.
+const STD_CHROMINANCE: [u16; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99, 99,
+    47, 66, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+/// One marker segment of JPEG file.
+pub struct Segment {
+    pub marker: u8,
+    /// Offset of the 0xFF byte of the marker.
+    pub offset: usize,
+    /// Value of the length field (0 for standalone markers like SOI, EOI or RSTn).
+    pub length: usize,
+}
+
+impl Segment {
+    /// Segment payload without the length field.
+    fn payload<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        if self.length < 2 {
+            return &[];
+        }
+        let start = self.offset + 4;
+        let end = (self.offset + 2 + self.length).min(data.len());
+        &data[start.min(end)..end]
+    }
+}
+
+pub struct Frame {
+    pub marker: u8,
+    pub precision: u8,
+    pub width: u16,
+    pub height: u16,
+    /// (component id, horizontal sampling, vertical sampling, quantization table id)
+    pub components: Vec<(u8, u8, u8, u8)>,
+}
+
+pub struct QuantizationTable {
+    pub id: u8,
+    pub is_16bit: bool,
+    /// Values in zigzag order as stored in the file.
+    pub values: [u16; 64],
+}
+
+pub struct Thumbnail {
+    pub source: &'static str,
+    pub size: usize,
+    pub dimensions: Option<(u16, u16)>,
+}
+
+pub struct MpEntry {
+    pub attribute: u32,
+    pub size: u32,
+    /// Absolute offset in file (first image is always at 0).
+    pub offset: usize,
+}
+
+pub struct IccProfile {
+    /// Count of chunks found, broken file can have more than 255.
+    pub chunks: usize,
+    pub expected_chunks: u8,
+    pub data: Vec<u8>,
+}
+
+/// Everything collected from single pass over JPEG segments.
+#[derive(Default)]
+pub struct JpegInfo {
+    pub segments: Vec<Segment>,
+    /// Offset right after EOI marker, if EOI was found.
+    pub end_offset: Option<usize>,
+    pub frame: Option<Frame>,
+    pub quantization_tables: Vec<QuantizationTable>,
+    pub jfif: Option<String>,
+    pub adobe: Option<String>,
+    pub comments: Vec<String>,
+    pub exif: Option<Vec<u8>>,
+    pub xmp_sizes: Vec<usize>,
+    pub extended_xmp_size: usize,
+    pub icc: Option<IccProfile>,
+    pub mp_entries: Vec<MpEntry>,
+    pub thumbnails: Vec<Thumbnail>,
+}
+
+fn be16(data: &[u8], offset: usize) -> usize {
+    u16::from_be_bytes([data[offset], data[offset + 1]]) as usize
+}
+
+fn be32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// Gets name of JPEG marker.
+pub fn marker_name(marker: u8) -> String {
+    match marker {
+        0xC4 => "DHT".to_string(),
+        0xC8 => "JPG".to_string(),
+        0xCC => "DAC".to_string(),
+        0xC0..=0xCF => format!("SOF{}", marker - 0xC0),
+        0xD0..=0xD7 => format!("RST{}", marker - 0xD0),
+        0xD8 => "SOI".to_string(),
+        0xD9 => "EOI".to_string(),
+        0xDA => "SOS".to_string(),
+        0xDB => "DQT".to_string(),
+        0xDC => "DNL".to_string(),
+        0xDD => "DRI".to_string(),
+        0xDE => "DHP".to_string(),
+        0xDF => "EXP".to_string(),
+        0xE0..=0xEF => format!("APP{}", marker - 0xE0),
+        0xFE => "COM".to_string(),
+        _ => format!("0xFF{:02X}", marker),
+    }
+}
+
+/// Gets coding process of SOFn marker.
+pub fn frame_type(marker: u8) -> &'static str {
+    match marker {
+        0xC0 => "Baseline DCT",
+        0xC1 => "Extended sequential DCT (Huffman)",
+        0xC2 => "Progressive DCT (Huffman)",
+        0xC3 => "Lossless (Huffman)",
+        0xC5 => "Differential sequential DCT (Huffman)",
+        0xC6 => "Differential progressive DCT (Huffman)",
+        0xC7 => "Differential lossless (Huffman)",
+        0xC9 => "Extended sequential DCT (arithmetic)",
+        0xCA => "Progressive DCT (arithmetic)",
+        0xCB => "Lossless (arithmetic)",
+        0xCD => "Differential sequential DCT (arithmetic)",
+        0xCE => "Differential progressive DCT (arithmetic)",
+        0xCF => "Differential lossless (arithmetic)",
+        _ => "unknown",
+    }
+}
+
+fn is_frame_marker(marker: u8) -> bool {
+    (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker)
+}
+
+/// Walks marker segments of JPEG data, skipping entropy-coded data after SOS.
+/// Returns error only if data does not start with SOI.
+pub fn get_segments(data: &[u8]) -> Result<(Vec<Segment>, Option<usize>), Error> {
+    if data.len() < 4 || data[0] != 0xFF || data[1] != 0xD8 {
+        return Err(Error::new(ErrorKind::InvalidData, "missing SOI marker"));
+    }
+    let mut segments = vec![Segment {
+        marker: 0xD8,
+        offset: 0,
+        length: 0,
+    }];
+    let mut pos = 2;
+    while pos + 1 < data.len() {
+        if data[pos] != 0xFF {
+            // Garbage between segments, nothing reliable can be read from here.
+            break;
+        }
+        // Any number of 0xFF fill bytes can precede a marker.
+        while pos + 2 < data.len() && data[pos + 1] == 0xFF {
+            pos += 1;
+        }
+        let marker = data[pos + 1];
+        if marker == 0xD9 {
+            segments.push(Segment {
+                marker,
+                offset: pos,
+                length: 0,
+            });
+            return Ok((segments, Some(pos + 2)));
+        }
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            segments.push(Segment {
+                marker,
+                offset: pos,
+                length: 0,
+            });
+            pos += 2;
+            continue;
+        }
+        if pos + 4 > data.len() {
+            break;
+        }
+        let length = be16(data, pos + 2);
+        segments.push(Segment {
+            marker,
+            offset: pos,
+            length,
+        });
+        pos += 2 + length;
+        if marker == 0xDA {
+            // Skip entropy-coded data: 0xFF00 is stuffed byte, RSTn and fill bytes belong to scan.
+            while pos + 1 < data.len() {
+                if data[pos] == 0xFF {
+                    let next = data[pos + 1];
+                    if next == 0xFF {
+                        pos += 1;
+                        continue;
+                    }
+                    if next == 0x00 || (0xD0..=0xD7).contains(&next) {
+                        pos += 2;
+                        continue;
+                    }
+                    break;
+                }
+                pos += 1;
+            }
+        }
+    }
+    Ok((segments, None))
+}
+
+/// Gets frame dimensions from first SOFn segment of JPEG data.
+pub fn get_dimensions(data: &[u8]) -> Option<(u16, u16)> {
+    let (segments, _) = get_segments(data).ok()?;
+    let sof = segments.iter().find(|s| is_frame_marker(s.marker))?;
+    let payload = sof.payload(data);
+    if payload.len() < 5 {
+        return None;
+    }
+    Some((be16(payload, 3) as u16, be16(payload, 1) as u16))
+}
+
+/// Estimates libjpeg-style quality (1-100) by comparing table to scaled standard table.
+pub fn estimate_quality(table: &QuantizationTable) -> u8 {
+    let standard = if table.id == 0 {
+        &STD_LUMINANCE
+    } else {
+        &STD_CHROMINANCE
+    };
+    let mut sum = 0.0;
+    for (i, value) in table.values.iter().enumerate() {
+        sum += *value as f64 * 100.0 / standard[ZIGZAG[i]] as f64;
+    }
+    let scale = sum / 64.0;
+    let quality = if scale <= 100.0 {
+        (200.0 - scale) / 2.0
+    } else {
+        5000.0 / scale
+    };
+    quality.round().clamp(1.0, 100.0) as u8
+}
+
+fn parse_frame(marker: u8, payload: &[u8]) -> Option<Frame> {
+    if payload.len() < 6 {
+        return None;
+    }
+    let count = payload[5] as usize;
+    let mut components = Vec::new();
+    for i in 0..count {
+        let at = 6 + i * 3;
+        if at + 3 > payload.len() {
+            break;
+        }
+        components.push((
+            payload[at],
+            payload[at + 1] >> 4,
+            payload[at + 1] & 0x0F,
+            payload[at + 2],
+        ));
+    }
+    Some(Frame {
+        marker,
+        precision: payload[0],
+        height: be16(payload, 1) as u16,
+        width: be16(payload, 3) as u16,
+        components,
+    })
+}
+
+fn parse_quantization_tables(payload: &[u8], tables: &mut Vec<QuantizationTable>) {
+    let mut pos = 0;
+    while pos < payload.len() {
+        let is_16bit = payload[pos] >> 4 != 0;
+        let id = payload[pos] & 0x0F;
+        pos += 1;
+        let size = if is_16bit { 128 } else { 64 };
+        if pos + size > payload.len() {
+            return;
+        }
+        let mut values = [0u16; 64];
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = if is_16bit {
+                be16(payload, pos + i * 2) as u16
+            } else {
+                payload[pos + i] as u16
+            };
+        }
+        pos += size;
+        tables.retain(|t| t.id != id);
+        tables.push(QuantizationTable {
+            id,
+            is_16bit,
+            values,
+        });
+    }
+}
+
+fn parse_jfif(payload: &[u8], thumbnails: &mut Vec<Thumbnail>) -> Option<String> {
+    if payload.starts_with(b"JFIF\0") && payload.len() >= 14 {
+        let units = match payload[7] {
+            0 => "no units (aspect ratio)",
+            1 => "dots per inch",
+            2 => "dots per cm",
+            _ => "unknown units",
+        };
+        let (thumb_w, thumb_h) = (payload[12] as u16, payload[13] as u16);
+        if thumb_w > 0 && thumb_h > 0 {
+            thumbnails.push(Thumbnail {
+                source: "JFIF (RGB)",
+                size: thumb_w as usize * thumb_h as usize * 3,
+                dimensions: Some((thumb_w, thumb_h)),
+            });
+        }
+        return Some(format!(
+            "version {}.{:02}, density {}x{} {}",
+            payload[5],
+            payload[6],
+            be16(payload, 8),
+            be16(payload, 10),
+            units
+        ));
+    }
+    if payload.starts_with(b"JFXX\0") && payload.len() >= 6 {
+        let thumb = &payload[6..];
+        let (source, dimensions) = match payload[5] {
+            0x10 => ("JFXX (JPEG)", get_dimensions(thumb)),
+            0x11 => (
+                "JFXX (palette)",
+                thumb.get(..2).map(|d| (d[0] as u16, d[1] as u16)),
+            ),
+            0x13 => (
+                "JFXX (RGB)",
+                thumb.get(..2).map(|d| (d[0] as u16, d[1] as u16)),
+            ),
+            _ => ("JFXX (unknown)", None),
+        };
+        thumbnails.push(Thumbnail {
+            source,
+            size: thumb.len(),
+            dimensions,
+        });
+    }
+    None
+}
+
+fn parse_adobe(payload: &[u8]) -> Option<String> {
+    if !payload.starts_with(b"Adobe") || payload.len() < 12 {
+        return None;
+    }
+    let transform = match payload[11] {
+        0 => "none (RGB or CMYK)",
+        1 => "YCbCr",
+        2 => "YCCK",
+        _ => "unknown",
+    };
+    Some(format!(
+        "version {}, flags 0x{:04X}/0x{:04X}, color transform: {}",
+        be16(payload, 5),
+        be16(payload, 7),
+        be16(payload, 9),
+        transform
+    ))
+}
+
+/// Reads MP Index IFD of APP2 "MPF" segment. `tiff_offset` is absolute offset of the TIFF header.
+fn parse_mpf(tiff: &[u8], tiff_offset: usize) -> Vec<MpEntry> {
+    let mut entries = Vec::new();
+    if tiff.len() < 8 {
+        return entries;
+    }
+    let le = &tiff[..2] == b"II";
+    let u16_at = |o: usize| -> Option<u32> {
+        let b = tiff.get(o..o + 2)?;
+        Some(if le {
+            u16::from_le_bytes([b[0], b[1]])
+        } else {
+            u16::from_be_bytes([b[0], b[1]])
+        } as u32)
+    };
+    let u32_at = |o: usize| -> Option<u32> {
+        let b: [u8; 4] = tiff.get(o..o + 4)?.try_into().ok()?;
+        Some(if le {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    };
+    let Some(ifd) = u32_at(4).map(|o| o as usize) else {
+        return entries;
+    };
+    let count = u16_at(ifd).unwrap_or(0) as usize;
+    for i in 0..count {
+        let entry = ifd + 2 + i * 12;
+        // 0xB002 is MP Entry tag, 16 bytes per image.
+        if u16_at(entry) != Some(0xB002) {
+            continue;
+        }
+        let length = u32_at(entry + 4).unwrap_or(0) as usize;
+        let Some(values) = u32_at(entry + 8).map(|o| o as usize) else {
+            break;
+        };
+        for j in 0..length / 16 {
+            let at = values + j * 16;
+            let (Some(attribute), Some(size), Some(offset)) =
+                (u32_at(at), u32_at(at + 4), u32_at(at + 8))
+            else {
+                break;
+            };
+            let offset = if offset == 0 {
+                0
+            } else {
+                tiff_offset + offset as usize
+            };
+            entries.push(MpEntry {
+                attribute,
+                size,
+                offset,
+            });
+        }
+    }
+    entries
+}
+
+/// Gets MP image type from MP entry attribute.
+pub fn mp_type(attribute: u32) -> &'static str {
+    match attribute & 0x00FF_FFFF {
+        0x030000 => "Baseline MP primary image",
+        0x010001 => "Large thumbnail (VGA)",
+        0x010002 => "Large thumbnail (full HD)",
+        0x020001 => "Multi-frame panorama",
+        0x020002 => "Multi-frame disparity",
+        0x020003 => "Multi-frame multi-angle",
+        _ => "Undefined",
+    }
+}
+
+/// Collects info about JPEG structure: segments, frame, tables, APP segments and thumbnails.
+pub fn get_jpeg_structure(data: &[u8]) -> Result<JpegInfo, Error> {
+    let (segments, end_offset) = get_segments(data)?;
+    let mut info = JpegInfo {
+        end_offset,
+        ..Default::default()
+    };
+    let mut icc_chunks: Vec<(u8, u8, Vec<u8>)> = Vec::new();
+
+    for segment in segments.iter() {
+        let payload = segment.payload(data);
+        match segment.marker {
+            m if is_frame_marker(m) && info.frame.is_none() => {
+                info.frame = parse_frame(m, payload);
+            }
+            0xDB => parse_quantization_tables(payload, &mut info.quantization_tables),
+            0xE0 => {
+                if let Some(jfif) = parse_jfif(payload, &mut info.thumbnails) {
+                    info.jfif = Some(jfif);
+                }
+            }
+            0xE1 => {
+                if payload.starts_with(b"Exif\0\0") && info.exif.is_none() {
+                    info.exif = Some(payload[6..].to_vec());
+                } else if payload.starts_with(b"http://ns.adobe.com/xap/1.0/\0") {
+                    info.xmp_sizes.push(payload.len() - 29);
+                } else if payload.starts_with(b"http://ns.adobe.com/xmp/extension/\0")
+                    && payload.len() >= 75
+                {
+                    // GUID (32), full length (4), chunk offset (4), then chunk data.
+                    info.extended_xmp_size = be32(payload, 67) as usize;
+                }
+            }
+            0xE2 => {
+                if payload.starts_with(b"ICC_PROFILE\0") && payload.len() >= 14 {
+                    icc_chunks.push((payload[12], payload[13], payload[14..].to_vec()));
+                } else if payload.starts_with(b"MPF\0") {
+                    info.mp_entries = parse_mpf(&payload[4..], segment.offset + 8);
+                }
+            }
+            0xEE => {
+                if let Some(adobe) = parse_adobe(payload) {
+                    info.adobe = Some(adobe);
+                }
+            }
+            0xFE => info
+                .comments
+                .push(String::from_utf8_lossy(payload).to_string()),
+            _ => (),
+        }
+    }
+
+    if !icc_chunks.is_empty() {
+        icc_chunks.sort_by_key(|(seq, _, _)| *seq);
+        let expected_chunks = icc_chunks[0].1;
+        let chunks = icc_chunks.len();
+        let data = icc_chunks.into_iter().flat_map(|(_, _, d)| d).collect();
+        info.icc = Some(IccProfile {
+            chunks,
+            expected_chunks,
+            data,
+        });
+    }
+
+    if let Some(exif) = &info.exif {
+        if let Ok(exif) = Reader::new().read_raw(exif.clone()) {
+            let offset = exif.get_field(Tag::JPEGInterchangeFormat, In::THUMBNAIL);
+            let length = exif.get_field(Tag::JPEGInterchangeFormatLength, In::THUMBNAIL);
+            if let (Some(offset), Some(length)) = (offset, length) {
+                let offset = offset.value.get_uint(0).unwrap_or(0) as usize;
+                let size = length.value.get_uint(0).unwrap_or(0) as usize;
+                let dimensions = exif
+                    .buf()
+                    .get(offset..offset + size)
+                    .and_then(get_dimensions);
+                info.thumbnails.push(Thumbnail {
+                    source: "EXIF IFD1",
+                    size,
+                    dimensions,
+                });
+            }
+        }
+    }
+
+    for entry in info.mp_entries.iter().skip(1) {
+        let dimensions = data
+            .get(entry.offset..entry.offset + entry.size as usize)
+            .and_then(get_dimensions);
+        info.thumbnails.push(Thumbnail {
+            source: mp_type(entry.attribute),
+            size: entry.size as usize,
+            dimensions,
+        });
+    }
+
+    info.segments = segments;
+    Ok(info)
+}
+
+/// Gets color space signature, device class and description of ICC profile.
+fn get_icc_summary(profile: &[u8]) -> Option<String> {
+    if profile.len() < 132 || &profile[36..40] != b"acsp" {
+        return None;
+    }
+    let sig = |o: usize| {
+        String::from_utf8_lossy(&profile[o..o + 4])
+            .trim()
+            .to_string()
+    };
+    let mut summary = format!(
+        "v{}.{}, class {}, color space {}, PCS {}",
+        profile[8],
+        profile[9] >> 4,
+        sig(12),
+        sig(16),
+        sig(20)
+    );
+    let tag_count = be32(profile, 128) as usize;
+    for i in 0..tag_count {
+        let entry = 132 + i * 12;
+        if entry + 12 > profile.len() {
+            break;
+        }
+        if &profile[entry..entry + 4] != b"desc" {
+            continue;
+        }
+        let offset = be32(profile, entry + 4) as usize;
+        let Some(tag) = profile.get(offset..offset + be32(profile, entry + 8) as usize) else {
+            break;
+        };
+        let description = if tag.starts_with(b"desc") && tag.len() >= 12 {
+            let length = (be32(tag, 8) as usize).min(tag.len() - 12);
+            String::from_utf8_lossy(&tag[12..12 + length])
+                .trim_end_matches('\0')
+                .to_string()
+        } else if tag.starts_with(b"mluc") && tag.len() >= 28 {
+            let length = be32(tag, 20) as usize;
+            let start = be32(tag, 24) as usize;
+            let units: Vec<u16> = tag
+                .get(start..start + length)
+                .unwrap_or(&[])
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+                .trim_end_matches('\0')
+                .to_string()
+        } else {
+            continue;
+        };
+        summary += &format!(", \"{}\"", description);
+    }
+    Some(summary)
+}
+
+/// Formats EXIF date, which is local time unless offset tag (EXIF 2.31) is present.
+fn format_exif_date(args: &Arguments, exif: &Exif, field: &Field) -> Option<String> {
+    let offset_tag = match field.tag {
+        Tag::DateTime => Tag::OffsetTime,
+        Tag::DateTimeOriginal => Tag::OffsetTimeOriginal,
+        Tag::DateTimeDigitized => Tag::OffsetTimeDigitized,
+        _ => return None,
+    };
+    let Value::Ascii(ref values) = field.value else {
+        return None;
+    };
+    let mut date = DateTime::from_ascii(values.first()?).ok()?;
+    let offset = exif
+        .get_field(offset_tag, field.ifd_num)
+        .and_then(|f| match &f.value {
+            Value::Ascii(values) => values.first(),
+            _ => None,
+        });
+    // Invalid offset leaves date without zone.
+    if let Some(offset) = offset {
+        date.parse_offset(offset).ok();
+    }
+    let time = PrimitiveDateTime::new(
+        time::Date::from_calendar_date(
+            date.year as i32,
+            time::Month::try_from(date.month).ok()?,
+            date.day,
+        )
+        .ok()?,
+        time::Time::from_hms(date.hour, date.minute, date.second).ok()?,
+    );
+    let offset = date
+        .offset
+        .and_then(|minutes| UtcOffset::from_whole_seconds(minutes as i32 * 60).ok());
+    Some(format_parsed_datetime(args, time, offset))
+}
+
+/// Gets specified jpeg info about file.
+pub fn get_jpeg_info(args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## JPEG information");
+    let data = input.bytes();
+    let info = match get_jpeg_structure(data) {
+        Ok(info) => info,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
+
+    if let Some(frame) = &info.frame {
+        writeln!(report, "# Coding process: {}", frame_type(frame.marker));
+        writeln!(
+            report,
+            "# Dimensions: {}x{}, {} bit, {} component(s)",
+            frame.width,
+            frame.height,
+            frame.precision,
+            frame.components.len()
+        );
+        write!(report, "# Sampling factors: ");
+        for (id, h, v, table) in frame.components.iter() {
+            write!(report, "{}: {}x{} (table {}); ", id, h, v, table);
+        }
+        writeln!(report);
+    }
+    for table in info.quantization_tables.iter() {
+        writeln!(
+            report,
+            "# Quantization table {}{}: estimated quality {}",
+            table.id,
+            if table.is_16bit { " (16-bit)" } else { "" },
+            estimate_quality(table)
+        );
+    }
+    if let Some(jfif) = &info.jfif {
+        writeln!(report, "# JFIF: {}", jfif);
+    }
+    if let Some(adobe) = &info.adobe {
+        writeln!(report, "# Adobe: {}", adobe);
+    }
+    for comment in info.comments.iter() {
+        writeln!(report, "# Comment: {:?}", comment);
+    }
+    for size in info.xmp_sizes.iter() {
+        writeln!(report, "# XMP packet: {}", format_size(args, *size as u64));
+    }
+    if info.extended_xmp_size > 0 {
+        writeln!(
+            report,
+            "# Extended XMP: {}",
+            format_size(args, info.extended_xmp_size as u64)
+        );
+    }
+    if let Some(icc) = &info.icc {
+        write!(
+            report,
+            "# ICC profile: {} in {}/{} chunk(s)",
+            format_size(args, icc.data.len() as u64),
+            icc.chunks,
+            icc.expected_chunks
+        );
+        if let Some(summary) = get_icc_summary(&icc.data) {
+            write!(report, ", {}", summary);
+        }
+        writeln!(report);
+    }
+    if !info.mp_entries.is_empty() {
+        writeln!(
+            report,
+            "# Multi-picture (MPF) images: {}",
+            info.mp_entries.len()
+        );
+        for (i, entry) in info.mp_entries.iter().enumerate() {
+            writeln!(
+                report,
+                "{}: {} at offset {} ({})",
+                i + 1,
+                mp_type(entry.attribute),
+                entry.offset,
+                format_size(args, entry.size as u64)
+            );
+        }
+    }
+    if info.thumbnails.is_empty() {
+        writeln!(report, "# Embedded thumbnails: none");
+    } else {
+        writeln!(report, "# Embedded thumbnails:");
+        for thumbnail in info.thumbnails.iter() {
+            write!(
+                report,
+                "{}: {}",
+                thumbnail.source,
+                format_size(args, thumbnail.size as u64)
+            );
+            if let Some((width, height)) = thumbnail.dimensions {
+                write!(report, " ({}x{})", width, height);
+            }
+            writeln!(report);
+        }
+    }
+
+    match info.end_offset {
+        Some(end) if end < data.len() => {
+            let trailing = data.len() - end;
+            let mpf: usize = info
+                .mp_entries
+                .iter()
+                .filter(|e| e.offset >= end)
+                .map(|e| e.size as usize)
+                .sum();
+            write!(
+                report,
+                "# Data after EOI: {}",
+                format_size(args, trailing as u64)
+            );
+            if mpf > 0 {
+                write!(
+                    report,
+                    " ({} belong to MPF images)",
+                    format_size(args, mpf as u64)
+                );
+            }
+            if mpf < trailing {
+                let rest = &data[end + mpf..];
+                if rest.starts_with(b"PK\x03\x04") {
+                    write!(report, " (WARNING: looks like appended ZIP archive)");
+                } else if rest.iter().any(|b| *b != 0) {
+                    write!(report, " (WARNING: unexplained trailing data)");
+                }
+            }
+            writeln!(report);
+        }
+        Some(_) => (),
+        None => writeln!(report, "# EOI marker not found, file is truncated"),
+    }
+
+    if let Some(exif) = info.exif {
+        match Reader::new().read_raw(exif) {
+            Ok(exif) => {
+                writeln!(report, "# EXIF tags:");
+                for field in exif.fields() {
+                    let value = match format_exif_date(args, &exif, field) {
+                        Some(date) => date,
+                        None => field.display_value().with_unit(&exif).to_string(),
+                    };
+                    writeln!(report, "{} ({}): {}", field.tag, field.ifd_num, value);
+                }
+            }
+            Err(e) => writeln!(report, "Error when reading EXIF: {}", e),
+        }
+    }
+
+    writeln!(report, "# Segments:");
+    for segment in info.segments.iter() {
+        if segment.length == 0 {
+            writeln!(
+                report,
+                "0x{:08X} {}",
+                segment.offset,
+                marker_name(segment.marker)
+            );
+        } else {
+            writeln!(
+                report,
+                "0x{:08X} {} (length {})",
+                segment.offset,
+                marker_name(segment.marker),
+                segment.length
+            );
+        }
+    }
+}
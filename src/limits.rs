@@ -0,0 +1,158 @@
+use std::cell::{Cell, RefCell};
+use std::io::{Error, Read};
+use std::time::{Duration, Instant};
+
+/// Limits of analysis, which protect against hostile inputs like zip bombs or deeply nested boxes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Entries of archive which are listed, hashed or scanned.
+    pub max_entries: usize,
+    /// Bytes decompressed from archive entries and streams of whole file.
+    pub max_decompressed: u64,
+    /// Nesting of boxes, objects and trees.
+    pub max_depth: usize,
+    /// Bytes of file given to analyzers, `None` for whole file.
+    pub max_read: Option<u64>,
+    /// Wall-clock time of analysis of one file.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_entries: 10000,
+            max_decompressed: 1 << 30,
+            max_depth: 32,
+            max_read: None,
+            timeout: None,
+        }
+    }
+}
+
+/// Limit which was reached and truncated report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Entries,
+    Decompressed,
+    Depth,
+    Read,
+    Timeout,
+}
+
+thread_local! {
+    /// Limits of file analyzed by this thread.
+    static LIMITS: Cell<Limits> = Cell::new(Limits::default());
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+    static DECOMPRESSED: Cell<u64> = const { Cell::new(0) };
+    static REACHED: RefCell<Vec<Limit>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Starts analysis of file on this thread, with its own time and decompression budget.
+pub fn start(limits: Limits) {
+    LIMITS.set(limits);
+    DEADLINE.set(limits.timeout.map(|timeout| Instant::now() + timeout));
+    DECOMPRESSED.set(0);
+    REACHED.take();
+}
+
+/// Gets limits of file analyzed by this thread.
+pub fn get() -> Limits {
+    LIMITS.get()
+}
+
+/// Notes that limit was reached, so it's reported.
+pub fn reach(limit: Limit) {
+    REACHED.with_borrow_mut(|reached| {
+        if !reached.contains(&limit) {
+            reached.push(limit);
+        }
+    });
+}
+
+/// Gets limits reached since start of analysis.
+pub fn get_reached() -> Vec<Limit> {
+    REACHED.with_borrow(|reached| reached.clone())
+}
+
+/// Is time for analysis of file over.
+pub fn is_timed_out() -> bool {
+    let timed_out = DEADLINE
+        .get()
+        .is_some_and(|deadline| Instant::now() >= deadline);
+    if timed_out {
+        reach(Limit::Timeout);
+    }
+    timed_out
+}
+
+/// Can entry with given index be processed, it checks count of entries and time.
+pub fn allow_entry(index: usize) -> bool {
+    if index >= get().max_entries {
+        reach(Limit::Entries);
+        return false;
+    }
+    !is_timed_out()
+}
+
+/// Can box or object with given nesting be parsed.
+pub fn allow_depth(depth: usize) -> bool {
+    if depth >= get().max_depth {
+        reach(Limit::Depth);
+        return false;
+    }
+    true
+}
+
+/// Can entry of given size be decompressed whole into memory, for libraries which don't stream it.
+/// Data is counted to budget later by its reader.
+pub fn allow_decompressed(size: u64) -> bool {
+    if DECOMPRESSED.get().saturating_add(size) > get().max_decompressed {
+        reach(Limit::Decompressed);
+        return false;
+    }
+    true
+}
+
+/// Reader of decompressed data, which ends when decompression budget of file or time is spent.
+pub struct DecompressedReader<R> {
+    inner: R,
+}
+
+impl<R: Read> Read for DecompressedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.is_empty() || is_timed_out() {
+            return Ok(0);
+        }
+        let remaining = get().max_decompressed.saturating_sub(DECOMPRESSED.get());
+        if remaining == 0 {
+            // Data ending exactly at limit isn't truncated.
+            if self.inner.read(&mut [0u8])? > 0 {
+                reach(Limit::Decompressed);
+            }
+            return Ok(0);
+        }
+        let length = buf.len().min(remaining.min(usize::MAX as u64) as usize);
+        let read = self.inner.read(&mut buf[..length])?;
+        DECOMPRESSED.set(DECOMPRESSED.get() + read as u64);
+        Ok(read)
+    }
+}
+
+/// Wraps reader of decompressed data, so it counts to limit.
+pub fn limit_decompressed<R: Read>(inner: R) -> DecompressedReader<R> {
+    DecompressedReader { inner }
+}
+
+/// Describes limit with its value, like `max-entries (10000)`.
+pub fn describe(limits: &Limits, limit: Limit) -> String {
+    match limit {
+        Limit::Entries => format!("max-entries ({})", limits.max_entries),
+        Limit::Decompressed => format!("max-decompressed ({} bytes)", limits.max_decompressed),
+        Limit::Depth => format!("max-depth ({})", limits.max_depth),
+        Limit::Read => format!("max-read ({} bytes)", limits.max_read.unwrap_or(0)),
+        Limit::Timeout => format!(
+            "timeout ({} s)",
+            limits.timeout.unwrap_or_default().as_secs_f64()
+        ),
+    }
+}
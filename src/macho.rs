@@ -0,0 +1,800 @@
+use crate::input::Input;
+use crate::limits::limit_decompressed;
+use crate::pe::{get_signer, Signer};
+use crate::report::Report;
+use crate::{format_size, Arguments};
+use std::io::{Error, ErrorKind, Read};
+
+const FAT_MAGIC: u32 = 0xCAFEBABE;
+const FAT_MAGIC_64: u32 = 0xCAFEBABF;
+const MH_MAGIC: u32 = 0xFEEDFACE;
+const MH_MAGIC_64: u32 = 0xFEEDFACF;
+
+const CPU_ARCH_ABI64: i32 = 0x0100_0000;
+const CPU_ARCH_ABI64_32: i32 = 0x0200_0000;
+const CPU_ARCH_ABI64_X86: i32 = CPU_ARCH_ABI64 | 7;
+
+const LC_SEGMENT: u32 = 0x1;
+const LC_LOAD_DYLIB: u32 = 0xC;
+const LC_ID_DYLIB: u32 = 0xD;
+const LC_LOAD_DYLINKER: u32 = 0xE;
+const LC_SEGMENT_64: u32 = 0x19;
+const LC_UUID: u32 = 0x1B;
+const LC_CODE_SIGNATURE: u32 = 0x1D;
+const LC_LAZY_LOAD_DYLIB: u32 = 0x20;
+const LC_ENCRYPTION_INFO: u32 = 0x21;
+const LC_VERSION_MIN_MACOSX: u32 = 0x24;
+const LC_VERSION_MIN_IPHONEOS: u32 = 0x25;
+const LC_SOURCE_VERSION: u32 = 0x2A;
+const LC_ENCRYPTION_INFO_64: u32 = 0x2C;
+const LC_VERSION_MIN_TVOS: u32 = 0x2F;
+const LC_VERSION_MIN_WATCHOS: u32 = 0x30;
+const LC_BUILD_VERSION: u32 = 0x32;
+const LC_LOAD_WEAK_DYLIB: u32 = 0x8000_0018;
+const LC_RPATH: u32 = 0x8000_001C;
+const LC_REEXPORT_DYLIB: u32 = 0x8000_001F;
+const LC_LOAD_UPWARD_DYLIB: u32 = 0x8000_0023;
+const LC_MAIN: u32 = 0x8000_0028;
+
+const CSMAGIC_EMBEDDED_SIGNATURE: u32 = 0xFADE0CC0;
+const CSMAGIC_CODEDIRECTORY: u32 = 0xFADE0C02;
+const CSMAGIC_EMBEDDED_ENTITLEMENTS: u32 = 0xFADE7171;
+const CSMAGIC_EMBEDDED_DER_ENTITLEMENTS: u32 = 0xFADE7172;
+const CSMAGIC_BLOBWRAPPER: u32 = 0xFADE0B01;
+
+pub struct FatSlice {
+    pub cpu_type: i32,
+    pub cpu_subtype: i32,
+    pub offset: u64,
+    pub size: u64,
+}
+
+pub struct MachSection {
+    pub name: String,
+    pub address: u64,
+    pub size: u64,
+}
+
+pub struct MachSegment {
+    pub name: String,
+    pub vm_address: u64,
+    pub vm_size: u64,
+    pub file_offset: u64,
+    pub file_size: u64,
+    pub initial_protection: u32,
+    pub sections: Vec<MachSection>,
+}
+
+pub struct Dylib {
+    /// "load", "weak", "reexport", "lazy" or "upward"
+    pub kind: &'static str,
+    pub name: String,
+    pub current_version: String,
+    pub compatibility_version: String,
+}
+
+pub struct CodeSignature {
+    pub size: usize,
+    pub identifier: Option<String>,
+    pub team_id: Option<String>,
+    pub flags: u32,
+    pub hash_type: u8,
+    pub has_cms: bool,
+    pub signer: Option<Signer>,
+    pub entitlements: Option<String>,
+    pub has_der_entitlements: bool,
+}
+
+/// Minimal OS version, SDK version and platform.
+pub struct BuildVersion {
+    pub platform: &'static str,
+    pub minimal_os: String,
+    pub sdk: String,
+}
+
+#[derive(Default)]
+pub struct MachO {
+    pub is_64: bool,
+    pub big_endian: bool,
+    pub cpu_type: i32,
+    pub cpu_subtype: i32,
+    pub file_type: u32,
+    pub flags: u32,
+    pub load_commands: usize,
+    pub segments: Vec<MachSegment>,
+    pub dylibs: Vec<Dylib>,
+    pub id_dylib: Option<Dylib>,
+    pub rpaths: Vec<String>,
+    pub dylinker: Option<String>,
+    pub uuid: Option<String>,
+    pub build_version: Option<BuildVersion>,
+    pub entry_offset: Option<u64>,
+    pub source_version: Option<String>,
+    pub encrypted: Option<bool>,
+    pub code_signature: Option<CodeSignature>,
+}
+
+/// Reads integers with endianness of file.
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self
+            .data
+            .get(offset..offset.checked_add(4)?)?
+            .try_into()
+            .ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn u64(&self, offset: usize) -> Option<u64> {
+        let bytes = self
+            .data
+            .get(offset..offset.checked_add(8)?)?
+            .try_into()
+            .ok()?;
+        Some(if self.big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        })
+    }
+
+    /// Reads null terminated string limited to `max` bytes.
+    fn string(&self, offset: usize, max: usize) -> String {
+        let rest = self.data.get(offset..).unwrap_or_default();
+        let rest = &rest[..rest.len().min(max)];
+        let end = rest.iter().position(|b| *b == 0).unwrap_or(rest.len());
+        String::from_utf8_lossy(&rest[..end]).to_string()
+    }
+}
+
+fn be_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset.checked_add(4)?)?.try_into().ok()?,
+    ))
+}
+
+pub fn cpu_name(cpu_type: i32, cpu_subtype: i32) -> String {
+    let subtype = cpu_subtype & 0xFF;
+    let name = match (cpu_type & !(CPU_ARCH_ABI64 | CPU_ARCH_ABI64_32), cpu_type) {
+        (7, CPU_ARCH_ABI64_X86) if subtype == 8 => "x86_64h",
+        (7, CPU_ARCH_ABI64_X86) => "x86_64",
+        (7, _) => "i386",
+        (12, t) if t & CPU_ARCH_ABI64_32 != 0 => "arm64_32",
+        (12, t) if t & CPU_ARCH_ABI64 != 0 && subtype == 2 => "arm64e",
+        (12, t) if t & CPU_ARCH_ABI64 != 0 => "arm64",
+        (12, _) if subtype == 9 => "armv7",
+        (12, _) if subtype == 11 => "armv7s",
+        (12, _) => "arm",
+        (18, t) if t & CPU_ARCH_ABI64 != 0 => "ppc64",
+        (18, _) => "ppc",
+        _ => return format!("unknown ({})", cpu_type),
+    };
+    name.to_string()
+}
+
+pub fn file_type_name(file_type: u32) -> &'static str {
+    match file_type {
+        1 => "object",
+        2 => "executable",
+        3 => "fixed VM shared library",
+        4 => "core dump",
+        5 => "preloaded executable",
+        6 => "dynamic library",
+        7 => "dynamic linker",
+        8 => "bundle",
+        9 => "dynamic library stub",
+        10 => "debug symbols (dSYM)",
+        11 => "kernel extension",
+        12 => "file set",
+        _ => "unknown",
+    }
+}
+
+fn platform_name(platform: u32) -> &'static str {
+    match platform {
+        1 => "macOS",
+        2 => "iOS",
+        3 => "tvOS",
+        4 => "watchOS",
+        5 => "bridgeOS",
+        6 => "Mac Catalyst",
+        7 => "iOS simulator",
+        8 => "tvOS simulator",
+        9 => "watchOS simulator",
+        10 => "DriverKit",
+        11 => "visionOS",
+        12 => "visionOS simulator",
+        _ => "unknown",
+    }
+}
+
+/// Formats version packed as xxxx.yy.zz nibbles.
+fn format_version(version: u32) -> String {
+    format!(
+        "{}.{}.{}",
+        version >> 16,
+        (version >> 8) & 0xFF,
+        version & 0xFF
+    )
+}
+
+/// Formats memory protection like "r-x".
+fn format_protection(protection: u32) -> String {
+    format!(
+        "{}{}{}",
+        if protection & 1 != 0 { 'r' } else { '-' },
+        if protection & 2 != 0 { 'w' } else { '-' },
+        if protection & 4 != 0 { 'x' } else { '-' }
+    )
+}
+
+/// Checks for universal binary header.
+fn is_fat(data: &[u8]) -> bool {
+    let (Some(magic), Some(count)) = (be_u32(data, 0), be_u32(data, 4)) else {
+        return false;
+    };
+    // Java class files share the magic, their version is always above 44.
+    (magic == FAT_MAGIC || magic == FAT_MAGIC_64) && count > 0 && count < 45
+}
+
+/// Gets architecture slices of universal (fat) binary, None if it's not one.
+pub fn get_fat_slices(data: &[u8]) -> Option<Vec<FatSlice>> {
+    if !is_fat(data) {
+        return None;
+    }
+    let magic = be_u32(data, 0)?;
+    let count = be_u32(data, 4)?;
+    let entry_size = if magic == FAT_MAGIC_64 { 32 } else { 20 };
+    let mut slices = Vec::new();
+    for i in 0..count as usize {
+        let at = 8 + i * entry_size;
+        let (offset, size) = if magic == FAT_MAGIC_64 {
+            let word = |o| be_u32(data, o).map(|v| v as u64);
+            (
+                (word(at + 8)? << 32) | word(at + 12)?,
+                (word(at + 16)? << 32) | word(at + 20)?,
+            )
+        } else {
+            (be_u32(data, at + 8)? as u64, be_u32(data, at + 12)? as u64)
+        };
+        slices.push(FatSlice {
+            cpu_type: be_u32(data, at)? as i32,
+            cpu_subtype: be_u32(data, at + 4)? as i32,
+            offset,
+            size,
+        });
+    }
+    Some(slices)
+}
+
+fn get_dylib(reader: &Reader, at: usize, size: usize, kind: &'static str) -> Option<Dylib> {
+    let name_offset = reader.u32(at + 8)? as usize;
+    Some(Dylib {
+        kind,
+        name: reader.string(at + name_offset, size.saturating_sub(name_offset)),
+        current_version: format_version(reader.u32(at + 16)?),
+        compatibility_version: format_version(reader.u32(at + 20)?),
+    })
+}
+
+fn get_segment(reader: &Reader, at: usize, is_64: bool) -> Option<MachSegment> {
+    let word = |o: usize| {
+        if is_64 {
+            reader.u64(o)
+        } else {
+            reader.u32(o).map(|v| v as u64)
+        }
+    };
+    let word_size = if is_64 { 8 } else { 4 };
+    let fields = at + 24;
+    let mut segment = MachSegment {
+        name: reader.string(at + 8, 16),
+        vm_address: word(fields)?,
+        vm_size: word(fields + word_size)?,
+        file_offset: word(fields + 2 * word_size)?,
+        file_size: word(fields + 3 * word_size)?,
+        initial_protection: reader.u32(fields + 4 * word_size + 4)?,
+        sections: Vec::new(),
+    };
+    let count = reader.u32(fields + 4 * word_size + 8)? as usize;
+    let (first, section_size) = if is_64 { (at + 72, 80) } else { (at + 56, 68) };
+    for i in 0..count.min(256) {
+        let section = first + i * section_size;
+        let Some(address) = word(section + 32) else {
+            break;
+        };
+        segment.sections.push(MachSection {
+            name: reader.string(section, 16),
+            address,
+            size: word(section + 32 + word_size).unwrap_or(0),
+        });
+    }
+    Some(segment)
+}
+
+/// Parses embedded code signature super blob (always big endian).
+pub fn get_code_signature(blob: &[u8]) -> Option<CodeSignature> {
+    if be_u32(blob, 0)? != CSMAGIC_EMBEDDED_SIGNATURE {
+        return None;
+    }
+    let mut signature = CodeSignature {
+        size: blob.len(),
+        identifier: None,
+        team_id: None,
+        flags: 0,
+        hash_type: 0,
+        has_cms: false,
+        signer: None,
+        entitlements: None,
+        has_der_entitlements: false,
+    };
+    let count = be_u32(blob, 8)? as usize;
+    for i in 0..count.min(64) {
+        let Some(offset) = be_u32(blob, 12 + i * 8 + 4).map(|o| o as usize) else {
+            break;
+        };
+        let (Some(magic), Some(length)) = (be_u32(blob, offset), be_u32(blob, offset + 4)) else {
+            continue;
+        };
+        let Some(content) = blob.get(offset..offset.saturating_add(length as usize)) else {
+            continue;
+        };
+        let string = |at: usize| -> Option<String> {
+            let rest = content.get(at..)?;
+            let end = rest.iter().position(|b| *b == 0)?;
+            Some(String::from_utf8_lossy(&rest[..end]).to_string())
+        };
+        match magic {
+            // Alternate code directories (SHA-256 next to SHA-1) repeat same identifiers.
+            CSMAGIC_CODEDIRECTORY if signature.identifier.is_none() => {
+                let version = be_u32(content, 8).unwrap_or(0);
+                signature.flags = be_u32(content, 12).unwrap_or(0);
+                signature.identifier = be_u32(content, 20).and_then(|o| string(o as usize));
+                signature.hash_type = content.get(37).copied().unwrap_or(0);
+                if version >= 0x20200 {
+                    signature.team_id = be_u32(content, 48)
+                        .filter(|o| *o != 0)
+                        .and_then(|o| string(o as usize));
+                }
+            }
+            CSMAGIC_EMBEDDED_ENTITLEMENTS => {
+                signature.entitlements =
+                    Some(String::from_utf8_lossy(&content[8.min(content.len())..]).to_string())
+            }
+            CSMAGIC_EMBEDDED_DER_ENTITLEMENTS => signature.has_der_entitlements = true,
+            // Ad-hoc signatures have empty CMS wrapper.
+            CSMAGIC_BLOBWRAPPER if content.len() > 8 => {
+                signature.has_cms = true;
+                signature.signer = get_signer(&content[8..]);
+            }
+            _ => (),
+        }
+    }
+    Some(signature)
+}
+
+/// Gets header and load commands of single architecture Mach-O file.
+pub fn get_macho_structure(data: &[u8]) -> Result<MachO, Error> {
+    let magic = be_u32(data, 0).ok_or(Error::new(ErrorKind::UnexpectedEof, "file too short"))?;
+    let (is_64, big_endian) = match magic {
+        MH_MAGIC => (false, true),
+        MH_MAGIC_64 => (true, true),
+        m if m == MH_MAGIC.swap_bytes() => (false, false),
+        m if m == MH_MAGIC_64.swap_bytes() => (true, false),
+        _ => return Err(Error::new(ErrorKind::InvalidData, "not a Mach-O file")),
+    };
+    let reader = Reader { data, big_endian };
+    let truncated = || Error::new(ErrorKind::UnexpectedEof, "truncated Mach-O header");
+    let mut info = MachO {
+        is_64,
+        big_endian,
+        cpu_type: reader.u32(4).ok_or_else(truncated)? as i32,
+        cpu_subtype: reader.u32(8).ok_or_else(truncated)? as i32,
+        file_type: reader.u32(12).ok_or_else(truncated)?,
+        load_commands: reader.u32(16).ok_or_else(truncated)? as usize,
+        flags: reader.u32(24).ok_or_else(truncated)?,
+        ..Default::default()
+    };
+
+    let mut at = if is_64 { 32 } else { 28 };
+    for _ in 0..info.load_commands.min(4096) {
+        let (Some(command), Some(size)) = (reader.u32(at), reader.u32(at + 4)) else {
+            break;
+        };
+        let size = size as usize;
+        if size < 8 {
+            break;
+        }
+        let dylib_kind = match command {
+            LC_LOAD_DYLIB => Some("load"),
+            LC_LOAD_WEAK_DYLIB => Some("weak"),
+            LC_REEXPORT_DYLIB => Some("reexport"),
+            LC_LAZY_LOAD_DYLIB => Some("lazy"),
+            LC_LOAD_UPWARD_DYLIB => Some("upward"),
+            _ => None,
+        };
+        if let Some(kind) = dylib_kind {
+            info.dylibs.extend(get_dylib(&reader, at, size, kind));
+        }
+        match command {
+            LC_SEGMENT | LC_SEGMENT_64 => {
+                info.segments
+                    .extend(get_segment(&reader, at, command == LC_SEGMENT_64));
+            }
+            LC_ID_DYLIB => info.id_dylib = get_dylib(&reader, at, size, "id"),
+            LC_LOAD_DYLINKER => {
+                let offset = reader.u32(at + 8).unwrap_or(12) as usize;
+                info.dylinker = Some(reader.string(at + offset, size.saturating_sub(offset)));
+            }
+            LC_RPATH => {
+                let offset = reader.u32(at + 8).unwrap_or(12) as usize;
+                info.rpaths
+                    .push(reader.string(at + offset, size.saturating_sub(offset)));
+            }
+            LC_UUID => {
+                if let Some(uuid) = data.get(at + 8..at + 24) {
+                    let hex: String = uuid.iter().map(|b| format!("{:02X}", b)).collect();
+                    info.uuid = Some(format!(
+                        "{}-{}-{}-{}-{}",
+                        &hex[..8],
+                        &hex[8..12],
+                        &hex[12..16],
+                        &hex[16..20],
+                        &hex[20..]
+                    ));
+                }
+            }
+            LC_BUILD_VERSION => {
+                if let (Some(platform), Some(minimal_os), Some(sdk)) =
+                    (reader.u32(at + 8), reader.u32(at + 12), reader.u32(at + 16))
+                {
+                    info.build_version = Some(BuildVersion {
+                        platform: platform_name(platform),
+                        minimal_os: format_version(minimal_os),
+                        sdk: format_version(sdk),
+                    });
+                }
+            }
+            LC_VERSION_MIN_MACOSX
+            | LC_VERSION_MIN_IPHONEOS
+            | LC_VERSION_MIN_TVOS
+            | LC_VERSION_MIN_WATCHOS
+                if info.build_version.is_none() =>
+            {
+                let platform = match command {
+                    LC_VERSION_MIN_MACOSX => "macOS",
+                    LC_VERSION_MIN_IPHONEOS => "iOS",
+                    LC_VERSION_MIN_TVOS => "tvOS",
+                    _ => "watchOS",
+                };
+                if let (Some(minimal_os), Some(sdk)) = (reader.u32(at + 8), reader.u32(at + 12)) {
+                    info.build_version = Some(BuildVersion {
+                        platform,
+                        minimal_os: format_version(minimal_os),
+                        sdk: format_version(sdk),
+                    });
+                }
+            }
+            LC_MAIN => info.entry_offset = reader.u64(at + 8),
+            LC_SOURCE_VERSION => {
+                info.source_version = reader.u64(at + 8).map(|v| {
+                    format!(
+                        "{}.{}.{}.{}.{}",
+                        v >> 40,
+                        (v >> 30) & 0x3FF,
+                        (v >> 20) & 0x3FF,
+                        (v >> 10) & 0x3FF,
+                        v & 0x3FF
+                    )
+                })
+            }
+            LC_ENCRYPTION_INFO | LC_ENCRYPTION_INFO_64 => {
+                info.encrypted = reader.u32(at + 16).map(|id| id != 0)
+            }
+            LC_CODE_SIGNATURE => {
+                if let (Some(offset), Some(length)) = (reader.u32(at + 8), reader.u32(at + 12)) {
+                    let (offset, length) = (offset as usize, length as usize);
+                    info.code_signature = data
+                        .get(offset..offset.saturating_add(length))
+                        .and_then(get_code_signature);
+                }
+            }
+            _ => (),
+        }
+        at += size;
+    }
+    Ok(info)
+}
+
+/// Gets (key, value) pairs from XML entitlements plist, arrays joined by ", ".
+pub fn get_plist_entries(plist: &str) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    let mut rest = plist;
+    while let Some(at) = rest.find("<key>") {
+        rest = &rest[at + 5..];
+        let Some(end) = rest.find("</key>") else {
+            break;
+        };
+        let key = rest[..end].to_string();
+        rest = rest[end + 6..].trim_start();
+        let value = if rest.starts_with("<true/>") {
+            "true".to_string()
+        } else if rest.starts_with("<false/>") {
+            "false".to_string()
+        } else if rest.starts_with("<array>") {
+            let end = rest.find("</array>").unwrap_or(rest.len());
+            rest[..end]
+                .split("<string>")
+                .skip(1)
+                .filter_map(|s| s.split("</string>").next())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        } else if rest.starts_with("<array/>") {
+            String::new()
+        } else {
+            // <string>, <integer> and alike
+            let start = rest.find('>').map(|i| i + 1).unwrap_or(0);
+            let end = rest.find("</").unwrap_or(start).max(start);
+            rest[start..end].to_string()
+        };
+        entries.push((key, value));
+    }
+    entries
+}
+
+/// Gets more specific extension of extensionless Mach-O file.
+pub fn get_macho_extension(input: &Input) -> Option<String> {
+    let header = input.bytes().get(..16)?;
+    if is_fat(header) {
+        return Some("macho".to_string());
+    }
+    let magic = be_u32(header, 0)?;
+    let file_type = if magic == MH_MAGIC || magic == MH_MAGIC_64 {
+        be_u32(header, 12)?
+    } else if magic.swap_bytes() == MH_MAGIC || magic.swap_bytes() == MH_MAGIC_64 {
+        be_u32(header, 12)?.swap_bytes()
+    } else {
+        return None;
+    };
+    Some(if file_type == 6 { "dylib" } else { "macho" }.to_string())
+}
+
+fn print_macho(args: &Arguments, data: &[u8], report: &mut Report) {
+    let info = match get_macho_structure(data) {
+        Ok(info) => info,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
+    writeln!(
+        report,
+        "# CPU: {}, {}-bit, {} endian",
+        cpu_name(info.cpu_type, info.cpu_subtype),
+        if info.is_64 { 64 } else { 32 },
+        if info.big_endian { "big" } else { "little" }
+    );
+    writeln!(report, "# File type: {}", file_type_name(info.file_type));
+    let flags: Vec<&str> = [
+        (0x4, "dyld linked"),
+        (0x80, "two-level namespace"),
+        (0x20000, "executable stack"),
+        (0x200000, "PIE"),
+        (0x1000000, "no heap execution"),
+        (0x2000000, "app extension safe"),
+    ]
+    .iter()
+    .filter(|(flag, _)| info.flags & flag != 0)
+    .map(|(_, name)| *name)
+    .collect();
+    writeln!(report, "# Flags: {}", flags.join(", "));
+    writeln!(report, "# Load commands: {}", info.load_commands);
+    if let Some(uuid) = &info.uuid {
+        writeln!(report, "# UUID: {}", uuid);
+    }
+    if let Some(build) = &info.build_version {
+        writeln!(
+            report,
+            "# Platform: {}, minimal OS {}, SDK {}",
+            build.platform, build.minimal_os, build.sdk
+        );
+    }
+    if let Some(version) = &info.source_version {
+        writeln!(report, "# Source version: {}", version);
+    }
+    if let Some(entry) = info.entry_offset {
+        writeln!(report, "# Entry point offset: 0x{:x}", entry);
+    }
+    if let Some(dylinker) = &info.dylinker {
+        writeln!(report, "# Dynamic linker: {}", dylinker);
+    }
+    if let Some(id) = &info.id_dylib {
+        writeln!(
+            report,
+            "# Install name: {} (version {}, compatible {})",
+            id.name, id.current_version, id.compatibility_version
+        );
+    }
+    if !info.dylibs.is_empty() {
+        writeln!(report, "# Linked libraries: {}", info.dylibs.len());
+        for dylib in info.dylibs.iter() {
+            write!(report, "{} (version {}", dylib.name, dylib.current_version);
+            if dylib.kind != "load" {
+                write!(report, ", {}", dylib.kind);
+            }
+            writeln!(report, ")");
+        }
+    }
+    for rpath in info.rpaths.iter() {
+        writeln!(report, "# RPATH: {}", rpath);
+    }
+    if let Some(encrypted) = info.encrypted {
+        writeln!(
+            report,
+            "# Encrypted (FairPlay): {}",
+            if encrypted { "yes" } else { "no" }
+        );
+    }
+
+    writeln!(report, "# Segments: {}", info.segments.len());
+    for segment in info.segments.iter() {
+        writeln!(
+            report,
+            "{} {} address 0x{:x}, memory size {}, file offset {}, file size {}",
+            if segment.name.is_empty() {
+                "(unnamed)"
+            } else {
+                &segment.name
+            },
+            format_protection(segment.initial_protection),
+            segment.vm_address,
+            format_size(args, segment.vm_size),
+            segment.file_offset,
+            format_size(args, segment.file_size)
+        );
+        for section in segment.sections.iter() {
+            writeln!(
+                report,
+                "  {} address 0x{:x}, size {}",
+                section.name,
+                section.address,
+                format_size(args, section.size)
+            );
+        }
+    }
+
+    let Some(signature) = &info.code_signature else {
+        writeln!(report, "# Code signature: none");
+        return;
+    };
+    write!(
+        report,
+        "# Code signature: {}, ",
+        format_size(args, signature.size as u64)
+    );
+    if signature.has_cms {
+        write!(report, "signed with certificate");
+    } else {
+        write!(report, "ad-hoc");
+    }
+    if signature.flags & 0x10000 != 0 {
+        write!(report, ", hardened runtime");
+    }
+    if signature.flags & 0x20000 != 0 {
+        write!(report, ", linker signed");
+    }
+    match signature.hash_type {
+        1 => write!(report, ", SHA-1"),
+        2 => write!(report, ", SHA-256"),
+        _ => (),
+    }
+    writeln!(report, ", not verified");
+    if let Some(identifier) = &signature.identifier {
+        writeln!(report, "Identifier: {}", identifier);
+    }
+    if let Some(team_id) = &signature.team_id {
+        writeln!(report, "Team ID: {}", team_id);
+    }
+    if let Some(signer) = &signature.signer {
+        writeln!(report, "Signer: {}", signer.subject);
+        writeln!(report, "Issuer: {}", signer.issuer);
+    }
+    if let Some(entitlements) = &signature.entitlements {
+        let entries = get_plist_entries(entitlements);
+        writeln!(report, "# Entitlements: {}", entries.len());
+        for (key, value) in entries {
+            writeln!(report, "{}: {}", key, value);
+        }
+    } else if signature.has_der_entitlements {
+        writeln!(report, "# Entitlements: DER encoded only");
+    }
+}
+
+/// Prints every slice of universal binary or the single one.
+fn print_macho_data(args: &Arguments, data: &[u8], report: &mut Report) {
+    let Some(slices) = get_fat_slices(data) else {
+        print_macho(args, data, report);
+        return;
+    };
+    writeln!(
+        report,
+        "# Universal binary: {} architecture(s)",
+        slices.len()
+    );
+    for slice in slices.iter() {
+        writeln!(
+            report,
+            "## Slice {} at offset {}, size {}",
+            cpu_name(slice.cpu_type, slice.cpu_subtype),
+            slice.offset,
+            format_size(args, slice.size)
+        );
+        let start = slice.offset as usize;
+        match data.get(start..start.saturating_add(slice.size as usize)) {
+            Some(slice) => print_macho(args, slice, report),
+            None => writeln!(report, "Error: slice is outside of file"),
+        }
+    }
+}
+
+/// Gets specified Mach-O info about file.
+pub fn get_macho_info(args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## Mach-O information");
+    let data = input.bytes();
+    print_macho_data(args, data, report);
+}
+
+/// Gets Mach-O info about main executable of iOS app package.
+pub fn get_ipa_info(args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## iOS app information");
+    let mut archive = match zip::ZipArchive::new(input.reader()) {
+        Ok(archive) => archive,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
+    // Main executable is usually named after bundle: Payload/Name.app/Name
+    let executable = archive.file_names().find_map(|name| {
+        let bundle = name.strip_prefix("Payload/")?;
+        let (app, file) = bundle.split_once(".app/")?;
+        (file == app).then(|| name.to_string())
+    });
+    let has_profile = archive
+        .file_names()
+        .any(|name| name.ends_with(".app/embedded.mobileprovision"));
+    writeln!(
+        report,
+        "# Provisioning profile: {}",
+        if has_profile { "embedded" } else { "none" }
+    );
+    let Some(executable) = executable else {
+        writeln!(report, "Error: main executable was not found in Payload");
+        return;
+    };
+    writeln!(report, "# Main executable: {}", executable);
+    let mut data = Vec::new();
+    match archive.by_name(&executable) {
+        Ok(mut file) => {
+            if let Err(e) = limit_decompressed(&mut file).read_to_end(&mut data) {
+                writeln!(report, "Error: {}", e);
+                return;
+            }
+        }
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    }
+    writeln!(report, "## Mach-O information");
+    print_macho_data(args, &data, report);
+}
@@ -1,14 +1,46 @@
-extern crate bytesize;
-
+mod archive;
+mod audio;
+mod batch;
 mod components;
+mod datetime;
+mod elf;
+mod entropy;
+mod fuzzy;
+mod hash;
+mod input;
+mod isobmff;
+mod java;
+mod jpeg;
+mod limits;
+mod macho;
+mod matroska;
+mod pdf;
+mod pe;
+mod plaintext;
 mod rar;
+mod report;
+mod spool;
+mod tar;
 mod tests;
+#[cfg(unix)]
+mod unix;
+mod wasm;
+#[cfg(target_os = "linux")]
+mod xattr;
 mod zip;
 
-use crate::components::{Arguments, ExtensionVec};
-use bytesize::ByteSize;
-use clap::{arg, Arg, ArgAction, Command};
-use fltk::app::quit;
+use crate::archive::ArchiveKind;
+use crate::components::{
+    Arguments, Category, ExtensionVec, HashAlgorithm, LineMode, Section, SizeUnits, TimeFormat,
+    DEFAULT_SECTIONS,
+};
+use crate::datetime::TimeZone;
+#[cfg(not(unix))]
+use crate::datetime::{format_timestamp, Timestamp};
+use crate::input::Input;
+use crate::limits::{Limit, Limits};
+use crate::report::Report;
+use clap::{arg, error::ErrorKind, Arg, ArgAction, Command};
 use fltk::utils::oncelock::Lazy;
 use fltk::{enums::*, menu, text, dialog, window};
 use fltk::{app, prelude::*, group};
@@ -22,87 +54,146 @@ use std::{
     io::{BufReader, Error},
     path::PathBuf,
     str::FromStr,
-    time::SystemTime,
+    sync::OnceLock,
 };
-use time::OffsetDateTime;
+
+/// Formats number with comma between every three digits, like `1,234,567`.
+fn group_thousands(number: u64) -> String {
+    let digits = number.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Formats size in bytes in units asked by user.
+pub fn format_size(args: &Arguments, size: u64) -> String {
+    let (base, units) = match args.units {
+        SizeUnits::Plain => return size.to_string(),
+        SizeUnits::Bytes => return format!("{} B", group_thousands(size)),
+        SizeUnits::Si => (1000.0, ["B", "kB", "MB", "GB", "TB", "PB", "EB"]),
+        SizeUnits::Iec => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"]),
+    };
+    if (size as f64) < base {
+        return format!("{} B", size);
+    }
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    format!("{:.*} {}", args.size_precision, value, units[unit])
+}
+
+/// Formats how much data was compressed, as percentage of original size and as `x:1` ratio.
+pub fn format_ratio(compressed: u64, size: u64) -> String {
+    if size == 0 {
+        return "empty".to_string();
+    }
+    let percent = compressed as f64 / size as f64 * 100.0;
+    if compressed == 0 {
+        return format!("{:.2}%", percent);
+    }
+    format!("{:.2}%, {:.2}:1", percent, size as f64 / compressed as f64)
+}
 
 /// Gets generic file info like time properties.
-fn get_general_info(args: &Arguments) {
-    println!("## General information:");
-    println!(
-        "# Name: {}",
-        args.file_path.file_name().unwrap().to_string_lossy()
+fn get_general_info(args: &Arguments, report: &mut Report) {
+    writeln!(report, "## General information:");
+    let name_path = args.name_path();
+    report.print_field(
+        Section::General,
+        "name",
+        "Name",
+        name_path
+            .file_name()
+            .unwrap_or(name_path.as_os_str())
+            .to_string_lossy(),
     );
+    if let Some(stream_path) = &args.stream_path {
+        report.print_field(
+            Section::General,
+            "source",
+            "Source",
+            crate::spool::describe_stream(stream_path),
+        );
+    }
 
     let metadata = fs::metadata(args.file_path.clone()).unwrap();
 
-    if !args.is_human {
-        println!("# Size: {:?}", metadata.len())
-    } else {
-        println!("# Size: {}", ByteSize(metadata.len()).to_string_as(true));
-    }
-    // TODO: proper handling of inaccessible time
-    let created_time: OffsetDateTime = metadata.created().unwrap_or(SystemTime::now()).into();
-    let modified_time: OffsetDateTime = metadata.modified().unwrap_or(SystemTime::now()).into();
-    let accessed_time: OffsetDateTime = metadata.accessed().unwrap_or(SystemTime::now()).into();
-
-    println!(
-        "# Created: {:0>4}-{:0>2}-{:0>2} {:0>2}:{:0>2}:{:0>2}",
-        created_time.year(),
-        created_time.month() as u8,
-        created_time.day(),
-        created_time.hour(),
-        created_time.minute(),
-        created_time.second()
-    );
-    println!(
-        "# Last modified: {:0>4}-{:0>2}-{:0>2} {:0>2}:{:0>2}:{:0>2}",
-        modified_time.year(),
-        modified_time.month() as u8,
-        modified_time.day(),
-        modified_time.hour(),
-        modified_time.minute(),
-        modified_time.second()
-    );
-    println!(
-        "# Last accessed: {:0>4}-{:0>2}-{:0>2} {:0>2}:{:0>2}:{:0>2}",
-        accessed_time.year(),
-        accessed_time.month() as u8,
-        accessed_time.day(),
-        accessed_time.hour(),
-        accessed_time.minute(),
-        accessed_time.second()
+    report.print_field(
+        Section::General,
+        "size",
+        "Size",
+        format_size(args, metadata.len()),
     );
+    // Metadata of spooled copy tells nothing about stream.
+    if args.stream_path.is_some() {
+        return;
+    }
+    #[cfg(unix)]
+    crate::unix::get_unix_info(args, report);
+    #[cfg(target_os = "linux")]
+    crate::xattr::get_xattr_info(args, report);
+    #[cfg(not(unix))]
+    for (key, name, time) in [
+        ("created", "Created", metadata.created()),
+        ("modified", "Last modified", metadata.modified()),
+        ("accessed", "Last accessed", metadata.accessed()),
+    ] {
+        // Times not supported by platform or filesystem are not made up.
+        match time {
+            Ok(time) => report.print_field(
+                Section::General,
+                key,
+                name,
+                format_timestamp(args, Timestamp::from_system_time(time)),
+            ),
+            Err(_) => report.print_field(Section::General, key, name, "unavailable"),
+        }
+    }
 
     if metadata.permissions().readonly() {
-        println!("Readonly");
+        writeln!(report, "Readonly");
     } else {
-        println!("# Readable and writable");
+        writeln!(report, "# Readable and writable");
     }
 }
 
-/// Gets `ExtensionVec` by reading Extensions.toml.
-fn get_extension_vec(args: &Arguments) -> ExtensionVec {
-    let extensions_str = match fs::read_to_string(args.extensions_path.clone()) {
-        Ok(c) => c,
-        Err(_) => {
-            println!(
-                "Could not read extensions file: {}",
-                args.extensions_path.to_string_lossy()
-            );
-            quit();
-            unreachable!();
-        }
-    };
+/// Extensions.toml is read once and shared by all files (and threads analyzing them).
+static EXTENSION_VEC: OnceLock<ExtensionVec> = OnceLock::new();
 
-    let extension_vec: ExtensionVec = toml::from_str(&extensions_str).unwrap();
-    extension_vec
+/// Gets `ExtensionVec` by reading Extensions.toml, only for the first time.
+fn get_extension_vec(args: &Arguments) -> &'static ExtensionVec {
+    EXTENSION_VEC.get_or_init(|| read_extension_vec(args))
+}
+
+/// Reads Extensions.toml, error is printed once and no extensions are known then,
+/// so files are still analyzed.
+fn read_extension_vec(args: &Arguments) -> ExtensionVec {
+    let extension_vec = fs::read_to_string(args.extensions_path.clone())
+        .map_err(|e| e.to_string())
+        .and_then(|extensions_str| toml::from_str(&extensions_str).map_err(|e| e.to_string()));
+    extension_vec.unwrap_or_else(|e| {
+        eprintln!(
+            "Could not read extensions file: {}: {}",
+            args.extensions_path.to_string_lossy(),
+            e
+        );
+        ExtensionVec {
+            extensions: Vec::new(),
+        }
+    })
 }
 
 /// Gets extension from it's id from Extensions.toml. Errors if not found.
 fn get_extension_from_id(args: &Arguments, id: String) -> Result<String, Error> {
-    let mut extension_vec = get_extension_vec(args);
-    for extension_data in extension_vec.extensions.iter_mut() {
+    for extension_data in get_extension_vec(args).extensions.iter() {
         if extension_data.id != id {
             continue;
         };
@@ -114,10 +205,9 @@ fn get_extension_from_id(args: &Arguments, id: String) -> Result<String, Error>
     ))
 }
 
-/// Contain extension data as global to minimize calls --- TODO URGENT
+/// Gets name of extension from Extensions.toml, "unknown type" if not found.
 fn get_extension_name(args: &Arguments, extension: &OsStr) -> String {
-    let mut extension_vec = get_extension_vec(args);
-    for extension_data in extension_vec.extensions.iter_mut() {
+    for extension_data in get_extension_vec(args).extensions.iter() {
         if extension_data.extension != extension.to_str().unwrap() {
             continue;
         };
@@ -126,89 +216,352 @@ fn get_extension_name(args: &Arguments, extension: &OsStr) -> String {
     "unknown type".to_string()
 }
 
-/// Gets extension info from Extensions.toml from file.
-fn get_extension_info(args: &Arguments, extension: String) {
-    println!("## Extension: {}", extension);
-    let extensions_str = match fs::read_to_string(args.extensions_path.clone()) {
-        Ok(c) => c,
-        Err(_) => {
-            println!(
-                "Could not read extensions file: {}",
-                args.extensions_path.to_string_lossy()
-            );
-            return;
-        }
-    };
+/// Gets category of extension from Extensions.toml, `None` if not found.
+fn get_extension_category(args: &Arguments, extension: &str) -> Option<Category> {
+    get_extension_vec(args)
+        .extensions
+        .iter()
+        .find(|extension_data| extension_data.extension == extension)
+        .map(|extension_data| extension_data.category)
+}
 
-    let mut extension_vec: ExtensionVec = toml::from_str(&extensions_str).unwrap();
-    for extension_data in extension_vec.extensions.iter_mut() {
-        if extension_data.extension.ne(&extension) {
+/// Gets extension info from Extensions.toml from file.
+fn get_extension_info(args: &Arguments, extension: &str, report: &mut Report) {
+    writeln!(report, "## Extension: {}", extension);
+    report.note_field(Section::Type, "extension", extension);
+    for extension_data in get_extension_vec(args).extensions.iter() {
+        if extension_data.extension.ne(extension) {
             continue;
         };
-        let category: &str = (&extension_data.category).into();
-        println!("# Category: {}", category);
-        println!("# Name: {}", extension_data.name);
-        println!("# Media type (mime): {}", extension_data.preferred_mime);
+        if args.has_section(Section::Type) {
+            let category: &str = (&extension_data.category).into();
+            report.print_field(Section::Type, "category", "Category", category);
+            report.print_field(Section::Type, "name", "Name", &extension_data.name);
+            report.print_field(
+                Section::Type,
+                "mime",
+                "Media type (mime)",
+                &extension_data.preferred_mime,
+            );
+        }
 
         // Maybe print ids???
-        if args.extension_info {
+        if args.has_section(Section::Extension) {
             if extension_data.mime.len() > 1 {
-                print!("# Other possible media types (mimes): ");
-                for mime in extension_data.mime.iter_mut() {
+                let mut mimes = String::new();
+                for mime in extension_data.mime.iter() {
                     if mime == &extension_data.preferred_mime {
                         continue;
                     }
-                    print!("{}; ", mime);
+                    mimes += &format!("{}; ", mime);
                 }
-                println!();
+                report.print_field(
+                    Section::Extension,
+                    "mimes",
+                    "Other possible media types (mimes)",
+                    mimes,
+                );
             }
-            println!("# Description: {}", extension_data.description);
-            println!("# Further reading: {}", extension_data.further_reading)
+            report.print_field(
+                Section::Extension,
+                "description",
+                "Description",
+                &extension_data.description,
+            );
+            report.print_field(
+                Section::Extension,
+                "further-reading",
+                "Further reading",
+                &extension_data.further_reading,
+            );
         }
     }
 }
 
-/// Gets info about file.
-fn get_info(args: &Arguments) {
+/// Gets one line about file like `file` does, description of its type or media type and charset.
+fn get_line_info(args: &Arguments, mode: LineMode, report: &mut Report) {
+    let path = args.stream_path.as_ref().unwrap_or(&args.file_path);
+    let prefix = if args.brief {
+        String::new()
+    } else {
+        format!("{}: ", path.display())
+    };
+    let (description, mime_type, encoding) = if args.file_path.is_dir() {
+        (
+            "directory".to_string(),
+            "inode/directory".to_string(),
+            "binary".to_string(),
+        )
+    } else {
+        crate::limits::start(args.limits);
+        let input = match Input::open(&args.file_path) {
+            Ok(input) => input,
+            Err(e) => {
+                writeln!(report, "{}cannot open ({})", prefix, e);
+                return;
+            }
+        };
+        let data = input.bytes();
+        let file_extension = args.name_path().extension().unwrap_or(OsStr::new(""));
+        // Errors of detection would break one-line output.
+        let extension = detect_extension(args, &input, file_extension, &mut Report::dropped());
+        let text_encoding = crate::plaintext::get_text_encoding(data);
+        let encoding = text_encoding
+            .as_deref()
+            .map_or("binary".to_string(), crate::plaintext::get_charset);
+        let extension_data = get_extension_vec(args)
+            .extensions
+            .iter()
+            .find(|data| data.extension == extension);
+        match extension_data {
+            _ if data.is_empty() => (
+                "empty".to_string(),
+                "inode/x-empty".to_string(),
+                "binary".to_string(),
+            ),
+            Some(extension_data) => {
+                // Type of zip-based formats is found by their entries, not by magic bytes.
+                let is_zip = extension != "zip"
+                    && crate::archive::get_archive_kind(data) == Some(ArchiveKind::Zip);
+                let description = if is_zip {
+                    format!("{} (ZIP container)", extension_data.name)
+                } else {
+                    extension_data.name.clone()
+                };
+                (description, extension_data.preferred_mime.clone(), encoding)
+            }
+            None => match &text_encoding {
+                Some(text_encoding) => (
+                    format!("{} text", text_encoding),
+                    "text/plain".to_string(),
+                    encoding,
+                ),
+                None => (
+                    "data".to_string(),
+                    "application/octet-stream".to_string(),
+                    encoding,
+                ),
+            },
+        }
+    };
+    let line = match mode {
+        LineMode::Description => description,
+        LineMode::MimeType => mime_type,
+        LineMode::MimeEncoding => encoding,
+        LineMode::Mime => format!("{}; charset={}", mime_type, encoding),
+    };
+    writeln!(report, "{}{}", prefix, line);
+}
+
+/// Gets info about file, or only values of selected fields.
+fn get_info(args: &Arguments, report: &mut Report) {
+    if let Some(mode) = args.line {
+        return get_line_info(args, mode, report);
+    }
+    if args.hash_sums {
+        return crate::hash::get_sums_info(args, report);
+    }
+    if args.fields.is_empty() {
+        return get_file_info(args, report);
+    }
+    let mut fields = Report::fields();
+    get_file_info(args, &mut fields);
+    let values = fields.into_fields();
+    for field in args.fields.iter() {
+        // Missing field is printed as empty line, so lines still match fields.
+        let value = values
+            .iter()
+            .find(|(name, _)| name == field)
+            .map_or("", |(_, value)| value);
+        writeln!(report, "{}", value);
+    }
+}
+
+/// Gets report about file.
+fn get_file_info(args: &Arguments, report: &mut Report) {
     if !args.file_path.exists() {
-        println!("Path to file does not exist.");
+        writeln!(report, "Path to file does not exist.");
         return;
     }
     if !args.file_path.is_file() {
-        println!("Path to file leads to directory, not file.");
+        writeln!(report, "Path to file leads to directory, not file.");
         return;
     }
 
-    let file_extension: &std::ffi::OsStr = args.file_path.extension().unwrap_or(OsStr::new(""));
-    let buf_reader: BufReader<fs::File> = BufReader::new(fs::File::open(&args.file_path).unwrap());
-
-    if !args.ignore_general {
-        get_general_info(args)
+    let file_extension: &std::ffi::OsStr = args.name_path().extension().unwrap_or(OsStr::new(""));
+    // File is opened only once, detection and all analyzers share it.
+    crate::limits::start(args.limits);
+    let mut input = match Input::open(&args.file_path) {
+        Ok(input) => input,
+        Err(e) => {
+            writeln!(report, "Could not open file: {}", e);
+            return;
+        }
     };
+    if args.is_debug {
+        writeln!(report, "Memory-mapped: {}", input.is_mapped());
+    }
+    if let Some(max_read) = args.limits.max_read {
+        if input.truncate(max_read) {
+            crate::limits::reach(Limit::Read);
+        }
+    }
+    get_limited_info(args, &input, file_extension, report);
+    for limit in crate::limits::get_reached() {
+        writeln!(
+            report,
+            "# Truncated: limit {} reached",
+            crate::limits::describe(&args.limits, limit)
+        );
+    }
+}
+
+/// Detects extension of file by its content, extension from name is kept if nothing more specific is found.
+fn detect_extension(
+    args: &Arguments,
+    input: &Input,
+    file_extension: &OsStr,
+    report: &mut Report,
+) -> String {
     let mut extension = file_extension.to_str().unwrap_or("").to_string();
 
     // Now we scan zip data to find some complex types.
     if extension.eq("zip") {
         // If it's a zip, we might need to check for more complex zip types
-        extension = match crate::zip::get_complex_zip_extension(args, buf_reader) {
+        extension = match crate::zip::get_complex_zip_extension(args, input, report) {
             Ok(extension) => extension.to_string(),
             Err(e) => {
-                println!("## Unreadable zip file: {}", e);
+                writeln!(report, "## Unreadable zip file: {}", e);
                 "zip".to_string()
             }
         };
+    } else if ["mp4", "m4a", "m4v", "mov", "heic", "heif", "avif", "3gp"]
+        .contains(&extension.as_str())
+    {
+        // Same container is used for video, audio and images, brands tell which one it is.
+        extension = match crate::isobmff::get_complex_bmff_extension(args, input) {
+            Ok(extension) => extension,
+            Err(e) => {
+                writeln!(report, "## Unreadable ISO base media file: {}", e);
+                extension
+            }
+        };
+    } else if extension.is_empty() || extension.chars().all(|c| c.is_ascii_digit()) {
+        // Executables usually have no extension and versioned libraries end with a number.
+        let magic_extension = crate::elf::get_elf_extension(args, input)
+            .or_else(|| crate::macho::get_macho_extension(input))
+            // Scripts and structured text are recognized by shebang, modelines or prologue.
+            .or_else(|| crate::plaintext::get_text_extension(args, input))
+            // Downloaded apps and documents often lose their name.
+            .or_else(|| {
+                (crate::archive::get_archive_kind(input.bytes()) == Some(ArchiveKind::Zip))
+                    .then(|| crate::zip::get_complex_zip_extension(args, input, report).ok())
+                    .flatten()
+            });
+        if let Some(magic_extension) = magic_extension {
+            extension = magic_extension;
+        }
     }
+    extension
+}
 
-    let buf_reader: BufReader<fs::File> = BufReader::new(fs::File::open(&args.file_path).unwrap());
-    get_extension_info(args, extension);
+/// Gets info about opened file, analysis can be cut short by limits.
+fn get_limited_info(args: &Arguments, input: &Input, file_extension: &OsStr, report: &mut Report) {
+    if args.has_section(Section::General) {
+        get_general_info(args, report)
+    }
+    if args.has_section(Section::Hashes) && !args.hashes.is_empty() {
+        crate::hash::get_hash_info(args, input, report)
+    }
+    let extension = detect_extension(args, input, file_extension, report);
+
+    if args.has_section(Section::Type) || args.has_section(Section::Extension) {
+        get_extension_info(args, &extension, report);
+    }
+    if crate::limits::is_timed_out() {
+        return;
+    }
+    let entries_hashes = args.has_section(Section::Hashes) && !args.hashes.is_empty();
+    let archive_kind = if args.has_section(Section::Entropy) || entries_hashes {
+        crate::archive::get_archive_kind(input.bytes())
+    } else {
+        None
+    };
+    // Archives are listed, other formats have their own metadata.
+    let section = if ["zip", "rar"].map(OsStr::new).contains(&file_extension)
+        || ["jar", "war", "ear"].contains(&extension.as_str())
+    {
+        Section::Archive
+    } else {
+        Section::Metadata
+    };
     // Specific use-cases (even works for specific files like .apk for listing files)
-    if !args.only_general {
+    if args.has_section(section) {
         if file_extension.eq("zip") {
-            crate::zip::get_zip_info(args, buf_reader)
+            crate::zip::get_zip_info(args, input, report)
         } else if file_extension.eq("rar") {
-            crate::rar::get_rar_info(args)
+            crate::rar::get_rar_info(input, report)
+        } else if ["jpg", "jpeg", "jpe", "jfif"]
+            .map(OsStr::new)
+            .contains(&file_extension)
+        {
+            crate::jpeg::get_jpeg_info(args, input, report)
+        } else if ["mp3", "flac", "wav", "ogg", "oga", "opus"]
+            .map(OsStr::new)
+            .contains(&file_extension)
+        {
+            crate::audio::get_audio_info(args, input, report)
+        } else if ["mp4", "m4a", "m4v", "mov", "heic", "heif", "avif", "3gp"]
+            .map(OsStr::new)
+            .contains(&file_extension)
+        {
+            crate::isobmff::get_isobmff_info(args, input, report)
+        } else if ["mkv", "mka", "mks", "webm"]
+            .map(OsStr::new)
+            .contains(&file_extension)
+        {
+            crate::matroska::get_matroska_info(args, input, report)
+        } else if file_extension.eq("pdf") {
+            crate::pdf::get_pdf_info(args, input, report)
+        } else if ["elf", "so", "o", "ko"].contains(&extension.as_str()) {
+            // Detected extension, ELF files rarely have one.
+            crate::elf::get_elf_info(args, input, report)
+        } else if ["exe", "dll", "sys", "ocx", "cpl", "scr", "efi"]
+            .map(OsStr::new)
+            .contains(&file_extension)
+        {
+            crate::pe::get_pe_info(args, input, report)
+        } else if ["macho", "dylib", "bundle"].contains(&extension.as_str()) {
+            crate::macho::get_macho_info(args, input, report)
+        } else if extension.eq("ipa") {
+            crate::macho::get_ipa_info(args, input, report)
+        } else if file_extension.eq("wasm") {
+            crate::wasm::get_wasm_info(args, input, report)
+        } else if file_extension.eq("class") {
+            crate::java::get_class_info(args, input, report)
+        } else if ["jar", "war", "ear"].contains(&extension.as_str()) {
+            crate::java::get_jar_info(args, input, report)
+        } else {
+            // Text files are recognized by content, language is guessed only for source code.
+            let is_code = get_extension_category(args, &extension) == Some(Category::Code);
+            crate::plaintext::get_text_info(
+                args,
+                input,
+                Some(extension.as_str()).filter(|_| is_code),
+                report,
+            )
         };
     }
+    if args.has_section(Section::Entropy) && !crate::limits::is_timed_out() {
+        crate::entropy::get_entropy_info(args, input, report);
+        // Zip-based formats (jar, docx, apk...) are scanned per entry too.
+        if let Some(kind) = archive_kind {
+            crate::entropy::get_entries_entropy_info(args, input, kind, report)
+        }
+    }
+    if let Some(kind) = archive_kind.filter(|_| entries_hashes && !crate::limits::is_timed_out()) {
+        crate::hash::get_entries_hash_info(args, input, kind, report)
+    }
 }
 
 /** GUI */
@@ -477,13 +830,15 @@ fn menu_cb(m: &mut impl MenuExt) {
 /// Boot function.
 fn main() {
     // Console arguments
-    let argm = Command::new("fat")
+    let mut command = Command::new("fat")
         .author("caffidev, caffidev@gmail.com")
         .version("0.3.1")
         .about("fat - File Analysis Tool, analyzes metadata and tries to guess its extension.")
         .disable_help_subcommand(true)
         .disable_help_flag(true)
-        .arg(arg!(<FILE> ... "File to analyze").value_parser(clap::value_parser!(PathBuf)))
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+        .arg(arg!(<FILE> ... "Files or directories to analyze, - for stdin").value_parser(clap::value_parser!(PathBuf)))
         .arg(
             Arg::new("help")
                 .short('?')
@@ -503,7 +858,7 @@ fn main() {
             .action(ArgAction::SetTrue)
                 .short('e')
                 .long("extension-info")
-                .help("Provides more info about extension: MIME type, where to read about it etc..")
+                .help("Provides more info about extension: MIME type, where to read about it etc.., adds extension section")
         )
         .arg(
             Arg::new("debug")
@@ -517,39 +872,371 @@ fn main() {
             .action(ArgAction::SetTrue)
                 .short('h')
                 .long("human")
-                .help("Prints numbers in human-readable way (124 kiB, 76 miB)")
+                .help("Prints sizes in human-readable way (124 KiB, 76 MiB), same as --units iec")
+        )
+        .arg(
+            Arg::new("units")
+                .long("units")
+                .value_name("UNITS")
+                .value_parser(["si", "iec", "bytes"])
+                .help("Units of sizes: si (1.2 MB), iec (1.2 MiB) or bytes (1,234,567 B), plain numbers by default")
+        )
+        .arg(
+            Arg::new("precision")
+                .long("precision")
+                .value_name("DIGITS")
+                .value_parser(clap::value_parser!(u64).range(..=9))
+                .default_value("1")
+                .help("Decimal places of sizes in si and iec units")
         )
         .arg(
             Arg::new("ignore-general")
             .action(ArgAction::SetTrue)
                 .long("ignore-general")
                 .short('i')
-                .help("Provides only general info e.g name, size, when accessed...")
+                .conflicts_with("sections")
+                .help("Provides all info except general one, same as --sections type,archive,metadata,hashes (with only-general provides only info of extension)")
         )
         .arg(
             Arg::new("only-general")
             .action(ArgAction::SetTrue)
                 .long("only-general")
                 .short('o')
-                .help("Provide only special info e.g basic extension info, special metadata of file... (when with ignore-general provides only info of extension)")
+                .conflicts_with("sections")
+                .help("Provides only general info e.g name, size, when accessed..., same as --sections general,type,hashes")
+        )
+        .arg(
+            Arg::new("entropy")
+            .action(ArgAction::SetTrue)
+                .long("entropy")
+                .help("Analyzes entropy and byte distribution of file (and of every entry of ZIP/RAR archive), adds entropy section")
+        )
+        .arg(
+            Arg::new("brief")
+                .action(ArgAction::SetTrue)
+                .short('b')
+                .long("brief")
+                .conflicts_with("fields")
+                .help("Prints one line per file without its name, description of type unless --mime-type or --mime-encoding is given, like file -b")
+        )
+        .arg(
+            Arg::new("mime-type")
+                .action(ArgAction::SetTrue)
+                .long("mime-type")
+                .conflicts_with("fields")
+                .help("Prints media type of every file in one line, like file --mime-type")
+        )
+        .arg(
+            Arg::new("mime-encoding")
+                .action(ArgAction::SetTrue)
+                .long("mime-encoding")
+                .conflicts_with("fields")
+                .help("Prints charset of every file in one line (binary for non-text files), like file --mime-encoding")
+        )
+        .arg(
+            Arg::new("sections")
+                .long("sections")
+                .value_name("SECTIONS")
+                .value_delimiter(',')
+                .value_parser(["general", "type", "extension", "archive", "metadata", "hashes", "entropy"])
+                .help("Sections of report, e.g. --sections general,type (general,type,archive,metadata,hashes by default)")
+        )
+        .arg(
+            Arg::new("fields")
+                .long("fields")
+                .value_name("FIELDS")
+                .value_delimiter(',')
+                .value_parser(|field: &str| Section::check_field(field).map(|_| field.to_string()))
+                .help("Prints only values of fields, one per line, e.g. --fields general.size,type.mime")
+        )
+        .arg(
+            Arg::new("entropy-block")
+                .long("entropy-block")
+                .value_name("BYTES")
+                .value_parser(clap::value_parser!(u64).range(16..))
+                .default_value("4096")
+                .help("Block size for entropy profile.")
+        )
+        .arg(
+            Arg::new("hash")
+                .long("hash")
+                .value_name("ALGORITHMS")
+                .value_delimiter(',')
+                .value_parser(["md5", "sha1", "sha256", "sha512", "blake3", "crc32", "ssdeep", "tlsh"])
+                .help("Computes hashes of file (and of every entry of ZIP/RAR/tar archive), e.g. --hash md5,sha256")
+        )
+        .arg(
+            Arg::new("hash-sums")
+            .action(ArgAction::SetTrue)
+                .long("hash-sums")
+                .requires("hash")
+                .conflicts_with_all(["fields", "brief", "mime-type", "mime-encoding"])
+                .help("Prints hashes of files (not of archive entries) as `<hash>  <file>` lines, readable by sha256sum -c and similar tools.")
+        )
+        .arg(
+            Arg::new("time-format")
+                .long("time-format")
+                .value_name("STYLE")
+                .value_parser(["default", "rfc3339", "iso8601", "unix", "relative"])
+                .default_value("default")
+                .help("Style of printed timestamps, e.g. --time-format rfc3339")
+        )
+        .arg(
+            Arg::new("tz")
+                .long("tz")
+                .value_name("ZONE")
+                .value_parser(|name: &str| TimeZone::from_name(name).map_err(|e| e.to_string()))
+                .default_value("UTC")
+                .help("Time zone of printed timestamps: local, UTC or IANA name like Europe/Prague.")
+        )
+        .arg(
+            Arg::new("name")
+                .long("name")
+                .value_name("NAME")
+                .help("Name of file used to look up its extension, e.g. when reading from stdin (-).")
         )
-        .after_help("This app was written to analyze files, and give as much info about it as possible")
-        .get_matches();
+        .arg(
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help("Count of files analyzed in parallel, when given more files or directories (default is count of CPUs).")
+        )
+        .arg(
+            Arg::new("unordered")
+                .action(ArgAction::SetTrue)
+                .long("unordered")
+                .help("Prints reports of more files as soon as they are done, not in order of input.")
+        )
+        .arg(
+            Arg::new("spool-limit")
+                .long("spool-limit")
+                .value_name("BYTES")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("1073741824")
+                .help("Largest input read from stdin or pipe, it's copied to temporary file for analysis.")
+        )
+        .arg(
+            Arg::new("max-entries")
+                .long("max-entries")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("10000")
+                .help("Most archive entries listed, hashed or scanned, the rest is skipped.")
+        )
+        .arg(
+            Arg::new("max-decompressed")
+                .long("max-decompressed")
+                .value_name("BYTES")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("1073741824")
+                .help("Most bytes decompressed from archive entries and streams of one file.")
+        )
+        .arg(
+            Arg::new("max-depth")
+                .long("max-depth")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("32")
+                .help("Deepest nesting of boxes, objects and name trees which is parsed.")
+        )
+        .arg(
+            Arg::new("max-read")
+                .long("max-read")
+                .value_name("BYTES")
+                .value_parser(clap::value_parser!(u64))
+                .help("Gives only first bytes of file to analyzers.")
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64))
+                .help("Stops analysis of one file after time, report is truncated.")
+        )
+        .subcommand(
+            Command::new("compare")
+                .about("Compares two files by fuzzy hashes, or clusters files (and directories) by similarity.")
+                .arg(arg!(<PATHS> ... "Files or directories to compare").value_parser(clap::value_parser!(PathBuf)))
+                .arg(
+                    Arg::new("algorithm")
+                        .long("algorithm")
+                        .value_parser(["ssdeep", "tlsh"])
+                        .default_value("ssdeep")
+                        .help("Fuzzy hash used for clustering.")
+                )
+                .arg(
+                    Arg::new("threshold")
+                        .long("threshold")
+                        .value_parser(clap::value_parser!(u32))
+                        .help("Minimal ssdeep score (default 50) or maximal TLSH distance (default 100) of similar files.")
+                )
+        )
+        .after_help("This app was written to analyze files, and give as much info about it as possible");
+    let argm = command.get_matches_mut();
+
+    if let Some(compare) = argm.subcommand_matches("compare") {
+        let paths: Vec<PathBuf> = compare
+            .get_many::<PathBuf>("PATHS")
+            .unwrap()
+            .cloned()
+            .collect();
+        let algorithm =
+            HashAlgorithm::from_str(compare.get_one::<String>("algorithm").unwrap()).unwrap();
+        let threshold = match (compare.get_one::<u32>("threshold"), algorithm) {
+            (Some(threshold), _) => *threshold,
+            (None, HashAlgorithm::Tlsh) => 100,
+            (None, _) => 50,
+        };
+        if let Err(e) = crate::fuzzy::compare(&paths, algorithm, threshold) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    let file_path: PathBuf = argm.get_one::<PathBuf>("FILE").unwrap().clone();
+    let paths: Vec<PathBuf> = argm.get_many::<PathBuf>("FILE").unwrap().cloned().collect();
+    // More files and directories are analyzed in parallel.
+    let files = (paths.len() > 1 || paths[0].is_dir()).then(|| crate::batch::collect_files(&paths));
+    let mut file_path = paths[0].clone();
+    // Stdin and pipes can be read only once, but analyzers reopen and seek files.
+    let stream_path =
+        (files.is_none() && crate::spool::is_stream(&file_path)).then(|| file_path.clone());
+    let spool = match &stream_path {
+        Some(stream_path) => match crate::spool::spool_stream(
+            stream_path,
+            *argm.get_one::<u64>("spool-limit").unwrap(),
+        ) {
+            Ok(spool) => Some(spool),
+            Err(e) => {
+                println!(
+                    "Could not read {}: {}",
+                    crate::spool::describe_stream(stream_path),
+                    e
+                );
+                return;
+            }
+        },
+        None => None,
+    };
+    if let Some(spool) = &spool {
+        file_path = spool.path.clone();
+    }
     // Getting path to extensions.toml (forced to use env::current_dir())
     let mut extensions_path = env::current_dir().unwrap().clone();
     extensions_path.push("Extensions.toml");
 
+    let fields: Vec<String> = argm
+        .get_many::<String>("fields")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    let mut sections: Vec<Section> = match argm.get_many::<String>("sections") {
+        Some(names) => names.map(|name| Section::from_str(name).unwrap()).collect(),
+        // Only sections with selected fields are analyzed.
+        None if !fields.is_empty() => fields
+            .iter()
+            .filter_map(|field| Section::of_field(field))
+            .collect(),
+        None => match (
+            argm.get_flag("only-general"),
+            argm.get_flag("ignore-general"),
+        ) {
+            (true, true) => vec![Section::Type],
+            (true, false) => vec![Section::General, Section::Type, Section::Hashes],
+            (false, true) => DEFAULT_SECTIONS
+                .into_iter()
+                .filter(|section| *section != Section::General)
+                .collect(),
+            (false, false) => DEFAULT_SECTIONS.to_vec(),
+        },
+    };
+    let mut hashes: Vec<HashAlgorithm> = argm
+        .get_many::<String>("hash")
+        .unwrap_or_default()
+        .map(|name| HashAlgorithm::from_str(name).unwrap())
+        .collect();
+    // Selected hash fields are computed even without --hash.
+    for field in fields.iter() {
+        if let Some(algorithm) = field
+            .strip_prefix("hashes.")
+            .and_then(|name| HashAlgorithm::from_str(name).ok())
+        {
+            if !hashes.contains(&algorithm) {
+                hashes.push(algorithm);
+            }
+        }
+    }
+    // Checksum lines can be checked only by tool of one algorithm.
+    if argm.get_flag("hash-sums") && hashes.len() != 1 {
+        command
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--hash-sums needs exactly one --hash algorithm",
+            )
+            .exit();
+    }
+    // Fuzzy hashes are compared by similarity, checksum tools can't check them.
+    if argm.get_flag("hash-sums")
+        && hashes
+            .iter()
+            .any(|algorithm| matches!(algorithm, HashAlgorithm::Ssdeep | HashAlgorithm::Tlsh))
+    {
+        command
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--hash-sums needs checksum algorithm, not ssdeep or tlsh",
+            )
+            .exit();
+    }
+    if argm.get_flag("extension-info") {
+        sections.push(Section::Extension);
+    }
+    if argm.get_flag("entropy") {
+        sections.push(Section::Entropy);
+    }
     let args = Arguments {
         file_path,
         extensions_path,
         gui: argm.get_flag("gui"),
         is_debug: argm.get_flag("debug"),
-        is_human: argm.get_flag("human"),
-        only_general: argm.get_flag("only-general"),
-        ignore_general: argm.get_flag("ignore-general"),
-        extension_info: argm.get_flag("extension-info"),
+        units: match argm.get_one::<String>("units") {
+            Some(units) => SizeUnits::from_str(units).unwrap(),
+            None if argm.get_flag("human") => SizeUnits::Iec,
+            None => SizeUnits::Plain,
+        },
+        size_precision: *argm.get_one::<u64>("precision").unwrap() as usize,
+        sections,
+        fields,
+        line: match (
+            argm.get_flag("mime-type"),
+            argm.get_flag("mime-encoding"),
+            argm.get_flag("brief"),
+        ) {
+            (true, true, _) => Some(LineMode::Mime),
+            (true, false, _) => Some(LineMode::MimeType),
+            (false, true, _) => Some(LineMode::MimeEncoding),
+            (false, false, true) => Some(LineMode::Description),
+            (false, false, false) => None,
+        },
+        brief: argm.get_flag("brief"),
+        entropy_block_size: *argm.get_one::<u64>("entropy-block").unwrap() as usize,
+        hashes,
+        hash_sums: argm.get_flag("hash-sums"),
+        time_format: TimeFormat::from_str(argm.get_one::<String>("time-format").unwrap()).unwrap(),
+        time_zone: argm.get_one::<TimeZone>("tz").unwrap().clone(),
+        name: argm.get_one::<String>("name").cloned(),
+        stream_path,
+        limits: Limits {
+            max_entries: *argm.get_one::<u64>("max-entries").unwrap() as usize,
+            max_decompressed: *argm.get_one::<u64>("max-decompressed").unwrap(),
+            max_depth: *argm.get_one::<u64>("max-depth").unwrap() as usize,
+            max_read: argm.get_one::<u64>("max-read").copied(),
+            timeout: argm
+                .get_one::<u64>("timeout")
+                .map(|seconds| std::time::Duration::from_secs(*seconds)),
+        },
     };
 
     if args.is_debug {
@@ -590,7 +1277,13 @@ fn main() {
         window.show();
         window.set_callback(win_cb);
         app.run().unwrap();
+    } else if let Some(files) = files {
+        let jobs = match argm.get_one::<u64>("jobs") {
+            Some(jobs) => *jobs as usize,
+            None => std::thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+        };
+        crate::batch::analyze_files(&args, &files, jobs, !argm.get_flag("unordered"));
     } else {
-        get_info(&args);
+        get_info(&args, &mut Report::stdout());
     }
 }
@@ -0,0 +1,560 @@
+use crate::datetime::{format_timestamp, Timestamp};
+use crate::input::Input;
+use crate::limits::{allow_depth, is_timed_out};
+use crate::report::Report;
+use crate::{format_size, Arguments};
+use std::io::{Error, ErrorKind};
+
+/// Seconds between 1970-01-01 and 2001-01-01 (Matroska date epoch).
+const MATROSKA_EPOCH_OFFSET: i64 = 978_307_200;
+
+const EBML: u32 = 0x1A45DFA3;
+const SEGMENT: u32 = 0x18538067;
+const INFO: u32 = 0x1549A966;
+const TRACKS: u32 = 0x1654AE6B;
+const CHAPTERS: u32 = 0x1043A770;
+const ATTACHMENTS: u32 = 0x1941A469;
+const TAGS: u32 = 0x1254C367;
+const CUES: u32 = 0x1C53BB6B;
+const CLUSTER: u32 = 0x1F43B675;
+
+/// Elements which can appear inside of cluster, used to find end of unknown-sized clusters.
+const CLUSTER_CHILDREN: [u32; 7] = [0xE7, 0xA7, 0xAB, 0xA3, 0xA0, 0xAF, 0x5854];
+
+pub struct MkvTrack {
+    pub number: u64,
+    pub kind: u64,
+    pub codec_id: String,
+    pub codec_name: Option<String>,
+    pub name: Option<String>,
+    pub language: String,
+    pub enabled: bool,
+    pub default: bool,
+    pub forced: bool,
+    pub dimensions: Option<(u64, u64)>,
+    pub sampling_frequency: Option<f64>,
+    pub channels: Option<u64>,
+    pub bit_depth: Option<u64>,
+}
+
+pub struct Attachment {
+    pub name: String,
+    pub mime: String,
+    pub description: Option<String>,
+    pub size: usize,
+}
+
+#[derive(Default)]
+pub struct MatroskaInfo {
+    pub doc_type: String,
+    pub doc_type_version: u64,
+    pub title: Option<String>,
+    pub muxing_app: Option<String>,
+    pub writing_app: Option<String>,
+    /// Duration in seconds.
+    pub duration: Option<f64>,
+    pub date: Option<Timestamp>,
+    pub tracks: Vec<MkvTrack>,
+    /// (start in nanoseconds, title) of every chapter, nested chapters included.
+    pub chapters: Vec<(u64, String)>,
+    pub attachments: Vec<Attachment>,
+    /// (target, name, value) of every simple tag.
+    pub tags: Vec<(String, String, String)>,
+    pub cue_points: Option<usize>,
+    pub clusters: usize,
+}
+
+/// Reads variable length integer, returns (value with marker removed, length).
+/// Value of None means "unknown size" (all value bits set).
+fn read_vint(data: &[u8], pos: usize) -> Option<(Option<u64>, usize)> {
+    let first = *data.get(pos)?;
+    if first == 0 {
+        return None;
+    }
+    let length = first.leading_zeros() as usize + 1;
+    let bytes = data.get(pos..pos + length)?;
+    let mut value = (first as u64) & (0xFF >> length);
+    for b in &bytes[1..] {
+        value = (value << 8) | *b as u64;
+    }
+    let unknown = value == (1u64 << (7 * length)) - 1;
+    Some((if unknown { None } else { Some(value) }, length))
+}
+
+/// Reads element header, returns (id, size, header length).
+fn read_header(data: &[u8], pos: usize) -> Option<(u32, Option<u64>, usize)> {
+    let first = *data.get(pos)?;
+    let id_length = first.leading_zeros() as usize + 1;
+    if id_length > 4 {
+        return None;
+    }
+    let id = data
+        .get(pos..pos + id_length)?
+        .iter()
+        .fold(0u32, |acc, b| (acc << 8) | *b as u32);
+    let (size, size_length) = read_vint(data, pos + id_length)?;
+    Some((id, size, id_length + size_length))
+}
+
+/// Reads children of master element between start and end as (id, payload) pairs.
+fn get_children(data: &[u8], start: usize, end: usize) -> Vec<(u32, &[u8])> {
+    let mut children = Vec::new();
+    let mut pos = start;
+    while pos < end && !is_timed_out() {
+        let Some((id, size, header)) = read_header(data, pos) else {
+            break;
+        };
+        let payload_end = match size {
+            Some(size) => (pos + header).saturating_add(size as usize).min(end),
+            None => end,
+        };
+        children.push((id, &data[(pos + header).min(payload_end)..payload_end]));
+        pos = payload_end;
+    }
+    children
+}
+
+fn get_uint(payload: &[u8]) -> u64 {
+    payload
+        .iter()
+        .take(8)
+        .fold(0, |acc, b| (acc << 8) | *b as u64)
+}
+
+fn get_int(payload: &[u8]) -> i64 {
+    let value = get_uint(payload);
+    let bits = payload.len().min(8) * 8;
+    if bits == 0 || bits == 64 {
+        return value as i64;
+    }
+    // Sign-extend
+    ((value << (64 - bits)) as i64) >> (64 - bits)
+}
+
+fn get_float(payload: &[u8]) -> Option<f64> {
+    match payload.len() {
+        4 => Some(f32::from_be_bytes(payload.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(payload.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+fn get_string(payload: &[u8]) -> String {
+    String::from_utf8_lossy(payload)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+/// Gets name of Matroska track type.
+pub fn track_type(kind: u64) -> &'static str {
+    match kind {
+        1 => "video",
+        2 => "audio",
+        3 => "complex",
+        0x10 => "logo",
+        0x11 => "subtitle",
+        0x12 => "buttons",
+        0x20 => "control",
+        0x21 => "metadata",
+        _ => "unknown",
+    }
+}
+
+fn get_track(entry: &[u8]) -> MkvTrack {
+    let mut track = MkvTrack {
+        number: 0,
+        kind: 0,
+        codec_id: String::new(),
+        codec_name: None,
+        name: None,
+        language: "eng".to_string(),
+        enabled: true,
+        default: true,
+        forced: false,
+        dimensions: None,
+        sampling_frequency: None,
+        channels: None,
+        bit_depth: None,
+    };
+    let mut bcp47 = None;
+    for (id, payload) in get_children(entry, 0, entry.len()) {
+        match id {
+            0xD7 => track.number = get_uint(payload),
+            0x83 => track.kind = get_uint(payload),
+            0x86 => track.codec_id = get_string(payload),
+            0x258688 => track.codec_name = Some(get_string(payload)),
+            0x536E => track.name = Some(get_string(payload)),
+            0x22B59C => track.language = get_string(payload),
+            0x22B59D => bcp47 = Some(get_string(payload)),
+            0xB9 => track.enabled = get_uint(payload) != 0,
+            0x88 => track.default = get_uint(payload) != 0,
+            0x55AA => track.forced = get_uint(payload) != 0,
+            0xE0 => {
+                let (mut width, mut height) = (0, 0);
+                for (id, payload) in get_children(payload, 0, payload.len()) {
+                    match id {
+                        0xB0 => width = get_uint(payload),
+                        0xBA => height = get_uint(payload),
+                        _ => (),
+                    }
+                }
+                track.dimensions = Some((width, height));
+            }
+            0xE1 => {
+                track.sampling_frequency = Some(8000.0);
+                track.channels = Some(1);
+                for (id, payload) in get_children(payload, 0, payload.len()) {
+                    match id {
+                        0xB5 => track.sampling_frequency = get_float(payload),
+                        0x9F => track.channels = Some(get_uint(payload)),
+                        0x6264 => track.bit_depth = Some(get_uint(payload)),
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    // LanguageBCP47 takes precedence over legacy Language element.
+    if let Some(bcp47) = bcp47 {
+        track.language = bcp47;
+    }
+    track
+}
+
+fn get_chapter_atoms(atom: &[u8], chapters: &mut Vec<(u64, String)>, depth: usize) {
+    let mut start = 0;
+    let mut title = String::new();
+    let mut nested = Vec::new();
+    for (id, payload) in get_children(atom, 0, atom.len()) {
+        match id {
+            0x91 => start = get_uint(payload),
+            0x80 if title.is_empty() => {
+                for (id, payload) in get_children(payload, 0, payload.len()) {
+                    if id == 0x85 {
+                        title = get_string(payload);
+                    }
+                }
+            }
+            0xB6 => nested.push(payload),
+            _ => (),
+        }
+    }
+    chapters.push((start, title));
+    if !allow_depth(depth) {
+        return;
+    }
+    for payload in nested {
+        get_chapter_atoms(payload, chapters, depth + 1);
+    }
+}
+
+fn get_simple_tags(
+    target: &str,
+    tag: &[u8],
+    tags: &mut Vec<(String, String, String)>,
+    depth: usize,
+) {
+    let mut name = String::new();
+    let mut value = String::new();
+    let mut nested = Vec::new();
+    for (id, payload) in get_children(tag, 0, tag.len()) {
+        match id {
+            0x45A3 => name = get_string(payload),
+            0x4487 => value = get_string(payload),
+            0x4485 => value = format!("{} bytes of binary data", payload.len()),
+            0x67C8 => nested.push(payload),
+            _ => (),
+        }
+    }
+    tags.push((target.to_string(), name.clone(), value));
+    if !allow_depth(depth) {
+        return;
+    }
+    for payload in nested {
+        get_simple_tags(&format!("{}/{}", target, name), payload, tags, depth + 1);
+    }
+}
+
+fn get_tags(payload: &[u8], tags: &mut Vec<(String, String, String)>) {
+    for (id, tag) in get_children(payload, 0, payload.len()) {
+        if id != 0x7373 {
+            continue;
+        }
+        let mut target = "file".to_string();
+        for (id, payload) in get_children(tag, 0, tag.len()) {
+            if id != 0x63C0 {
+                continue;
+            }
+            for (id, payload) in get_children(payload, 0, payload.len()) {
+                match id {
+                    0x63CA => target = get_string(payload),
+                    0x68CA if target == "file" => target = format!("level {}", get_uint(payload)),
+                    0x63C5 => target = format!("track uid {}", get_uint(payload)),
+                    _ => (),
+                }
+            }
+        }
+        for (id, payload) in get_children(tag, 0, tag.len()) {
+            if id == 0x67C8 {
+                get_simple_tags(&target, payload, tags, 0);
+            }
+        }
+    }
+}
+
+/// Finds end of cluster with unknown size by walking its children.
+fn get_unknown_cluster_end(data: &[u8], mut pos: usize) -> usize {
+    while let Some((id, size, header)) = read_header(data, pos).filter(|_| !is_timed_out()) {
+        match size {
+            Some(size) if CLUSTER_CHILDREN.contains(&id) => {
+                pos = (pos + header).saturating_add(size as usize)
+            }
+            _ => break,
+        }
+    }
+    pos.min(data.len())
+}
+
+/// Reads EBML header and top level elements of Matroska segment.
+pub fn get_matroska_structure(data: &[u8]) -> Result<MatroskaInfo, Error> {
+    let Some((EBML, Some(size), header)) = read_header(data, 0) else {
+        return Err(Error::new(ErrorKind::InvalidData, "missing EBML header"));
+    };
+    let mut info = MatroskaInfo {
+        doc_type: "matroska".to_string(),
+        doc_type_version: 1,
+        ..Default::default()
+    };
+    let ebml_end = (header + size as usize).min(data.len());
+    for (id, payload) in get_children(data, header, ebml_end) {
+        match id {
+            0x4282 => info.doc_type = get_string(payload),
+            0x4287 => info.doc_type_version = get_uint(payload),
+            _ => (),
+        }
+    }
+
+    let Some((SEGMENT, size, header)) = read_header(data, ebml_end) else {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "missing Segment element",
+        ));
+    };
+    let segment_end = match size {
+        Some(size) => (ebml_end + header)
+            .saturating_add(size as usize)
+            .min(data.len()),
+        None => data.len(),
+    };
+
+    let mut timestamp_scale = 1_000_000;
+    let mut raw_duration = None;
+    let mut pos = ebml_end + header;
+    while pos < segment_end && !is_timed_out() {
+        let Some((id, size, header)) = read_header(data, pos) else {
+            break;
+        };
+        let end = match size {
+            Some(size) => (pos + header)
+                .saturating_add(size as usize)
+                .min(segment_end),
+            None if id == CLUSTER => get_unknown_cluster_end(data, pos + header),
+            None => segment_end,
+        };
+        let payload = &data[(pos + header).min(end)..end];
+        match id {
+            INFO => {
+                for (id, payload) in get_children(payload, 0, payload.len()) {
+                    match id {
+                        0x2AD7B1 => timestamp_scale = get_uint(payload),
+                        0x4489 => raw_duration = get_float(payload),
+                        0x7BA9 => info.title = Some(get_string(payload)),
+                        0x4D80 => info.muxing_app = Some(get_string(payload)),
+                        0x5741 => info.writing_app = Some(get_string(payload)),
+                        0x4461 => {
+                            let seconds = get_int(payload) / 1_000_000_000 + MATROSKA_EPOCH_OFFSET;
+                            info.date = Some(Timestamp::from_seconds(seconds));
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            TRACKS => {
+                for (id, payload) in get_children(payload, 0, payload.len()) {
+                    if id == 0xAE {
+                        info.tracks.push(get_track(payload));
+                    }
+                }
+            }
+            CHAPTERS => {
+                for (id, edition) in get_children(payload, 0, payload.len()) {
+                    if id != 0x45B9 {
+                        continue;
+                    }
+                    for (id, atom) in get_children(edition, 0, edition.len()) {
+                        if id == 0xB6 {
+                            get_chapter_atoms(atom, &mut info.chapters, 0);
+                        }
+                    }
+                }
+            }
+            ATTACHMENTS => {
+                for (id, file) in get_children(payload, 0, payload.len()) {
+                    if id != 0x61A7 {
+                        continue;
+                    }
+                    let mut attachment = Attachment {
+                        name: String::new(),
+                        mime: String::new(),
+                        description: None,
+                        size: 0,
+                    };
+                    for (id, payload) in get_children(file, 0, file.len()) {
+                        match id {
+                            0x466E => attachment.name = get_string(payload),
+                            0x4660 => attachment.mime = get_string(payload),
+                            0x467E => attachment.description = Some(get_string(payload)),
+                            0x465C => attachment.size = payload.len(),
+                            _ => (),
+                        }
+                    }
+                    info.attachments.push(attachment);
+                }
+            }
+            TAGS => get_tags(payload, &mut info.tags),
+            CUES => {
+                let points = get_children(payload, 0, payload.len())
+                    .iter()
+                    .filter(|(id, _)| *id == 0xBB)
+                    .count();
+                info.cue_points = Some(points);
+            }
+            CLUSTER => info.clusters += 1,
+            _ => (),
+        }
+        if end <= pos {
+            break;
+        }
+        pos = end;
+    }
+    info.duration = raw_duration.map(|d| d * timestamp_scale as f64 / 1e9);
+    Ok(info)
+}
+
+fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0);
+    let hours = (total / 3600.0).floor();
+    let minutes = ((total - hours * 3600.0) / 60.0).floor();
+    format!(
+        "{:0>2}:{:0>2}:{:06.3}",
+        hours,
+        minutes,
+        total - hours * 3600.0 - minutes * 60.0
+    )
+}
+
+/// Gets specified Matroska/WebM info about file.
+pub fn get_matroska_info(args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## Matroska information");
+    let data = input.bytes();
+    let info = match get_matroska_structure(data) {
+        Ok(info) => info,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
+
+    writeln!(
+        report,
+        "# Document type: {} v{}",
+        info.doc_type, info.doc_type_version
+    );
+    if let Some(title) = &info.title {
+        writeln!(report, "# Title: {}", title);
+    }
+    if let Some(muxing_app) = &info.muxing_app {
+        writeln!(report, "# Muxing application: {}", muxing_app);
+    }
+    if let Some(writing_app) = &info.writing_app {
+        writeln!(report, "# Writing application: {}", writing_app);
+    }
+    if let Some(duration) = info.duration {
+        writeln!(report, "# Duration: {}", format_duration(duration));
+    }
+    if let Some(date) = &info.date {
+        writeln!(report, "# Date: {}", format_timestamp(args, *date));
+    }
+
+    writeln!(report, "# Tracks: {}", info.tracks.len());
+    for track in info.tracks.iter() {
+        write!(
+            report,
+            "{}: {} ({}), language {}",
+            track.number,
+            track_type(track.kind),
+            track.codec_id,
+            track.language
+        );
+        if let Some((width, height)) = track.dimensions {
+            write!(report, ", {}x{}", width, height);
+        }
+        if let Some(frequency) = track.sampling_frequency {
+            write!(report, ", {} Hz", frequency);
+        }
+        if let Some(channels) = track.channels {
+            write!(report, ", {} channel(s)", channels);
+        }
+        if let Some(bit_depth) = track.bit_depth {
+            write!(report, ", {} bit", bit_depth);
+        }
+        if let Some(name) = &track.name {
+            write!(report, ", \"{}\"", name);
+        }
+        if track.default {
+            write!(report, " (default)");
+        }
+        if track.forced {
+            write!(report, " (forced)");
+        }
+        if !track.enabled {
+            write!(report, " (disabled)");
+        }
+        writeln!(report);
+    }
+
+    if !info.chapters.is_empty() {
+        writeln!(report, "# Chapters: {}", info.chapters.len());
+        for (start, title) in info.chapters.iter() {
+            writeln!(report, "{} {}", format_duration(*start as f64 / 1e9), title);
+        }
+    }
+    if !info.attachments.is_empty() {
+        writeln!(report, "# Attachments: {}", info.attachments.len());
+        for attachment in info.attachments.iter() {
+            write!(
+                report,
+                "\"{}\" ({}) ({})",
+                attachment.name,
+                attachment.mime,
+                format_size(args, attachment.size as u64)
+            );
+            if let Some(description) = &attachment.description {
+                write!(report, " ({})", description);
+            }
+            writeln!(report);
+        }
+    }
+    if !info.tags.is_empty() {
+        writeln!(report, "# Tags:");
+        for (target, name, value) in info.tags.iter() {
+            writeln!(report, "[{}] {}: {}", target, name, value);
+        }
+    }
+    match info.cue_points {
+        Some(points) => writeln!(report, "# Cues: {} cue point(s)", points),
+        None => writeln!(report, "# Cues: absent (seeking will be slow)"),
+    }
+    writeln!(report, "# Clusters: {}", info.clusters);
+}
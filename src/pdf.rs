@@ -0,0 +1,1185 @@
+use crate::datetime::{format_parsed_datetime, parse_iso8601};
+use crate::input::Input;
+use crate::limits::{allow_depth, is_timed_out, limit_decompressed};
+use crate::report::Report;
+use crate::{format_size, Arguments};
+use flate2::read::ZlibDecoder;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Error, ErrorKind, Read};
+use time::{Date, Month, PrimitiveDateTime, Time, UtcOffset};
+
+/// Decoded streams bigger than this are cut, protects from decompression bombs.
+const MAX_DECODED_STREAM: u64 = 64 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PdfObject {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Name(String),
+    String(Vec<u8>),
+    Array(Vec<PdfObject>),
+    Dictionary(Vec<(String, PdfObject)>),
+    Reference(u32, u16),
+    /// Stream dictionary with start and length of raw data in file.
+    Stream(Vec<(String, PdfObject)>, usize, usize),
+}
+
+impl PdfObject {
+    /// Gets dictionary entries of dictionary or stream.
+    pub fn dictionary(&self) -> Option<&[(String, PdfObject)]> {
+        match self {
+            PdfObject::Dictionary(entries) | PdfObject::Stream(entries, _, _) => Some(entries),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&PdfObject> {
+        self.dictionary()?
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            PdfObject::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_name(&self) -> Option<&str> {
+        match self {
+            PdfObject::Name(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// Cross-reference section found by following startxref and /Prev chain.
+pub struct XrefSection {
+    pub offset: usize,
+    /// "table" or "stream"
+    pub kind: &'static str,
+    pub entries: usize,
+    pub compressed_entries: usize,
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b'\0' | b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+fn is_delimiter(b: u8) -> bool {
+    matches!(
+        b,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+/// Tokenizer and object parser over PDF bytes.
+pub struct Lexer<'a> {
+    data: &'a [u8],
+    pub pos: usize,
+    /// Names written with #xx escapes, often used to hide /JavaScript and alike.
+    pub escaped_names: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(data: &'a [u8], pos: usize) -> Self {
+        Lexer {
+            data,
+            pos,
+            escaped_names: 0,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(&b) = self.data.get(self.pos) {
+            if is_whitespace(b) {
+                self.pos += 1;
+            } else if b == b'%' {
+                while let Some(&b) = self.data.get(self.pos) {
+                    if b == b'\r' || b == b'\n' {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn starts_with(&self, token: &[u8]) -> bool {
+        self.data[self.pos.min(self.data.len())..].starts_with(token)
+    }
+
+    fn read_regular(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        while let Some(&b) = self.data.get(self.pos) {
+            if is_whitespace(b) || is_delimiter(b) {
+                break;
+            }
+            self.pos += 1;
+        }
+        &self.data[start..self.pos]
+    }
+
+    fn read_name(&mut self) -> String {
+        self.pos += 1;
+        let raw = self.read_regular();
+        let mut name = Vec::with_capacity(raw.len());
+        let mut i = 0;
+        while i < raw.len() {
+            if raw[i] == b'#' && i + 2 < raw.len() {
+                if let Ok(b) = u8::from_str_radix(&String::from_utf8_lossy(&raw[i + 1..i + 3]), 16)
+                {
+                    name.push(b);
+                    self.escaped_names += 1;
+                    i += 3;
+                    continue;
+                }
+            }
+            name.push(raw[i]);
+            i += 1;
+        }
+        String::from_utf8_lossy(&name).to_string()
+    }
+
+    fn read_literal_string(&mut self) -> Vec<u8> {
+        self.pos += 1;
+        let mut out = Vec::new();
+        let mut depth = 1;
+        while let Some(&b) = self.data.get(self.pos) {
+            self.pos += 1;
+            match b {
+                b'(' => {
+                    depth += 1;
+                    out.push(b);
+                }
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                    out.push(b);
+                }
+                b'\\' => {
+                    let Some(&next) = self.data.get(self.pos) else {
+                        break;
+                    };
+                    self.pos += 1;
+                    match next {
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'b' => out.push(0x08),
+                        b'f' => out.push(0x0C),
+                        b'0'..=b'7' => {
+                            let mut value = (next - b'0') as u32;
+                            for _ in 0..2 {
+                                match self.data.get(self.pos) {
+                                    Some(&d @ b'0'..=b'7') => {
+                                        value = value * 8 + (d - b'0') as u32;
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            out.push(value as u8);
+                        }
+                        // Line continuation
+                        b'\r' => {
+                            if self.data.get(self.pos) == Some(&b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => (),
+                        other => out.push(other),
+                    }
+                }
+                _ => out.push(b),
+            }
+        }
+        out
+    }
+
+    fn read_hex_string(&mut self) -> Vec<u8> {
+        self.pos += 1;
+        let mut digits = Vec::new();
+        while let Some(&b) = self.data.get(self.pos) {
+            self.pos += 1;
+            if b == b'>' {
+                break;
+            }
+            if b.is_ascii_hexdigit() {
+                digits.push(b);
+            }
+        }
+        if digits.len() % 2 == 1 {
+            digits.push(b'0');
+        }
+        digits
+            .chunks(2)
+            .map(|pair| u8::from_str_radix(&String::from_utf8_lossy(pair), 16).unwrap_or(0))
+            .collect()
+    }
+
+    fn read_number(&mut self) -> Option<f64> {
+        let token = self.read_regular();
+        String::from_utf8_lossy(token).parse().ok()
+    }
+
+    /// Parses object at current position, streams are recognized after dictionaries.
+    pub fn parse_object(&mut self, depth: usize) -> Option<PdfObject> {
+        if !allow_depth(depth) {
+            return None;
+        }
+        self.skip_whitespace();
+        let b = *self.data.get(self.pos)?;
+        match b {
+            b'/' => Some(PdfObject::Name(self.read_name())),
+            b'(' => Some(PdfObject::String(self.read_literal_string())),
+            b'<' if self.starts_with(b"<<") => {
+                self.pos += 2;
+                let mut entries = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.starts_with(b">>") {
+                        self.pos += 2;
+                        break;
+                    }
+                    if self.data.get(self.pos) != Some(&b'/') {
+                        // Broken dictionary, skip a token to avoid looping forever.
+                        self.pos += 1;
+                        if self.pos >= self.data.len() {
+                            break;
+                        }
+                        continue;
+                    }
+                    let key = self.read_name();
+                    let value = self.parse_object(depth + 1).unwrap_or(PdfObject::Null);
+                    entries.push((key, value));
+                }
+                let after_dictionary = self.pos;
+                self.skip_whitespace();
+                if self.starts_with(b"stream") {
+                    self.pos += 6;
+                    if self.starts_with(b"\r\n") {
+                        self.pos += 2;
+                    } else if self.starts_with(b"\n") || self.starts_with(b"\r") {
+                        self.pos += 1;
+                    }
+                    let start = self.pos;
+                    let length = self.get_stream_length(&entries, start);
+                    self.pos = (start + length).min(self.data.len());
+                    return Some(PdfObject::Stream(entries, start, length));
+                }
+                self.pos = after_dictionary;
+                Some(PdfObject::Dictionary(entries))
+            }
+            b'<' => Some(PdfObject::String(self.read_hex_string())),
+            b'[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.data.get(self.pos) {
+                        None => break,
+                        Some(b']') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        _ => {
+                            let before = self.pos;
+                            match self.parse_object(depth + 1) {
+                                Some(item) => items.push(item),
+                                None if self.pos == before => self.pos += 1,
+                                None => (),
+                            }
+                        }
+                    }
+                }
+                Some(PdfObject::Array(items))
+            }
+            b'+' | b'-' | b'.' | b'0'..=b'9' => {
+                let number = self.read_number()?;
+                // Check for "num gen R" reference.
+                let saved = self.pos;
+                self.skip_whitespace();
+                if number.fract() == 0.0 && number >= 0.0 {
+                    let generation_start = self.pos;
+                    let generation = self.read_regular();
+                    if !generation.is_empty() && generation.iter().all(|b| b.is_ascii_digit()) {
+                        self.skip_whitespace();
+                        if self.data.get(self.pos) == Some(&b'R')
+                            && self
+                                .data
+                                .get(self.pos + 1)
+                                .is_none_or(|b| is_whitespace(*b) || is_delimiter(*b))
+                        {
+                            self.pos += 1;
+                            let generation =
+                                String::from_utf8_lossy(generation).parse().unwrap_or(0);
+                            return Some(PdfObject::Reference(number as u32, generation));
+                        }
+                    }
+                    self.pos = generation_start;
+                }
+                self.pos = saved;
+                Some(PdfObject::Number(number))
+            }
+            _ => {
+                let token = self.read_regular();
+                match token {
+                    b"true" => Some(PdfObject::Bool(true)),
+                    b"false" => Some(PdfObject::Bool(false)),
+                    b"null" => Some(PdfObject::Null),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Uses direct /Length if data after it ends with "endstream", otherwise searches for it.
+    fn get_stream_length(&self, entries: &[(String, PdfObject)], start: usize) -> usize {
+        let length = entries
+            .iter()
+            .find(|(k, _)| k == "Length")
+            .and_then(|(_, v)| v.as_number())
+            .map(|n| n as usize);
+        if let Some(length) = length {
+            let mut end = start.saturating_add(length);
+            while self.data.get(end).is_some_and(|b| is_whitespace(*b)) {
+                end += 1;
+            }
+            if self.data[end.min(self.data.len())..].starts_with(b"endstream") {
+                return length;
+            }
+        }
+        match find(&self.data[start.min(self.data.len())..], b"endstream") {
+            Some(found) => {
+                let mut length = found;
+                // EOL before endstream is not part of data.
+                while length > 0 && matches!(self.data[start + length - 1], b'\r' | b'\n') {
+                    length -= 1;
+                }
+                length
+            }
+            None => self.data.len() - start.min(self.data.len()),
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle)
+}
+
+/// Reverses PNG predictors (used by xref and object streams).
+fn remove_png_predictor(data: &[u8], columns: usize, bpp: usize) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::with_capacity(data.len());
+    let mut previous = vec![0u8; columns];
+    for row in data.chunks(columns + 1) {
+        let (filter, row) = (row[0], &row[1..]);
+        let mut current = vec![0u8; columns];
+        for i in 0..row.len() {
+            let left = if i >= bpp { current[i - bpp] } else { 0 };
+            let up = previous[i];
+            let up_left = if i >= bpp { previous[i - bpp] } else { 0 };
+            current[i] = match filter {
+                1 => row[i].wrapping_add(left),
+                2 => row[i].wrapping_add(up),
+                3 => row[i].wrapping_add(((left as u16 + up as u16) / 2) as u8),
+                4 => {
+                    let p = left as i16 + up as i16 - up_left as i16;
+                    let (pa, pb, pc) = (
+                        (p - left as i16).abs(),
+                        (p - up as i16).abs(),
+                        (p - up_left as i16).abs(),
+                    );
+                    let predictor = if pa <= pb && pa <= pc {
+                        left
+                    } else if pb <= pc {
+                        up
+                    } else {
+                        up_left
+                    };
+                    row[i].wrapping_add(predictor)
+                }
+                _ => row[i],
+            };
+        }
+        out.extend_from_slice(&current[..row.len()]);
+        previous = current;
+    }
+    out
+}
+
+pub struct Pdf<'a> {
+    pub data: &'a [u8],
+    pub objects: HashMap<u32, PdfObject>,
+    /// Offsets of "obj" definitions, used for linearization check.
+    pub offsets: HashMap<u32, usize>,
+    pub compressed_objects: usize,
+    pub escaped_names: usize,
+}
+
+impl<'a> Pdf<'a> {
+    /// Finds all "num gen obj" definitions in file and objects inside of object streams.
+    pub fn new(data: &'a [u8]) -> Self {
+        let mut pdf = Pdf {
+            data,
+            objects: HashMap::new(),
+            offsets: HashMap::new(),
+            compressed_objects: 0,
+            escaped_names: 0,
+        };
+        let mut search = 0;
+        while let Some(found) = find(&data[search..], b"obj") {
+            if is_timed_out() {
+                break;
+            }
+            let at = search + found;
+            search = at + 3;
+            if data
+                .get(at + 3)
+                .is_some_and(|b| !is_whitespace(*b) && !is_delimiter(*b))
+            {
+                continue;
+            }
+            let Some((number, start)) = get_object_number(data, at) else {
+                continue;
+            };
+            let mut lexer = Lexer::new(data, at + 3);
+            if let Some(object) = lexer.parse_object(0) {
+                pdf.escaped_names += lexer.escaped_names;
+                // Later definitions come from incremental updates and replace earlier ones.
+                pdf.objects.insert(number, object);
+                pdf.offsets.insert(number, start);
+                search = search.max(lexer.pos);
+            }
+        }
+
+        let streams: Vec<PdfObject> = pdf
+            .objects
+            .values()
+            .filter(|o| o.get("Type").and_then(|t| t.as_name()) == Some("ObjStm"))
+            .cloned()
+            .collect();
+        for stream in streams.iter() {
+            let Some(decoded) = pdf.decode_stream(stream) else {
+                continue;
+            };
+            let count = stream.get("N").and_then(|n| n.as_number()).unwrap_or(0.0) as usize;
+            let first = stream
+                .get("First")
+                .and_then(|n| n.as_number())
+                .unwrap_or(0.0) as usize;
+            let mut header = Lexer::new(&decoded, 0);
+            for _ in 0..count {
+                let (Some(PdfObject::Number(number)), Some(PdfObject::Number(offset))) =
+                    (header.parse_object(0), header.parse_object(0))
+                else {
+                    break;
+                };
+                let Some(start) = first.checked_add(offset as usize) else {
+                    continue;
+                };
+                let mut lexer = Lexer::new(&decoded, start);
+                if let Some(object) = lexer.parse_object(0) {
+                    pdf.escaped_names += lexer.escaped_names;
+                    pdf.compressed_objects += 1;
+                    pdf.objects.entry(number as u32).or_insert(object);
+                }
+            }
+        }
+        pdf
+    }
+
+    /// Follows references until direct object.
+    pub fn resolve<'b>(&'b self, object: &'b PdfObject) -> &'b PdfObject {
+        let mut current = object;
+        for _ in 0..16 {
+            match current {
+                PdfObject::Reference(number, _) => match self.objects.get(number) {
+                    Some(object) => current = object,
+                    None => return &PdfObject::Null,
+                },
+                _ => return current,
+            }
+        }
+        &PdfObject::Null
+    }
+
+    /// Gets resolved value of dictionary key.
+    pub fn get<'b>(&'b self, object: &'b PdfObject, key: &str) -> Option<&'b PdfObject> {
+        self.resolve(object).get(key).map(|v| self.resolve(v))
+    }
+
+    /// Decodes stream data with supported filters (FlateDecode, ASCIIHexDecode).
+    pub fn decode_stream(&self, stream: &PdfObject) -> Option<Vec<u8>> {
+        let PdfObject::Stream(_, start, length) = stream else {
+            return None;
+        };
+        let mut data = self.data.get(*start..start + length)?.to_vec();
+        let filters = match self.get(stream, "Filter") {
+            Some(PdfObject::Name(name)) => vec![name.clone()],
+            Some(PdfObject::Array(names)) => names
+                .iter()
+                .filter_map(|n| self.resolve(n).as_name().map(|n| n.to_string()))
+                .collect(),
+            _ => Vec::new(),
+        };
+        for filter in filters.iter() {
+            data = match filter.as_str() {
+                "FlateDecode" | "Fl" => {
+                    let mut out = Vec::new();
+                    let mut decoder =
+                        limit_decompressed(ZlibDecoder::new(&data[..])).take(MAX_DECODED_STREAM);
+                    // Broken streams are common, keep whatever was inflated.
+                    if decoder.read_to_end(&mut out).is_err() && out.is_empty() {
+                        return None;
+                    }
+                    out
+                }
+                "ASCIIHexDecode" | "AHx" => {
+                    let mut hex = vec![b'<'];
+                    hex.extend(&data);
+                    hex.push(b'>');
+                    Lexer::new(&hex, 0).read_hex_string()
+                }
+                _ => return None,
+            };
+        }
+        if let Some(parameters) = self.get(stream, "DecodeParms") {
+            let predictor = self
+                .get(parameters, "Predictor")
+                .and_then(|p| p.as_number());
+            if predictor.unwrap_or(1.0) >= 10.0 {
+                // Parameters out of range are broken or hostile, NaN fails comparisons too.
+                let parameter = |name: &str, default: f64, max: f64| {
+                    let value = self
+                        .get(parameters, name)
+                        .and_then(|p| p.as_number())
+                        .unwrap_or(default);
+                    (value >= 1.0 && value <= max).then_some(value as usize)
+                };
+                let colors = parameter("Colors", 1.0, 32.0)?;
+                let bits = parameter("BitsPerComponent", 8.0, 16.0)?;
+                // Row is never longer than whole stream.
+                let columns = parameter("Columns", 1.0, data.len().max(1) as f64)?;
+                let bpp = (colors * bits).div_ceil(8);
+                let row = columns.checked_mul(colors)?.checked_mul(bits)?.div_ceil(8);
+                data = remove_png_predictor(&data, row, bpp);
+            }
+        }
+        Some(data)
+    }
+}
+
+/// Reads "num gen" before "obj" keyword at `at`, returns (object number, offset of definition).
+fn get_object_number(data: &[u8], at: usize) -> Option<(u32, usize)> {
+    let mut pos = at;
+    let mut read_back = |digits: bool| -> Option<usize> {
+        let end = pos;
+        if digits {
+            while pos > 0 && data[pos - 1].is_ascii_digit() {
+                pos -= 1;
+            }
+        } else {
+            while pos > 0 && is_whitespace(data[pos - 1]) {
+                pos -= 1;
+            }
+        }
+        (pos < end).then_some(end)
+    };
+    read_back(false)?;
+    read_back(true)?;
+    read_back(false)?;
+    let number_end = read_back(true)?;
+    let number_start = pos;
+    if number_start > 0
+        && !is_whitespace(data[number_start - 1])
+        && !is_delimiter(data[number_start - 1])
+    {
+        return None;
+    }
+    let number = String::from_utf8_lossy(&data[number_start..number_end])
+        .parse()
+        .ok()?;
+    Some((number, number_start))
+}
+
+/// Parses indirect object definition starting at offset.
+fn get_object_at(data: &[u8], offset: usize) -> Option<PdfObject> {
+    let header = data.get(offset..(offset + 32).min(data.len()))?;
+    let at = find(header, b"obj")?;
+    let mut lexer = Lexer::new(data, offset + at + 3);
+    lexer.parse_object(0)
+}
+
+/// Follows startxref and /Prev chain. Returns sections and merged trailer (newest keys win).
+pub fn get_xref_chain(pdf: &Pdf) -> (Vec<XrefSection>, Vec<(String, PdfObject)>, Option<String>) {
+    let data = pdf.data;
+    let mut sections = Vec::new();
+    let mut trailer: Vec<(String, PdfObject)> = Vec::new();
+    let mut warning = None;
+    let mut visited = HashSet::new();
+
+    let mut next = rfind(data, b"startxref").and_then(|at| {
+        let mut lexer = Lexer::new(data, at + 9);
+        lexer
+            .parse_object(0)
+            .and_then(|o| o.as_number())
+            .map(|n| n as usize)
+    });
+    while let Some(offset) = next.take() {
+        if !visited.insert(offset) || sections.len() > 256 {
+            warning = Some("cross-reference chain loops".to_string());
+            break;
+        }
+        // Some writers put whitespace before the xref keyword.
+        let mut lexer = Lexer::new(data, offset);
+        lexer.skip_whitespace();
+        let dictionary;
+        if lexer.starts_with(b"xref") {
+            lexer.pos += 4;
+            let mut entries = 0;
+            loop {
+                let before = lexer.pos;
+                let (Some(PdfObject::Number(_)), Some(PdfObject::Number(count))) =
+                    (lexer.parse_object(0), lexer.parse_object(0))
+                else {
+                    lexer.pos = before;
+                    break;
+                };
+                lexer.skip_whitespace();
+                // Entries have fixed length, count of broken table can point past the end.
+                let Some(end) = (count as usize)
+                    .checked_mul(20)
+                    .and_then(|length| lexer.pos.checked_add(length))
+                    .filter(|end| *end <= data.len())
+                else {
+                    break;
+                };
+                entries += count as usize;
+                lexer.pos = end;
+            }
+            lexer.skip_whitespace();
+            if !lexer.starts_with(b"trailer") {
+                warning = Some(format!("no trailer after xref table at offset {}", offset));
+                break;
+            }
+            lexer.pos += 7;
+            dictionary = lexer.parse_object(0);
+            sections.push(XrefSection {
+                offset,
+                kind: "table",
+                entries,
+                compressed_entries: 0,
+            });
+        } else {
+            let stream = get_object_at(data, offset);
+            let is_xref = stream
+                .as_ref()
+                .and_then(|s| s.get("Type"))
+                .and_then(|t| t.as_name())
+                == Some("XRef");
+            if !is_xref {
+                warning = Some(format!(
+                    "startxref/Prev points to invalid offset {}",
+                    offset
+                ));
+                break;
+            }
+            let stream = stream.unwrap();
+            let widths: Vec<usize> = match stream.get("W") {
+                Some(PdfObject::Array(w)) => w
+                    .iter()
+                    .map(|n| n.as_number().unwrap_or(0.0) as usize)
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let mut entries = 0;
+            let mut compressed_entries = 0;
+            if let (Some(decoded), 3) = (pdf.decode_stream(&stream), widths.len()) {
+                let row = widths.iter().sum::<usize>().max(1);
+                for entry in decoded.chunks_exact(row) {
+                    let kind = if widths[0] == 0 {
+                        1
+                    } else {
+                        entry[..widths[0]]
+                            .iter()
+                            .fold(0, |acc, b| (acc << 8) | *b as usize)
+                    };
+                    entries += 1;
+                    if kind == 2 {
+                        compressed_entries += 1;
+                    }
+                }
+            }
+            sections.push(XrefSection {
+                offset,
+                kind: "stream",
+                entries,
+                compressed_entries,
+            });
+            dictionary = Some(stream);
+        }
+        let Some(dictionary) = dictionary else {
+            break;
+        };
+        if let Some(entries) = dictionary.dictionary() {
+            for (key, value) in entries {
+                if !trailer.iter().any(|(k, _)| k == key) {
+                    trailer.push((key.clone(), value.clone()));
+                }
+            }
+        }
+        next = dictionary
+            .get("Prev")
+            .and_then(|p| p.as_number())
+            .map(|n| n as usize);
+        // Hybrid files keep compressed objects in stream referenced from table trailer.
+        if let Some(stream) = dictionary.get("XRefStm").and_then(|p| p.as_number()) {
+            if visited.insert(stream as usize) {
+                sections.push(XrefSection {
+                    offset: stream as usize,
+                    kind: "stream (hybrid)",
+                    entries: 0,
+                    compressed_entries: 0,
+                });
+            }
+        }
+    }
+    if sections.is_empty() && warning.is_none() {
+        warning = Some("startxref not found".to_string());
+    }
+    (sections, trailer, warning)
+}
+
+/// Decodes PDF text string (UTF-16BE with BOM, UTF-8 with BOM or PDFDocEncoding).
+pub fn decode_text(bytes: &[u8]) -> String {
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return String::from_utf8_lossy(&bytes[3..]).to_string();
+    }
+    bytes.iter().map(|b| *b as char).collect()
+}
+
+/// Parses PDF date, offset is `None` if date has no time zone.
+pub fn parse_pdf_date(date: &str) -> Option<(PrimitiveDateTime, Option<UtcOffset>)> {
+    let raw = date.trim_start_matches("D:");
+    let digits: String = raw.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.len() < 4 {
+        return None;
+    }
+    // Everything after year is optional.
+    let part = |from: usize, default: u8| -> Option<u8> {
+        digits
+            .get(from..from + 2)
+            .map_or(Some(default), |p| p.parse().ok())
+    };
+    let date = Date::from_calendar_date(
+        digits[..4].parse().ok()?,
+        Month::try_from(part(4, 1)?).ok()?,
+        part(6, 1)?,
+    )
+    .ok()?;
+    let time = Time::from_hms(part(8, 0)?, part(10, 0)?, part(12, 0)?).ok()?;
+    let zone = &raw[digits.len()..];
+    let offset = if zone.starts_with('Z') {
+        Some(UtcOffset::UTC)
+    } else if zone.starts_with(['+', '-']) {
+        let zone_digits: String = zone.chars().filter(|c| c.is_ascii_digit()).collect();
+        let hours: i32 = zone_digits.get(..2).unwrap_or("0").parse().ok()?;
+        let minutes: i32 = zone_digits.get(2..4).unwrap_or("0").parse().ok()?;
+        let sign = if zone.starts_with('-') { -1 } else { 1 };
+        UtcOffset::from_whole_seconds(sign * (hours * 3600 + minutes * 60)).ok()
+    } else {
+        None
+    };
+    Some((PrimitiveDateTime::new(date, time), offset))
+}
+
+/// Formats PDF date like "D:20240102030405+01'00'" in chosen style and time zone,
+/// date which can't be parsed is printed as it is.
+pub fn format_pdf_date(args: &Arguments, date: &str) -> String {
+    match parse_pdf_date(date) {
+        Some((time, offset)) => format_parsed_datetime(args, time, offset),
+        None => date.to_string(),
+    }
+}
+
+/// Gets text of XMP property, either element content or attribute value.
+pub fn get_xmp_value(xmp: &str, property: &str) -> Option<String> {
+    let attribute = format!("{}=\"", property);
+    if let Some(at) = xmp.find(&attribute) {
+        let rest = &xmp[at + attribute.len()..];
+        return rest.find('"').map(|end| rest[..end].to_string());
+    }
+    let open = format!("<{}", property);
+    let at = xmp.find(&open)?;
+    let rest = &xmp[at + open.len()..];
+    let end = rest.find(&format!("</{}>", property))?;
+    let content = &rest[rest[..end].find('>')? + 1..end];
+    // Strip nested rdf:Alt/rdf:Seq/rdf:li tags.
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in content.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                if !text.is_empty() && !text.ends_with("; ") {
+                    text += "; ";
+                }
+            }
+            _ if !in_tag => text.push(c),
+            _ => (),
+        }
+    }
+    let values: Vec<&str> = text
+        .split("; ")
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .collect();
+    Some(values.join("; "))
+}
+
+/// Gets names of allowed operations from /P value of standard security handler.
+pub fn get_permissions(p: i64) -> Vec<&'static str> {
+    let bits = [
+        (3, "print"),
+        (4, "modify"),
+        (5, "copy"),
+        (6, "annotate"),
+        (9, "fill forms"),
+        (10, "extract for accessibility"),
+        (11, "assemble"),
+        (12, "high quality print"),
+    ];
+    bits.iter()
+        .filter(|(bit, _)| p & (1 << (bit - 1)) != 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+/// Counts risky actions and features by walking all dictionaries.
+pub fn get_suspicious_keys(pdf: &Pdf) -> BTreeMap<&'static str, usize> {
+    fn walk(object: &PdfObject, found: &mut BTreeMap<&'static str, usize>) {
+        match object {
+            PdfObject::Dictionary(entries) | PdfObject::Stream(entries, _, _) => {
+                for (key, value) in entries {
+                    let key_name = match key.as_str() {
+                        "JavaScript" | "JS" => Some("JavaScript"),
+                        "OpenAction" => Some("OpenAction"),
+                        "AA" => Some("Additional actions (AA)"),
+                        "XFA" => Some("XFA form"),
+                        "RichMedia" => Some("RichMedia"),
+                        _ => None,
+                    };
+                    if let Some(name) = key_name {
+                        *found.entry(name).or_insert(0) += 1;
+                    }
+                    if key == "S" {
+                        let action = match value.as_name() {
+                            Some("Launch") => Some("Launch"),
+                            Some("URI") => Some("URI"),
+                            Some("SubmitForm") => Some("SubmitForm"),
+                            Some("ImportData") => Some("ImportData"),
+                            Some("GoToR") => Some("GoToR (remote)"),
+                            Some("GoToE") => Some("GoToE (embedded)"),
+                            _ => None,
+                        };
+                        if let Some(action) = action {
+                            *found.entry(action).or_insert(0) += 1;
+                        }
+                    }
+                    walk(value, found);
+                }
+            }
+            PdfObject::Array(items) => items.iter().for_each(|i| walk(i, found)),
+            _ => (),
+        }
+    }
+    let mut found = BTreeMap::new();
+    for object in pdf.objects.values() {
+        walk(object, &mut found);
+    }
+    found
+}
+
+/// Collects (name, size) of files from /EmbeddedFiles name tree.
+fn get_embedded_files(
+    pdf: &Pdf,
+    node: &PdfObject,
+    files: &mut Vec<(String, Option<usize>)>,
+    depth: usize,
+) {
+    if !allow_depth(depth) {
+        return;
+    }
+    if let Some(PdfObject::Array(names)) = pdf.get(node, "Names") {
+        for pair in names.chunks(2) {
+            let name = match pdf.resolve(&pair[0]) {
+                PdfObject::String(name) => decode_text(name),
+                _ => "?".to_string(),
+            };
+            let size = pair.get(1).and_then(|spec| {
+                let stream = pdf.get(spec, "EF").and_then(|ef| pdf.get(ef, "F"))?;
+                let params = pdf.get(stream, "Params").and_then(|p| pdf.get(p, "Size"));
+                match (params.and_then(|s| s.as_number()), stream) {
+                    (Some(size), _) => Some(size as usize),
+                    (None, PdfObject::Stream(_, _, length)) => Some(*length),
+                    _ => None,
+                }
+            });
+            files.push((name, size));
+        }
+    }
+    if let Some(PdfObject::Array(kids)) = pdf.get(node, "Kids") {
+        for kid in kids.iter() {
+            get_embedded_files(pdf, kid, files, depth + 1);
+        }
+    }
+}
+
+/// Gets specified pdf info about file.
+pub fn get_pdf_info(args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## PDF information");
+    let data = input.bytes();
+    let header = &data[..data.len().min(1024)];
+    let Some(at) = find(header, b"%PDF-") else {
+        writeln!(
+            report,
+            "Error: {}",
+            Error::new(ErrorKind::InvalidData, "missing %PDF header")
+        );
+        return;
+    };
+    let version: String = header[at + 5..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit() || **b == b'.')
+        .map(|b| *b as char)
+        .collect();
+    writeln!(report, "# Version: {}", version);
+    if at > 0 {
+        writeln!(report, "# Header offset: {} (data before %PDF header)", at);
+    }
+
+    let pdf = Pdf::new(data);
+    writeln!(
+        report,
+        "# Objects: {} ({} in object streams)",
+        pdf.objects.len(),
+        pdf.compressed_objects
+    );
+
+    let (sections, mut trailer, warning) = get_xref_chain(&pdf);
+    for section in sections.iter() {
+        write!(
+            report,
+            "# Cross-reference {} at offset {}: {} entries",
+            section.kind, section.offset, section.entries
+        );
+        if section.compressed_entries > 0 {
+            write!(report, " ({} compressed)", section.compressed_entries);
+        }
+        writeln!(report);
+    }
+    if let Some(warning) = warning {
+        writeln!(report, "# Warning: {}", warning);
+    }
+    if trailer.is_empty() {
+        // Fall back to last trailer keyword in file.
+        if let Some(at) = rfind(data, b"trailer") {
+            let mut lexer = Lexer::new(data, at + 7);
+            if let Some(PdfObject::Dictionary(entries)) = lexer.parse_object(0) {
+                trailer = entries;
+            }
+        }
+    }
+    let trailer = PdfObject::Dictionary(trailer);
+
+    let eof_markers = data.windows(5).filter(|w| w == b"%%EOF").count();
+    let first_object = pdf
+        .offsets
+        .iter()
+        .min_by_key(|(_, offset)| **offset)
+        .map(|(n, _)| n);
+    let linearized = first_object
+        .and_then(|n| pdf.objects.get(n))
+        .and_then(|o| o.get("Linearized"))
+        .is_some();
+    writeln!(
+        report,
+        "# Linearized: {}",
+        if linearized { "yes" } else { "no" }
+    );
+    // Linearized files have two sections by design.
+    let base_sections = if linearized { 2 } else { 1 };
+    writeln!(
+        report,
+        "# Incremental updates: {} ({} %%EOF marker(s))",
+        sections.len().max(1).saturating_sub(base_sections),
+        eof_markers
+    );
+
+    let catalog = match pdf.get(&trailer, "Root") {
+        Some(root) => Some(root),
+        None => pdf
+            .objects
+            .values()
+            .find(|o| o.get("Type").and_then(|t| t.as_name()) == Some("Catalog")),
+    };
+    if let Some(catalog) = catalog {
+        if let Some(version) = pdf.get(catalog, "Version").and_then(|v| v.as_name()) {
+            writeln!(report, "# Catalog version: {}", version);
+        }
+        if let Some(count) = pdf
+            .get(catalog, "Pages")
+            .and_then(|p| pdf.get(p, "Count"))
+            .and_then(|c| c.as_number())
+        {
+            writeln!(report, "# Pages: {}", count);
+        }
+    } else {
+        writeln!(report, "# Warning: document catalog not found");
+    }
+
+    match pdf.get(&trailer, "Encrypt") {
+        Some(encrypt) => {
+            let number = |key: &str| pdf.get(encrypt, key).and_then(|v| v.as_number());
+            write!(
+                report,
+                "# Encryption: filter {}",
+                pdf.get(encrypt, "Filter")
+                    .and_then(|f| f.as_name())
+                    .unwrap_or("?")
+            );
+            if let Some(sub_filter) = pdf.get(encrypt, "SubFilter").and_then(|f| f.as_name()) {
+                write!(report, " ({})", sub_filter);
+            }
+            write!(
+                report,
+                ", V {}, R {}, key length {} bits",
+                number("V").unwrap_or(0.0),
+                number("R").unwrap_or(0.0),
+                number("Length").unwrap_or(40.0)
+            );
+            writeln!(report);
+            if let Some(p) = number("P") {
+                writeln!(
+                    report,
+                    "# Permissions: {} (P = {})",
+                    get_permissions(p as i64).join(", "),
+                    p
+                );
+            }
+            writeln!(
+                report,
+                "# Strings are encrypted, metadata below may be unreadable"
+            );
+        }
+        None => writeln!(report, "# Encryption: none"),
+    }
+
+    if let Some(info) = pdf.get(&trailer, "Info") {
+        if let Some(entries) = info.dictionary() {
+            writeln!(report, "# Document information:");
+            for (key, value) in entries {
+                let value = match pdf.resolve(value) {
+                    PdfObject::String(bytes) => decode_text(bytes),
+                    PdfObject::Name(name) => name.clone(),
+                    other => format!("{:?}", other),
+                };
+                if key == "CreationDate" || key == "ModDate" {
+                    writeln!(report, "{}: {}", key, format_pdf_date(args, &value));
+                } else {
+                    writeln!(report, "{}: {}", key, value);
+                }
+            }
+        }
+    }
+
+    let metadata = catalog.and_then(|c| pdf.get(c, "Metadata"));
+    if let Some(xmp) = metadata.and_then(|m| pdf.decode_stream(m)) {
+        let xmp = String::from_utf8_lossy(&xmp);
+        writeln!(
+            report,
+            "# XMP metadata: {}",
+            format_size(args, xmp.len() as u64)
+        );
+        for (property, name) in [
+            ("dc:title", "Title"),
+            ("dc:creator", "Creator"),
+            ("pdf:Producer", "Producer"),
+            ("xmp:CreatorTool", "Creator tool"),
+            ("xmp:CreateDate", "Creation date"),
+            ("xmp:ModifyDate", "Modification date"),
+            ("xmpMM:DocumentID", "Document ID"),
+        ] {
+            if let Some(value) = get_xmp_value(&xmp, property) {
+                let value = match parse_iso8601(&value) {
+                    Some((time, offset)) => format_parsed_datetime(args, time, offset),
+                    None => value,
+                };
+                writeln!(report, "{}: {}", name, value);
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    if let Some(tree) = catalog
+        .and_then(|c| pdf.get(c, "Names"))
+        .and_then(|n| pdf.get(n, "EmbeddedFiles"))
+    {
+        get_embedded_files(&pdf, tree, &mut files, 0);
+    }
+    let embedded_streams = pdf
+        .objects
+        .values()
+        .filter(|o| o.get("Type").and_then(|t| t.as_name()) == Some("EmbeddedFile"))
+        .count();
+    writeln!(
+        report,
+        "# Embedded files: {} in name tree, {} embedded file stream(s)",
+        files.len(),
+        embedded_streams
+    );
+    for (name, size) in files.iter() {
+        match size {
+            Some(size) => writeln!(report, "\"{}\" ({})", name, format_size(args, *size as u64)),
+            None => writeln!(report, "\"{}\"", name),
+        }
+    }
+
+    let suspicious = get_suspicious_keys(&pdf);
+    if suspicious.is_empty() {
+        writeln!(report, "# Active content: none found");
+    } else {
+        write!(report, "# WARNING: active content found: ");
+        for (name, count) in suspicious.iter() {
+            write!(report, "{} ({}); ", name, count);
+        }
+        writeln!(report);
+    }
+    if pdf.escaped_names > 0 {
+        writeln!(
+            report,
+            "# WARNING: {} name(s) use #xx escapes (possible obfuscation)",
+            pdf.escaped_names
+        );
+    }
+}
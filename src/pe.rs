@@ -0,0 +1,982 @@
+use crate::datetime::{format_timestamp, Timestamp};
+use crate::entropy::get_entropy;
+use crate::input::Input;
+use crate::report::Report;
+use crate::{format_size, Arguments};
+use std::io::{Error, ErrorKind};
+
+const EXPORT_DIRECTORY: usize = 0;
+const IMPORT_DIRECTORY: usize = 1;
+const RESOURCE_DIRECTORY: usize = 2;
+const SECURITY_DIRECTORY: usize = 4;
+const DELAY_IMPORT_DIRECTORY: usize = 13;
+const CLR_DIRECTORY: usize = 14;
+
+const RT_VERSION: u32 = 16;
+const RT_MANIFEST: u32 = 24;
+
+pub struct PeSection {
+    pub name: String,
+    pub virtual_address: u32,
+    pub virtual_size: u32,
+    pub raw_offset: u32,
+    pub raw_size: u32,
+    pub characteristics: u32,
+    pub entropy: f64,
+}
+
+pub struct Import {
+    pub library: String,
+    /// Function names, ordinal imports as "#123".
+    pub functions: Vec<String>,
+    pub delayed: bool,
+}
+
+pub struct Signer {
+    pub subject: String,
+    pub issuer: String,
+    pub not_before: String,
+    pub not_after: String,
+}
+
+pub struct ClrHeader {
+    pub runtime: (u16, u16),
+    pub flags: u32,
+    pub metadata_version: Option<String>,
+}
+
+#[derive(Default)]
+pub struct PeInfo {
+    pub dos_stub_size: usize,
+    pub dos_message: Option<String>,
+    pub has_rich_header: bool,
+    pub machine: u16,
+    pub timestamp: u32,
+    pub characteristics: u16,
+    pub is_pe32_plus: bool,
+    pub linker_version: (u8, u8),
+    pub entry_point: u32,
+    pub image_base: u64,
+    pub os_version: (u16, u16),
+    pub subsystem: u16,
+    pub dll_characteristics: u16,
+    /// (RVA or file offset for security directory, size)
+    pub directories: Vec<(u32, u32)>,
+    pub sections: Vec<PeSection>,
+    pub imports: Vec<Import>,
+    pub export_name: Option<String>,
+    pub exports: Vec<String>,
+    /// (resource type, number of resources)
+    pub resources: Vec<(u32, usize)>,
+    pub fixed_version: Option<String>,
+    pub version_strings: Vec<(String, String)>,
+    pub execution_level: Option<String>,
+    pub certificate_type: Option<u16>,
+    pub signer: Option<Signer>,
+    pub clr: Option<ClrHeader>,
+    /// Start and size of data after last section, certificate table excluded.
+    pub overlay: Option<(usize, usize)>,
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset.checked_add(2)?)?.try_into().ok()?,
+    ))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset.checked_add(4)?)?.try_into().ok()?,
+    ))
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        data.get(offset..offset.checked_add(8)?)?.try_into().ok()?,
+    ))
+}
+
+/// Reads null terminated ASCII string.
+fn string_at(data: &[u8], offset: usize) -> Option<String> {
+    let rest = data.get(offset..)?;
+    let end = rest.iter().position(|b| *b == 0).unwrap_or(rest.len());
+    Some(String::from_utf8_lossy(&rest[..end.min(512)]).to_string())
+}
+
+/// Reads null terminated UTF-16LE string, returns it with its size in bytes (terminator included).
+fn utf16_at(data: &[u8], offset: usize) -> (String, usize) {
+    let units: Vec<u16> = data
+        .get(offset..)
+        .unwrap_or_default()
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|u| *u != 0)
+        .collect();
+    (String::from_utf16_lossy(&units), units.len() * 2 + 2)
+}
+
+pub fn machine_name(machine: u16) -> &'static str {
+    match machine {
+        0x0 => "unknown",
+        0x14C => "Intel 386",
+        0x166 => "MIPS",
+        0x1C0 => "ARM",
+        0x1C4 => "ARM Thumb-2",
+        0x200 => "IA-64",
+        0x5032 => "RISC-V 32-bit",
+        0x5064 => "RISC-V 64-bit",
+        0x8664 => "AMD64",
+        0xAA64 => "ARM64",
+        0xA641 => "ARM64EC",
+        0xEBC => "EFI byte code",
+        _ => "other",
+    }
+}
+
+pub fn subsystem_name(subsystem: u16) -> &'static str {
+    match subsystem {
+        1 => "native",
+        2 => "Windows GUI",
+        3 => "Windows console",
+        5 => "OS/2 console",
+        7 => "POSIX console",
+        9 => "Windows CE GUI",
+        10 => "EFI application",
+        11 => "EFI boot service driver",
+        12 => "EFI runtime driver",
+        13 => "EFI ROM",
+        14 => "Xbox",
+        16 => "Windows boot application",
+        _ => "unknown",
+    }
+}
+
+fn resource_type_name(kind: u32) -> String {
+    match kind {
+        1 => "cursor",
+        2 => "bitmap",
+        3 => "icon",
+        4 => "menu",
+        5 => "dialog",
+        6 => "string table",
+        7 => "font directory",
+        8 => "font",
+        9 => "accelerators",
+        10 => "raw data",
+        11 => "message table",
+        12 => "group cursor",
+        14 => "group icon",
+        RT_VERSION => "version",
+        23 => "HTML",
+        RT_MANIFEST => "manifest",
+        _ => return format!("type {}", kind),
+    }
+    .to_string()
+}
+
+/// Converts relative virtual address to file offset using section table.
+fn rva_to_offset(sections: &[PeSection], headers_size: u32, rva: u32) -> Option<usize> {
+    if rva < headers_size {
+        return Some(rva as usize);
+    }
+    sections
+        .iter()
+        .find(|s| {
+            rva >= s.virtual_address && rva - s.virtual_address < s.raw_size.max(s.virtual_size)
+        })
+        .filter(|s| rva - s.virtual_address < s.raw_size)
+        .map(|s| s.raw_offset as usize + (rva - s.virtual_address) as usize)
+}
+
+fn get_imports(data: &[u8], info: &PeInfo, headers_size: u32, delayed: bool) -> Vec<Import> {
+    let offset = |rva: u32| rva_to_offset(&info.sections, headers_size, rva);
+    let directory = if delayed {
+        DELAY_IMPORT_DIRECTORY
+    } else {
+        IMPORT_DIRECTORY
+    };
+    let Some(&(rva, _)) = info.directories.get(directory).filter(|d| d.0 != 0) else {
+        return Vec::new();
+    };
+    let Some(mut descriptor) = offset(rva) else {
+        return Vec::new();
+    };
+    let (descriptor_size, name_at, thunks_at) = if delayed { (32, 4, 16) } else { (20, 12, 0) };
+    let thunk_size = if info.is_pe32_plus { 8 } else { 4 };
+    let mut imports = Vec::new();
+    while imports.len() < 4096 {
+        let (Some(name), Some(mut thunks)) = (
+            u32_at(data, descriptor + name_at),
+            u32_at(data, descriptor + thunks_at),
+        ) else {
+            break;
+        };
+        if name == 0 {
+            break;
+        }
+        // Import lookup table may be missing in old binaries, use address table instead.
+        if thunks == 0 && !delayed {
+            thunks = u32_at(data, descriptor + 16).unwrap_or(0);
+        }
+        let library = offset(name)
+            .and_then(|o| string_at(data, o))
+            .unwrap_or_default();
+        let mut functions = Vec::new();
+        let mut thunk = Some(thunks).filter(|t| *t != 0).and_then(offset);
+        while let Some(at) = thunk {
+            let value = if info.is_pe32_plus {
+                u64_at(data, at)
+            } else {
+                u32_at(data, at).map(|v| v as u64)
+            };
+            let Some(value) = value.filter(|v| *v != 0) else {
+                break;
+            };
+            let ordinal_flag = 1u64 << (thunk_size * 8 - 1);
+            if value & ordinal_flag != 0 {
+                functions.push(format!("#{}", value & 0xFFFF));
+            } else if let Some(name) = offset(value as u32).and_then(|o| string_at(data, o + 2)) {
+                functions.push(name);
+            }
+            thunk = Some(at + thunk_size).filter(|_| functions.len() < 65536);
+        }
+        imports.push(Import {
+            library,
+            functions,
+            delayed,
+        });
+        descriptor += descriptor_size;
+    }
+    imports
+}
+
+fn get_exports(data: &[u8], info: &mut PeInfo, headers_size: u32) {
+    let Some(&(rva, _)) = info.directories.get(EXPORT_DIRECTORY).filter(|d| d.0 != 0) else {
+        return;
+    };
+    let sections = &info.sections;
+    let offset = |rva: u32| rva_to_offset(sections, headers_size, rva);
+    let Some(directory) = offset(rva) else {
+        return;
+    };
+    info.export_name = u32_at(data, directory + 12)
+        .and_then(offset)
+        .and_then(|o| string_at(data, o));
+    let count = u32_at(data, directory + 24).unwrap_or(0);
+    let Some(names) = u32_at(data, directory + 32).and_then(offset) else {
+        return;
+    };
+    for i in 0..count.min(65536) as usize {
+        match u32_at(data, names + i * 4)
+            .and_then(offset)
+            .and_then(|o| string_at(data, o))
+        {
+            Some(name) => info.exports.push(name),
+            None => break,
+        }
+    }
+}
+
+/// Walks resource directory tree, returns (type, offset of data, size) of every resource.
+fn get_resources(data: &[u8], info: &PeInfo, headers_size: u32) -> Vec<(u32, usize, usize)> {
+    let Some(&(rva, _)) = info
+        .directories
+        .get(RESOURCE_DIRECTORY)
+        .filter(|d| d.0 != 0)
+    else {
+        return Vec::new();
+    };
+    let Some(root) = rva_to_offset(&info.sections, headers_size, rva) else {
+        return Vec::new();
+    };
+    // Returns (id, offset relative to root, is directory) of entries of directory.
+    let entries = |directory: usize| -> Vec<(u32, usize, bool)> {
+        let named = u16_at(data, root + directory + 12).unwrap_or(0) as usize;
+        let ids = u16_at(data, root + directory + 14).unwrap_or(0) as usize;
+        (0..(named + ids).min(4096))
+            .map_while(|i| {
+                let at = root + directory + 16 + i * 8;
+                let target = u32_at(data, at + 4)?;
+                Some((
+                    u32_at(data, at)?,
+                    (target & 0x7FFF_FFFF) as usize,
+                    target & 0x8000_0000 != 0,
+                ))
+            })
+            .collect()
+    };
+    let mut resources = Vec::new();
+    // Tree is type / name / language.
+    for (kind, names, is_directory) in entries(0) {
+        if !is_directory {
+            continue;
+        }
+        for (_, languages, is_directory) in entries(names) {
+            let leaves = if is_directory {
+                entries(languages)
+            } else {
+                vec![(0, languages, false)]
+            };
+            for (_, leaf, is_directory) in leaves {
+                if is_directory {
+                    continue;
+                }
+                let (Some(data_rva), Some(size)) =
+                    (u32_at(data, root + leaf), u32_at(data, root + leaf + 4))
+                else {
+                    continue;
+                };
+                if let Some(offset) = rva_to_offset(&info.sections, headers_size, data_rva) {
+                    resources.push((kind, offset, size as usize));
+                }
+            }
+        }
+    }
+    resources
+}
+
+/// Reads VERSIONINFO block header, returns (key, value range, children start, end).
+fn get_version_block(data: &[u8], pos: usize) -> Option<(String, usize, usize, usize, usize)> {
+    let length = u16_at(data, pos)? as usize;
+    let value_length = u16_at(data, pos + 2)? as usize;
+    let is_text = u16_at(data, pos + 4)? == 1;
+    if length < 6 {
+        return None;
+    }
+    let (key, key_size) = utf16_at(data, pos + 6);
+    let value_start = (pos + 6 + key_size).next_multiple_of(4);
+    let value_size = if is_text {
+        value_length * 2
+    } else {
+        value_length
+    };
+    let children = (value_start + value_size).next_multiple_of(4);
+    Some((key, value_start, value_size, children, pos + length))
+}
+
+/// Gets fixed file version and strings (CompanyName, ProductVersion...) from version resource.
+fn get_version_info(data: &[u8], info: &mut PeInfo) {
+    let Some((key, value, value_size, children, end)) = get_version_block(data, 0) else {
+        return;
+    };
+    if key != "VS_VERSION_INFO" {
+        return;
+    }
+    // VS_FIXEDFILEINFO signature
+    if value_size >= 52 && u32_at(data, value) == Some(0xFEEF04BD) {
+        let high = u32_at(data, value + 8).unwrap_or(0);
+        let low = u32_at(data, value + 12).unwrap_or(0);
+        info.fixed_version = Some(format!(
+            "{}.{}.{}.{}",
+            high >> 16,
+            high & 0xFFFF,
+            low >> 16,
+            low & 0xFFFF
+        ));
+    }
+    let end = end.min(data.len());
+    let mut pos = children;
+    while let Some((key, _, _, tables, file_info_end)) = get_version_block(data, pos) {
+        if key == "StringFileInfo" {
+            let mut table = tables;
+            while let Some((_, _, _, strings, table_end)) = get_version_block(data, table) {
+                let mut string = strings;
+                while let Some((name, value, _, _, string_end)) = get_version_block(data, string) {
+                    let (value, _) = utf16_at(&data[..string_end.min(data.len())], value);
+                    info.version_strings.push((name, value));
+                    string = string_end.next_multiple_of(4);
+                    if string >= table_end {
+                        break;
+                    }
+                }
+                table = table_end.next_multiple_of(4);
+                if table >= file_info_end {
+                    break;
+                }
+            }
+        }
+        pos = file_info_end.next_multiple_of(4);
+        if pos >= end {
+            break;
+        }
+    }
+}
+
+/// Reads DER elements in data, returns (tag, content) of each.
+fn der_children(data: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut children = Vec::new();
+    let mut pos = 0;
+    while pos + 2 <= data.len() {
+        let tag = data[pos];
+        let first = data[pos + 1] as usize;
+        let (length, header) = if first < 0x80 {
+            (first, 2)
+        } else {
+            let count = first & 0x7F;
+            if count == 0 || count > 4 || pos + 2 + count > data.len() {
+                break;
+            }
+            let length = data[pos + 2..pos + 2 + count]
+                .iter()
+                .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+            (length, 2 + count)
+        };
+        let start = pos + header;
+        let Some(content) = start
+            .checked_add(length)
+            .and_then(|end| data.get(start..end))
+        else {
+            break;
+        };
+        children.push((tag, content));
+        pos = start + length;
+    }
+    children
+}
+
+/// Formats X.500 name as "CN=..., O=..., C=...".
+fn format_der_name(name: &[u8]) -> String {
+    let mut parts = Vec::new();
+    for (_, set) in der_children(name) {
+        for (_, attribute) in der_children(set) {
+            let attribute = der_children(attribute);
+            let [(_, oid), (kind, value)] = attribute[..] else {
+                continue;
+            };
+            let label = match oid {
+                [0x55, 4, 3] => "CN",
+                [0x55, 4, 6] => "C",
+                [0x55, 4, 7] => "L",
+                [0x55, 4, 8] => "ST",
+                [0x55, 4, 10] => "O",
+                [0x55, 4, 11] => "OU",
+                _ => continue,
+            };
+            // BMPString is UTF-16BE, other string types are (close enough to) UTF-8.
+            let value = if kind == 0x1E {
+                let units: Vec<u16> = value
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            } else {
+                String::from_utf8_lossy(value).to_string()
+            };
+            parts.push(format!("{}={}", label, value));
+        }
+    }
+    parts.join(", ")
+}
+
+/// Formats UTCTime or GeneralizedTime as "YYYY-MM-DD".
+pub fn format_der_time(kind: u8, time: &[u8]) -> String {
+    let time = String::from_utf8_lossy(time);
+    // Replaced invalid bytes are longer, so only ASCII can be sliced by position.
+    if !time.is_ascii() {
+        return time.to_string();
+    }
+    let (year, rest) = match kind {
+        0x17 if time.len() >= 6 => {
+            let year: u32 = time[..2].parse().unwrap_or(0);
+            (
+                if year < 50 { 2000 + year } else { 1900 + year },
+                &time[2..],
+            )
+        }
+        0x18 if time.len() >= 8 => (time[..4].parse().unwrap_or(0), &time[4..]),
+        _ => return time.to_string(),
+    };
+    format!("{:0>4}-{}-{}", year, &rest[..2], &rest[2..4])
+}
+
+/// Gets signing certificate from PKCS#7 SignedData of Authenticode signature.
+pub fn get_signer(pkcs7: &[u8]) -> Option<Signer> {
+    let content_info = der_children(pkcs7);
+    let content_info = der_children(content_info.first()?.1);
+    let explicit = content_info.iter().find(|(tag, _)| *tag == 0xA0)?.1;
+    let signed_data = der_children(explicit);
+    let signed_data = der_children(signed_data.first()?.1);
+    let certificates = der_children(signed_data.iter().find(|(tag, _)| *tag == 0xA0)?.1);
+    let signer_serial = signed_data
+        .iter()
+        .rfind(|(tag, _)| *tag == 0x31)
+        .and_then(|(_, infos)| {
+            let infos = der_children(infos);
+            let info = der_children(infos.first()?.1);
+            let issuer_and_serial = der_children(info.get(1)?.1);
+            Some(issuer_and_serial.get(1)?.1)
+        });
+
+    let mut signer = None;
+    for (_, certificate) in certificates.iter() {
+        let certificate = der_children(certificate);
+        let Some((_, tbs)) = certificate.first() else {
+            continue;
+        };
+        let mut fields = der_children(tbs);
+        // Version is optional explicit [0] field.
+        if fields.first().is_some_and(|(tag, _)| *tag == 0xA0) {
+            fields.remove(0);
+        }
+        let [(_, serial), _, (_, issuer), (_, validity), (_, subject), ..] = fields[..] else {
+            continue;
+        };
+        let validity = der_children(validity);
+        let time = |i: usize| {
+            validity
+                .get(i)
+                .map(|(kind, time)| format_der_time(*kind, time))
+                .unwrap_or_default()
+        };
+        let parsed = Signer {
+            subject: format_der_name(subject),
+            issuer: format_der_name(issuer),
+            not_before: time(0),
+            not_after: time(1),
+        };
+        if Some(serial) == signer_serial {
+            return Some(parsed);
+        }
+        signer.get_or_insert(parsed);
+    }
+    signer
+}
+
+fn get_clr_header(data: &[u8], info: &PeInfo, headers_size: u32) -> Option<ClrHeader> {
+    let &(rva, _) = info.directories.get(CLR_DIRECTORY).filter(|d| d.0 != 0)?;
+    let header = rva_to_offset(&info.sections, headers_size, rva)?;
+    let metadata_version = u32_at(data, header + 8)
+        .and_then(|rva| rva_to_offset(&info.sections, headers_size, rva))
+        .filter(|o| u32_at(data, *o) == Some(0x424A5342))
+        .and_then(|o| {
+            let length = u32_at(data, o + 12)? as usize;
+            let version = data.get(o + 16..o + 16 + length.min(256))?;
+            let end = version
+                .iter()
+                .position(|b| *b == 0)
+                .unwrap_or(version.len());
+            Some(String::from_utf8_lossy(&version[..end]).to_string())
+        });
+    Some(ClrHeader {
+        runtime: (u16_at(data, header + 4)?, u16_at(data, header + 6)?),
+        flags: u32_at(data, header + 16)?,
+        metadata_version,
+    })
+}
+
+/// Gets PE headers, sections, imports, exports, resources and signature info.
+pub fn get_pe_structure(data: &[u8]) -> Result<PeInfo, Error> {
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
+    if !data.starts_with(b"MZ") {
+        return Err(invalid("missing MZ signature"));
+    }
+    let pe_offset = u32_at(data, 0x3C).ok_or_else(|| invalid("truncated DOS header"))? as usize;
+    if data.get(pe_offset..pe_offset.saturating_add(4)) != Some(b"PE\0\0") {
+        return Err(invalid("missing PE signature (plain DOS executable?)"));
+    }
+    let mut info = PeInfo {
+        dos_stub_size: pe_offset.saturating_sub(0x40),
+        ..Default::default()
+    };
+    let stub = data.get(0x40..pe_offset).unwrap_or_default();
+    if let Some(at) = stub.windows(4).position(|w| w == b"This") {
+        let end = stub[at..]
+            .iter()
+            .position(|b| matches!(b, b'$' | b'\r' | b'\0'))
+            .unwrap_or(stub.len() - at);
+        info.dos_message = Some(String::from_utf8_lossy(&stub[at..at + end]).to_string());
+    }
+    // Undocumented Microsoft linker header with build tools info.
+    info.has_rich_header = stub.windows(4).any(|w| w == b"Rich");
+
+    let coff = pe_offset + 4;
+    let truncated = || invalid("truncated PE header");
+    info.machine = u16_at(data, coff).ok_or_else(truncated)?;
+    let section_count = u16_at(data, coff + 2).ok_or_else(truncated)?;
+    info.timestamp = u32_at(data, coff + 4).ok_or_else(truncated)?;
+    let optional_size = u16_at(data, coff + 16).ok_or_else(truncated)? as usize;
+    info.characteristics = u16_at(data, coff + 18).ok_or_else(truncated)?;
+
+    let optional = coff + 20;
+    let mut headers_size = 0;
+    if optional_size > 0 {
+        info.is_pe32_plus = match u16_at(data, optional) {
+            Some(0x10B) => false,
+            Some(0x20B) => true,
+            _ => return Err(invalid("unknown optional header magic")),
+        };
+        info.linker_version = (
+            *data.get(optional + 2).ok_or_else(truncated)?,
+            *data.get(optional + 3).ok_or_else(truncated)?,
+        );
+        info.entry_point = u32_at(data, optional + 16).ok_or_else(truncated)?;
+        info.image_base = if info.is_pe32_plus {
+            u64_at(data, optional + 24)
+        } else {
+            u32_at(data, optional + 28).map(|b| b as u64)
+        }
+        .ok_or_else(truncated)?;
+        info.os_version = (
+            u16_at(data, optional + 40).ok_or_else(truncated)?,
+            u16_at(data, optional + 42).ok_or_else(truncated)?,
+        );
+        headers_size = u32_at(data, optional + 60).ok_or_else(truncated)?;
+        info.subsystem = u16_at(data, optional + 68).ok_or_else(truncated)?;
+        info.dll_characteristics = u16_at(data, optional + 70).ok_or_else(truncated)?;
+        let directories_at = optional + if info.is_pe32_plus { 112 } else { 96 };
+        let count = u32_at(data, directories_at - 4).unwrap_or(0).min(16) as usize;
+        info.directories = (0..count)
+            .map_while(|i| {
+                let at = directories_at + i * 8;
+                Some((u32_at(data, at)?, u32_at(data, at + 4)?))
+            })
+            .collect();
+    }
+
+    let section_table = optional + optional_size;
+    for i in 0..section_count as usize {
+        let at = section_table + i * 40;
+        let Some(name) = data.get(at..at + 8) else {
+            break;
+        };
+        let end = name.iter().position(|b| *b == 0).unwrap_or(8);
+        let mut section = PeSection {
+            name: String::from_utf8_lossy(&name[..end]).to_string(),
+            virtual_size: u32_at(data, at + 8).unwrap_or(0),
+            virtual_address: u32_at(data, at + 12).unwrap_or(0),
+            raw_size: u32_at(data, at + 16).unwrap_or(0),
+            raw_offset: u32_at(data, at + 20).unwrap_or(0),
+            characteristics: u32_at(data, at + 36).unwrap_or(0),
+            entropy: 0.0,
+        };
+        let start = (section.raw_offset as usize).min(data.len());
+        let end = start
+            .saturating_add(section.raw_size as usize)
+            .min(data.len());
+        section.entropy = get_entropy(&data[start..end]);
+        info.sections.push(section);
+    }
+
+    info.imports = get_imports(data, &info, headers_size, false);
+    let delayed = get_imports(data, &info, headers_size, true);
+    info.imports.extend(delayed);
+    get_exports(data, &mut info, headers_size);
+
+    for (kind, offset, size) in get_resources(data, &info, headers_size) {
+        match info.resources.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, count)) => *count += 1,
+            None => info.resources.push((kind, 1)),
+        }
+        let Some(resource) = data.get(offset..offset.saturating_add(size)) else {
+            continue;
+        };
+        if kind == RT_VERSION && info.fixed_version.is_none() {
+            get_version_info(resource, &mut info);
+        } else if kind == RT_MANIFEST {
+            let manifest = String::from_utf8_lossy(resource);
+            if let Some(at) = manifest.find("requestedExecutionLevel") {
+                info.execution_level = manifest[at..]
+                    .split("level=")
+                    .nth(1)
+                    .and_then(|l| l.get(1..))
+                    .and_then(|l| l.split(['"', '\'']).next())
+                    .map(|l| l.to_string());
+            }
+        }
+    }
+
+    // Security directory holds file offset, not RVA.
+    let mut certificate_range = None;
+    if let Some(&(offset, size)) = info
+        .directories
+        .get(SECURITY_DIRECTORY)
+        .filter(|d| d.0 != 0)
+    {
+        let (offset, size) = (offset as usize, size as usize);
+        certificate_range = Some((offset, offset.saturating_add(size)));
+        info.certificate_type = u16_at(data, offset + 6);
+        let length = u32_at(data, offset).unwrap_or(0) as usize;
+        if info.certificate_type == Some(2) && length > 8 {
+            info.signer = data
+                .get(offset + 8..offset.saturating_add(length))
+                .and_then(get_signer);
+        }
+    }
+    info.clr = get_clr_header(data, &info, headers_size);
+
+    let sections_end = info
+        .sections
+        .iter()
+        .filter(|s| s.raw_size > 0)
+        .map(|s| s.raw_offset as usize + s.raw_size as usize)
+        .max()
+        .unwrap_or(headers_size as usize)
+        .max(headers_size as usize);
+    let mut overlay_end = data.len();
+    if let Some((start, end)) = certificate_range {
+        if start >= sections_end && end >= data.len() {
+            overlay_end = start;
+        }
+    }
+    if overlay_end > sections_end {
+        info.overlay = Some((sections_end, overlay_end - sections_end));
+    }
+    Ok(info)
+}
+
+/// Gets names of set DLL characteristics flags.
+fn get_dll_characteristics(flags: u16) -> Vec<&'static str> {
+    [
+        (0x20, "high entropy ASLR"),
+        (0x40, "ASLR"),
+        (0x80, "force integrity"),
+        (0x100, "DEP"),
+        (0x200, "no isolation"),
+        (0x400, "no SEH"),
+        (0x800, "no bind"),
+        (0x1000, "AppContainer"),
+        (0x2000, "WDM driver"),
+        (0x4000, "CFG"),
+        (0x8000, "terminal server aware"),
+    ]
+    .iter()
+    .filter(|(flag, _)| flags & flag != 0)
+    .map(|(_, name)| *name)
+    .collect()
+}
+
+/// Formats section flags like "R-X".
+fn section_flags(characteristics: u32) -> String {
+    format!(
+        "{}{}{}",
+        if characteristics & 0x4000_0000 != 0 {
+            'R'
+        } else {
+            '-'
+        },
+        if characteristics & 0x8000_0000 != 0 {
+            'W'
+        } else {
+            '-'
+        },
+        if characteristics & 0x2000_0000 != 0 {
+            'X'
+        } else {
+            '-'
+        }
+    )
+}
+
+/// Gets specified PE info about file.
+pub fn get_pe_info(args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## PE information");
+    let data = input.bytes();
+    let info = match get_pe_structure(data) {
+        Ok(info) => info,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
+
+    write!(
+        report,
+        "# DOS stub: {}",
+        format_size(args, info.dos_stub_size as u64)
+    );
+    if let Some(message) = &info.dos_message {
+        write!(report, ", \"{}\"", message);
+    }
+    if info.has_rich_header {
+        write!(report, ", Rich header");
+    }
+    writeln!(report);
+    writeln!(
+        report,
+        "# Machine: {} (0x{:x})",
+        machine_name(info.machine),
+        info.machine
+    );
+    let timestamp = Timestamp::from_seconds(info.timestamp as i64);
+    write!(report, "# Timestamp: {}", format_timestamp(args, timestamp));
+    if timestamp.seconds > Timestamp::now().seconds {
+        write!(report, " (in future, likely a reproducible build hash)");
+    }
+    writeln!(report);
+    let mut kind = vec![if info.is_pe32_plus { "PE32+" } else { "PE32" }];
+    if info.characteristics & 0x2000 != 0 {
+        kind.push("DLL");
+    } else if info.characteristics & 0x2 != 0 {
+        kind.push("executable image");
+    }
+    if info.characteristics & 0x20 != 0 {
+        kind.push("large address aware");
+    }
+    if info.clr.is_some() {
+        kind.push(".NET assembly");
+    }
+    writeln!(report, "# Type: {}", kind.join(", "));
+    writeln!(
+        report,
+        "# Subsystem: {} ({}), OS version {}.{}",
+        subsystem_name(info.subsystem),
+        info.subsystem,
+        info.os_version.0,
+        info.os_version.1
+    );
+    writeln!(
+        report,
+        "# Linker version: {}.{}",
+        info.linker_version.0, info.linker_version.1
+    );
+    writeln!(
+        report,
+        "# Entry point: 0x{:x}, image base 0x{:x}",
+        info.entry_point, info.image_base
+    );
+    let flags = get_dll_characteristics(info.dll_characteristics);
+    writeln!(
+        report,
+        "# DLL characteristics: {}",
+        if flags.is_empty() {
+            "none".to_string()
+        } else {
+            flags.join(", ")
+        }
+    );
+    let has = |flag: u16| {
+        if info.dll_characteristics & flag != 0 {
+            "yes"
+        } else {
+            "no"
+        }
+    };
+    writeln!(
+        report,
+        "# ASLR: {}, DEP: {}, CFG: {}",
+        has(0x40),
+        has(0x100),
+        has(0x4000)
+    );
+
+    if let Some(clr) = &info.clr {
+        write!(report, "# CLR runtime: {}.{}", clr.runtime.0, clr.runtime.1);
+        if let Some(version) = &clr.metadata_version {
+            write!(report, ", metadata version {}", version);
+        }
+        if clr.flags & 0x1 != 0 {
+            write!(report, ", IL only");
+        }
+        if clr.flags & 0x2 != 0 {
+            write!(report, ", 32-bit required");
+        }
+        if clr.flags & 0x8 != 0 {
+            write!(report, ", strong name signed");
+        }
+        writeln!(report);
+    }
+
+    writeln!(report, "# Sections: {}", info.sections.len());
+    for section in info.sections.iter() {
+        write!(
+            report,
+            "{} {} virtual size {}, raw size {}, entropy {:.2}",
+            section.name,
+            section_flags(section.characteristics),
+            format_size(args, section.virtual_size as u64),
+            format_size(args, section.raw_size as u64),
+            section.entropy
+        );
+        if section.entropy > 7.2 {
+            write!(report, " (compressed or encrypted)");
+        }
+        if section.characteristics & 0xA000_0000 == 0xA000_0000 {
+            write!(report, " (WARNING: writable and executable)");
+        }
+        writeln!(report);
+    }
+
+    if !info.imports.is_empty() {
+        writeln!(
+            report,
+            "# Imports: {} function(s) from {} librar(y/ies)",
+            info.imports
+                .iter()
+                .map(|i| i.functions.len())
+                .sum::<usize>(),
+            info.imports.len()
+        );
+        for import in info.imports.iter() {
+            writeln!(
+                report,
+                "{}{}: {}",
+                import.library,
+                if import.delayed {
+                    " (delay-loaded)"
+                } else {
+                    ""
+                },
+                import.functions.join(", ")
+            );
+        }
+    }
+    if !info.exports.is_empty() || info.export_name.is_some() {
+        writeln!(
+            report,
+            "# Exports: {} name(s) from {}",
+            info.exports.len(),
+            info.export_name.as_deref().unwrap_or("?")
+        );
+        for export in info.exports.iter() {
+            writeln!(report, "{}", export);
+        }
+    }
+
+    if !info.resources.is_empty() {
+        write!(report, "# Resources: ");
+        for (kind, count) in info.resources.iter() {
+            write!(report, "{} ({}); ", resource_type_name(*kind), count);
+        }
+        writeln!(report);
+    }
+    if let Some(version) = &info.fixed_version {
+        writeln!(report, "# File version: {}", version);
+    }
+    if !info.version_strings.is_empty() {
+        writeln!(report, "# Version information:");
+        for (name, value) in info.version_strings.iter() {
+            writeln!(report, "{}: {}", name, value);
+        }
+    }
+    if let Some(level) = &info.execution_level {
+        writeln!(report, "# Requested execution level: {}", level);
+    }
+
+    match info.certificate_type {
+        Some(kind) => {
+            write!(report, "# Authenticode: certificate table present");
+            if kind != 2 {
+                write!(report, " (certificate type {})", kind);
+            }
+            writeln!(report, ", signature not verified");
+            if let Some(signer) = &info.signer {
+                writeln!(report, "Signer: {}", signer.subject);
+                writeln!(report, "Issuer: {}", signer.issuer);
+                writeln!(
+                    report,
+                    "Valid: {} to {}",
+                    signer.not_before, signer.not_after
+                );
+            }
+        }
+        None => writeln!(report, "# Authenticode: not signed"),
+    }
+    if let Some((offset, size)) = info.overlay {
+        writeln!(
+            report,
+            "# Overlay: {} at offset {}",
+            format_size(args, size as u64),
+            offset
+        );
+    }
+}
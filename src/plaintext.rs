@@ -0,0 +1,939 @@
+use crate::input::Input;
+use crate::limits::is_timed_out;
+use crate::report::Report;
+use crate::{get_extension_from_id, Arguments};
+
+/// Bytes sniffed to tell text from binary files.
+const SNIFF_LENGTH: usize = 8192;
+/// Bytes read from the end of file for Vim modelines.
+const TAIL_LENGTH: usize = 1024;
+/// Lines at the beginning and end of file searched for modelines, the same as Vim does.
+const MODELINE_LINES: usize = 5;
+
+/// Extension ids of interpreters named in shebang lines.
+const INTERPRETER_IDS: [(&str, &str); 24] = [
+    ("sh", "sh"),
+    ("bash", "sh"),
+    ("dash", "sh"),
+    ("ash", "sh"),
+    ("ksh", "sh"),
+    ("mksh", "sh"),
+    ("zsh", "sh"),
+    ("python", "py"),
+    ("pypy", "py"),
+    ("ruby", "rb"),
+    ("perl", "pl"),
+    ("node", "js"),
+    ("nodejs", "js"),
+    ("bun", "js"),
+    ("deno", "ts"),
+    ("ts-node", "ts"),
+    ("php", "php"),
+    ("lua", "lua"),
+    ("luajit", "lua"),
+    ("awk", "awk"),
+    ("gawk", "awk"),
+    ("mawk", "awk"),
+    ("nawk", "awk"),
+    ("busybox", "sh"),
+];
+
+/// Extension ids of Emacs modes and Vim filetypes.
+const MODE_IDS: [(&str, &str); 30] = [
+    ("python", "py"),
+    ("ruby", "rb"),
+    ("perl", "pl"),
+    ("cperl", "pl"),
+    ("sh", "sh"),
+    ("bash", "sh"),
+    ("zsh", "sh"),
+    ("shell-script", "sh"),
+    ("javascript", "js"),
+    ("js", "js"),
+    ("typescript", "ts"),
+    ("c", "c"),
+    ("cpp", "cpp"),
+    ("c++", "cpp"),
+    ("rust", "rs"),
+    ("go", "go"),
+    ("java", "java"),
+    ("cs", "cs"),
+    ("csharp", "cs"),
+    ("lua", "lua"),
+    ("php", "php"),
+    ("awk", "awk"),
+    ("yaml", "yaml"),
+    ("toml", "toml"),
+    ("json", "json"),
+    ("xml", "xml"),
+    ("nxml", "xml"),
+    ("html", "html"),
+    ("markdown", "md"),
+    ("dosini", "ini"),
+];
+
+/// Idioms typical for languages, each match adds one point.
+const LANGUAGE_HINTS: [(&str, &[&str]); 17] = [
+    (
+        "Rust",
+        &[
+            "fn ",
+            "let mut ",
+            "impl ",
+            "pub fn ",
+            "use std::",
+            "-> Self",
+            "&mut ",
+            "#[derive(",
+            "Option<",
+            "Result<",
+            "unwrap()",
+            "pub struct ",
+            "&str",
+            "println!(",
+        ],
+    ),
+    (
+        "Python",
+        &[
+            "def ", "import ", "self.", "elif ", "None", "True", "__init__", "print(", "lambda ",
+            "\"\"\"",
+        ],
+    ),
+    (
+        "C",
+        &[
+            "#include <",
+            "int main(",
+            "printf(",
+            "->",
+            "struct ",
+            "malloc(",
+            "sizeof(",
+            "NULL",
+            "#define ",
+            "free(",
+        ],
+    ),
+    (
+        "C++",
+        &[
+            "std::",
+            "template<",
+            "template <",
+            "namespace ",
+            "cout",
+            "public:",
+            "private:",
+            "nullptr",
+            "#include <",
+            "virtual ",
+        ],
+    ),
+    (
+        "JavaScript",
+        &[
+            "function ",
+            "console.log",
+            "require(",
+            "module.exports",
+            "document.",
+            "===",
+            "!==",
+            "export default",
+            "undefined",
+            "async function",
+        ],
+    ),
+    (
+        "TypeScript",
+        &[
+            "interface ",
+            ": string",
+            ": number",
+            "export type ",
+            ": boolean",
+            "as const",
+            "readonly ",
+            "import {",
+            "<T>",
+            ": void",
+        ],
+    ),
+    (
+        "Go",
+        &[
+            "package ",
+            "func ",
+            ":= ",
+            "import (",
+            "fmt.",
+            "err != nil",
+            "chan ",
+            "defer ",
+            "go func",
+            "struct {",
+        ],
+    ),
+    (
+        "Java",
+        &[
+            "public class ",
+            "System.out",
+            "import java.",
+            "public static void main",
+            "extends ",
+            "implements ",
+            "@Override",
+            "private final ",
+            "throws ",
+            "String[] ",
+        ],
+    ),
+    (
+        "C#",
+        &[
+            "using System",
+            "Console.",
+            "{ get;",
+            "async Task",
+            "public class ",
+            "namespace ",
+            "public override ",
+            "string[] ",
+            "var ",
+            "<summary>",
+        ],
+    ),
+    (
+        "Shell",
+        &[
+            "#!/bin/sh",
+            "#!/bin/bash",
+            "fi\n",
+            "then\n",
+            "esac",
+            "echo ",
+            "$(",
+            "done\n",
+            "export ",
+            "${",
+        ],
+    ),
+    (
+        "Ruby",
+        &[
+            "def ",
+            "end\n",
+            "puts ",
+            "require '",
+            "attr_accessor",
+            "do |",
+            ".each ",
+            "elsif ",
+            "nil",
+            "unless ",
+        ],
+    ),
+    (
+        "PHP",
+        &[
+            "<?php",
+            "$this->",
+            "public function",
+            "array(",
+            "echo ",
+            "=> $",
+            "namespace ",
+            "use ",
+            "function ",
+            "?>",
+        ],
+    ),
+    (
+        "Kotlin",
+        &[
+            "fun ",
+            "val ",
+            "data class ",
+            "override fun",
+            "companion object",
+            "?.let",
+            "when (",
+            "println(",
+            "lateinit ",
+            "object ",
+        ],
+    ),
+    (
+        "Swift",
+        &[
+            "func ",
+            "import Foundation",
+            "guard ",
+            "extension ",
+            "@objc",
+            "import UIKit",
+            "some View",
+            "?? ",
+            "init(",
+            "protocol ",
+        ],
+    ),
+    (
+        "Perl",
+        &[
+            "my $",
+            "use strict",
+            "sub ",
+            "=~",
+            "foreach ",
+            "@_",
+            "$_",
+            "use warnings",
+            "qw(",
+            "print \"",
+        ],
+    ),
+    (
+        "Lua",
+        &[
+            "local ",
+            "function ",
+            "end\n",
+            "then\n",
+            "elseif ",
+            "~=",
+            "require(\"",
+            "pairs(",
+            "nil",
+            "..\"",
+        ],
+    ),
+    (
+        "AWK",
+        &[
+            "BEGIN {", "END {", "$0", "NR", "NF", "FS", "print $", "getline", "printf(", "next\n",
+        ],
+    ),
+];
+
+/// Languages expected for extensions, preferred when content matches them almost as well.
+const EXTENSION_LANGUAGES: [(&str, &str); 16] = [
+    ("rs", "Rust"),
+    ("py", "Python"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cpp", "C++"),
+    ("js", "JavaScript"),
+    ("ts", "TypeScript"),
+    ("go", "Go"),
+    ("java", "Java"),
+    ("cs", "C#"),
+    ("sh", "Shell"),
+    ("rb", "Ruby"),
+    ("php", "PHP"),
+    ("pl", "Perl"),
+    ("lua", "Lua"),
+    ("awk", "AWK"),
+];
+
+/// Text statistics which don't depend on encoding.
+#[derive(Default)]
+pub struct TextInfo {
+    pub encoding: String,
+    pub has_bom: bool,
+    /// Offset of first invalid UTF-8 sequence and number of invalid sequences.
+    pub invalid_utf8: Option<(usize, usize)>,
+    pub lines: usize,
+    pub words: usize,
+    pub chars: usize,
+    pub lf: usize,
+    pub crlf: usize,
+    pub cr: usize,
+    pub final_newline: bool,
+    pub trailing_whitespace: usize,
+    pub tab_indented: usize,
+    pub space_indented: usize,
+    pub mixed_indented: usize,
+    pub indent_width: Option<usize>,
+    /// Length in characters and line number (from 1).
+    pub longest_line: (usize, usize),
+}
+
+impl TextInfo {
+    pub fn line_ending(&self) -> String {
+        match (self.lf > 0, self.crlf > 0, self.cr > 0) {
+            (false, false, false) => "none".to_string(),
+            (true, false, false) => "LF (Unix)".to_string(),
+            (false, true, false) => "CRLF (Windows)".to_string(),
+            (false, false, true) => "CR (classic Mac OS)".to_string(),
+            _ => format!("mixed ({} LF, {} CRLF, {} CR)", self.lf, self.crlf, self.cr),
+        }
+    }
+}
+
+/// Is file likely text, judged by its beginning.
+pub fn is_text(data: &[u8]) -> bool {
+    let sample = &data[..data.len().min(SNIFF_LENGTH)];
+    if sample.is_empty() {
+        return false;
+    }
+    if get_bom(sample).is_some() || get_utf16_or_32(sample).is_some() {
+        return true;
+    }
+    // Control characters other than whitespace, form feed and escape are rare in text.
+    let binary = sample
+        .iter()
+        .filter(|b| matches!(b, 0..=8 | 14..=26 | 28..=31 | 127))
+        .count();
+    binary == 0 || (binary * 100 / sample.len() < 1 && !sample.contains(&0))
+}
+
+/// Gets encoding and length of byte order mark.
+fn get_bom(data: &[u8]) -> Option<(&'static str, usize)> {
+    // UTF-32 LE starts with UTF-16 LE mark, so it's checked first.
+    [
+        (&b"\xEF\xBB\xBF"[..], "UTF-8"),
+        (&b"\xFF\xFE\x00\x00"[..], "UTF-32LE"),
+        (&b"\x00\x00\xFE\xFF"[..], "UTF-32BE"),
+        (&b"\xFF\xFE"[..], "UTF-16LE"),
+        (&b"\xFE\xFF"[..], "UTF-16BE"),
+    ]
+    .iter()
+    .find(|(bom, _)| data.starts_with(bom))
+    .map(|(bom, encoding)| (*encoding, bom.len()))
+}
+
+/// Guesses UTF-16 or UTF-32 without BOM from positions of zero bytes in mostly ASCII text.
+fn get_utf16_or_32(data: &[u8]) -> Option<&'static str> {
+    if data.len() < 4 {
+        return None;
+    }
+    let mut zeros = [0usize; 4];
+    for (i, b) in data.iter().enumerate() {
+        if *b == 0 {
+            zeros[i % 4] += 1;
+        }
+    }
+    let quarter = data.len() / 4;
+    let half = data.len() / 2;
+    let mostly = |count: usize, total: usize| count * 10 >= total * 9;
+    // Lowest byte of characters is rarely zero, otherwise zero-filled data would pass.
+    let rarely = |count: usize| count * 10 < quarter;
+    if mostly(zeros[1], quarter)
+        && mostly(zeros[2], quarter)
+        && mostly(zeros[3], quarter)
+        && rarely(zeros[0])
+    {
+        Some("UTF-32LE")
+    } else if mostly(zeros[0], quarter)
+        && mostly(zeros[1], quarter)
+        && mostly(zeros[2], quarter)
+        && rarely(zeros[3])
+    {
+        Some("UTF-32BE")
+    } else if mostly(zeros[1] + zeros[3], half) && zeros[0] + zeros[2] == 0 {
+        Some("UTF-16LE")
+    } else if mostly(zeros[0] + zeros[2], half) && zeros[1] + zeros[3] == 0 {
+        Some("UTF-16BE")
+    } else {
+        None
+    }
+}
+
+/// Guesses legacy single-byte encoding from high bytes.
+fn get_legacy_encoding(data: &[u8]) -> &'static str {
+    // Most common lowercase Russian letters (о, е, а, и, н, т) in both encodings.
+    let windows_1251 = data
+        .iter()
+        .filter(|b| matches!(b, 0xEE | 0xE5 | 0xE0 | 0xE8 | 0xED | 0xF2))
+        .count();
+    let koi8_r = data
+        .iter()
+        .filter(|b| matches!(b, 0xCF | 0xC5 | 0xC1 | 0xC9 | 0xCE | 0xD4))
+        .count();
+    let high = data.iter().filter(|b| **b >= 0x80).count();
+    let letters = data.iter().filter(|b| b.is_ascii_alphabetic()).count();
+    // Cyrillic text is mostly high bytes, Western European one only has some accents.
+    if high > letters {
+        if koi8_r > windows_1251 {
+            return "KOI8-R (guessed)";
+        }
+        return "Windows-1251 (guessed)";
+    }
+    // Windows-1252 uses C1 range for printable characters like quotes and euro sign.
+    if data.iter().any(|b| (0x80..=0x9F).contains(b)) {
+        "Windows-1252 (guessed)"
+    } else {
+        "ISO-8859-1 (guessed)"
+    }
+}
+
+/// Gets offset of first invalid UTF-8 sequence and count of them.
+fn get_invalid_utf8(data: &[u8]) -> Option<(usize, usize)> {
+    let mut first = None;
+    let mut count = 0;
+    let mut offset = 0;
+    while offset < data.len() {
+        match std::str::from_utf8(&data[offset..]) {
+            Ok(_) => break,
+            Err(e) => {
+                first.get_or_insert(offset + e.valid_up_to());
+                count += 1;
+                // Sequence cut at the end has no error length.
+                offset += e.valid_up_to() + e.error_len().unwrap_or(data.len());
+            }
+        }
+    }
+    first.map(|first| (first, count))
+}
+
+/// Decodes UTF-16 or UTF-32 text, invalid characters are replaced.
+fn decode_wide(data: &[u8], encoding: &str) -> String {
+    match encoding {
+        "UTF-16LE" | "UTF-16BE" => {
+            let units: Vec<u16> = data
+                .chunks_exact(2)
+                .map(|c| match encoding {
+                    "UTF-16LE" => u16::from_le_bytes([c[0], c[1]]),
+                    _ => u16::from_be_bytes([c[0], c[1]]),
+                })
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => data
+            .chunks_exact(4)
+            .map(|c| {
+                let value = match encoding {
+                    "UTF-32LE" => u32::from_le_bytes([c[0], c[1], c[2], c[3]]),
+                    _ => u32::from_be_bytes([c[0], c[1], c[2], c[3]]),
+                };
+                char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER)
+            })
+            .collect(),
+    }
+}
+
+/// Gets encoding, validity and decoded text.
+fn decode(data: &[u8], info: &mut TextInfo) -> String {
+    let (encoding, bom_length) = match get_bom(data) {
+        Some((encoding, length)) => {
+            info.has_bom = true;
+            (encoding, length)
+        }
+        None => match get_utf16_or_32(&data[..data.len().min(SNIFF_LENGTH)]) {
+            Some(encoding) => (encoding, 0),
+            None => ("", 0),
+        },
+    };
+    let data = &data[bom_length..];
+    if encoding.starts_with("UTF-16") || encoding.starts_with("UTF-32") {
+        info.encoding = encoding.to_string();
+        return decode_wide(data, encoding);
+    }
+    info.invalid_utf8 = get_invalid_utf8(data);
+    info.encoding = match (info.invalid_utf8, data.is_ascii()) {
+        (None, true) if !info.has_bom => "ASCII".to_string(),
+        (None, _) => "UTF-8".to_string(),
+        // Few errors in otherwise UTF-8 text are more likely corruption than other encoding.
+        (Some((_, errors)), _) if errors * 100 < data.iter().filter(|b| **b >= 0x80).count() => {
+            "UTF-8 (with invalid sequences)".to_string()
+        }
+        (Some(_), _) => get_legacy_encoding(data).to_string(),
+    };
+    if info.encoding.starts_with("UTF-8") || info.encoding == "ASCII" {
+        String::from_utf8_lossy(data).to_string()
+    } else {
+        // Counting is the same for any single-byte encoding.
+        data.iter().map(|b| *b as char).collect()
+    }
+}
+
+/// Guesses indentation width from leading spaces.
+fn get_indent_width(widths: &[usize]) -> Option<usize> {
+    if widths.is_empty() {
+        return None;
+    }
+    [8, 4, 3, 2]
+        .into_iter()
+        .find(|width| widths.iter().filter(|w| *w % width == 0).count() * 10 >= widths.len() * 9)
+        .or(Some(1))
+}
+
+/// Gets encoding of text file, like `UTF-8` or `UTF-16LE`, `None` for binary files.
+pub fn get_text_encoding(data: &[u8]) -> Option<String> {
+    if !is_text(&data[..data.len().min(SNIFF_LENGTH)]) {
+        return None;
+    }
+    let mut info = TextInfo::default();
+    decode(data, &mut info);
+    Some(info.encoding)
+}
+
+/// Gets charset used in media types, like `utf-8`, from encoding of text file.
+pub fn get_charset(encoding: &str) -> String {
+    match encoding {
+        "ASCII" => "us-ascii".to_string(),
+        _ if encoding.starts_with("UTF-8") => "utf-8".to_string(),
+        _ => encoding.trim_end_matches(" (guessed)").to_lowercase(),
+    }
+}
+
+/// Gets counts, line endings and whitespace statistics of text file.
+pub fn get_text_structure(data: &[u8]) -> TextInfo {
+    let mut info = TextInfo::default();
+    let text = decode(data, &mut info);
+    info.chars = text.chars().count();
+    info.words = text.split_whitespace().count();
+
+    let bytes = text.as_bytes();
+    for (i, b) in bytes.iter().enumerate() {
+        match b {
+            b'\n' if i > 0 && bytes[i - 1] == b'\r' => info.crlf += 1,
+            b'\n' => info.lf += 1,
+            b'\r' if bytes.get(i + 1) != Some(&b'\n') => info.cr += 1,
+            _ => (),
+        }
+    }
+    info.final_newline = text.ends_with(['\n', '\r']);
+
+    let mut space_widths = Vec::new();
+    // Splitting on all line endings, CRLF leaves `\n` piece right after CR which is skipped.
+    let mut after_cr = false;
+    let lines = text.split_inclusive(['\n', '\r']).filter(|line| {
+        let skip = after_cr && *line == "\n";
+        after_cr = line.ends_with('\r');
+        !skip
+    });
+    for (number, line) in lines.enumerate() {
+        if is_timed_out() {
+            break;
+        }
+        info.lines += 1;
+        let line = line.trim_end_matches(['\n', '\r']);
+        let length = line.chars().count();
+        if length > info.longest_line.0 {
+            info.longest_line = (length, number + 1);
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        if line.ends_with([' ', '\t']) {
+            info.trailing_whitespace += 1;
+        }
+        let indent: &str = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        match (indent.contains('\t'), indent.contains(' ')) {
+            (true, true) => info.mixed_indented += 1,
+            (true, false) => info.tab_indented += 1,
+            (false, true) => {
+                info.space_indented += 1;
+                space_widths.push(indent.len());
+            }
+            (false, false) => (),
+        }
+    }
+    info.indent_width = get_indent_width(&space_widths);
+    info
+}
+
+/// Guesses programming language by counting typical idioms, extension breaks close calls.
+pub fn get_language(text: &str, extension: &str) -> Option<(&'static str, usize)> {
+    let scores: Vec<(&str, usize)> = LANGUAGE_HINTS
+        .iter()
+        .map(|(language, hints)| {
+            let score: usize = hints.iter().map(|hint| text.matches(hint).count()).sum();
+            (*language, score)
+        })
+        .collect();
+    // First language wins a tie, so more specific ones are listed before similar ones.
+    let best = scores
+        .iter()
+        .copied()
+        .filter(|(_, score)| *score > 0)
+        .fold(
+            None,
+            |best: Option<(&str, usize)>, (language, score)| match best {
+                Some((_, best_score)) if best_score >= score => best,
+                _ => Some((language, score)),
+            },
+        )?;
+    let expected = EXTENSION_LANGUAGES
+        .iter()
+        .find(|(e, _)| *e == extension)
+        .and_then(|(_, language)| scores.iter().find(|(l, _)| l == language));
+    match expected {
+        Some((language, score)) if score * 2 >= best.1 => Some((language, *score)),
+        _ => Some(best),
+    }
+}
+
+/// Gets extension id of interpreter in shebang line like `#!/usr/bin/env -S python3 -u`.
+pub fn get_shebang_id(line: &str) -> Option<&'static str> {
+    let command = line.strip_prefix("#!")?.trim();
+    let mut words = command.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        // Options, variable assignments and split string (`-S`) come before program.
+        let mut rest: Vec<&str> = Vec::new();
+        let mut skip = false;
+        for word in words.by_ref() {
+            if std::mem::take(&mut skip) {
+                continue;
+            }
+            // Options with separate argument.
+            if ["-u", "--unset", "-C", "--chdir"].contains(&word) {
+                skip = true;
+                continue;
+            }
+            rest.extend(word.strip_prefix("-S").filter(|w| !w.is_empty()));
+            if !word.starts_with('-') && !word.contains('=') {
+                rest.push(word);
+                break;
+            }
+        }
+        program = rest.first()?.rsplit('/').next()?;
+    }
+    // Versioned names like python3.12 or lua5.4.
+    let name = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETER_IDS
+        .iter()
+        .find(|(interpreter, _)| *interpreter == name)
+        .map(|(_, id)| *id)
+}
+
+/// Gets extension id from Emacs `-*- mode: python -*-` or Vim `vim: set ft=python :` modeline.
+pub fn get_modeline_id(line: &str) -> Option<&'static str> {
+    let mode = if let Some(start) = line.find("-*-") {
+        let variables = &line[start + 3..];
+        let variables = &variables[..variables.find("-*-")?];
+        // Either just mode name or `mode: name; other: value` pairs.
+        variables
+            .split(';')
+            .find_map(|pair| match pair.split_once(':') {
+                Some((key, value)) if key.trim().eq_ignore_ascii_case("mode") => Some(value),
+                Some(_) => None,
+                None => Some(pair),
+            })?
+            .trim()
+            .to_lowercase()
+    } else {
+        let start = ["vim:", "vi:", "ex:"]
+            .iter()
+            .filter_map(|marker| line.find(marker).map(|i| i + marker.len()))
+            .min()?;
+        line[start..]
+            .split([' ', ':', '\t'])
+            .find_map(|option| {
+                option
+                    .strip_prefix("ft=")
+                    .or_else(|| option.strip_prefix("filetype="))
+                    .or_else(|| option.strip_prefix("syntax="))
+                    .or_else(|| option.strip_prefix("syn="))
+            })?
+            .to_lowercase()
+    };
+    MODE_IDS
+        .iter()
+        .find(|(name, _)| *name == mode)
+        .map(|(_, id)| *id)
+}
+
+/// Gets extension id of structured text by its prologue or shape of lines.
+pub fn get_structure_id(text: &str) -> Option<&'static str> {
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    let lower = text
+        .chars()
+        .take(256)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if lower.starts_with("<?php") {
+        return Some("php");
+    }
+    if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+        return Some("html");
+    }
+    if lower.starts_with("<?xml") {
+        // XHTML is still HTML for readers.
+        return Some(if lower.contains("<html") {
+            "html"
+        } else {
+            "xml"
+        });
+    }
+    let end = text.trim_end();
+    let first_token = text.get(1..).and_then(|t| t.trim_start().chars().next());
+    if (text.starts_with('{') && end.ends_with('}') && matches!(first_token, Some('"' | '}')))
+        || (text.starts_with('[')
+            && end.ends_with(']')
+            && matches!(first_token, Some('{' | '[' | '"' | ']' | '-' | '0'..='9')))
+    {
+        return Some("json");
+    }
+    if text.starts_with("%YAML") || text.starts_with("---\n") || text.starts_with("---\r\n") {
+        return Some("yaml");
+    }
+
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with(['#', ';']))
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+    let is_key = |key: &str| {
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '"' | ' '))
+    };
+    let sections = lines
+        .iter()
+        .filter(|l| l.starts_with('[') && l.ends_with(']'))
+        .count();
+    let assignments: Vec<&str> = lines
+        .iter()
+        .filter_map(|l| l.split_once('=').filter(|(key, _)| is_key(key.trim())))
+        .map(|(_, value)| value.trim())
+        .collect();
+    let mappings = lines
+        .iter()
+        .filter(|l| {
+            l.starts_with("- ")
+                || l.split_once(": ")
+                    .or_else(|| l.strip_suffix(':').map(|key| (key, "")))
+                    .is_some_and(|(key, _)| is_key(key))
+        })
+        .count();
+    // Most lines must fit, prose often has a colon or an equals sign here and there.
+    if (sections + assignments.len()) * 10 >= lines.len() * 9 && !assignments.is_empty() {
+        // TOML values are typed, INI values are bare strings.
+        let typed = assignments
+            .iter()
+            .filter(|value| {
+                value.starts_with(['"', '\'', '[', '{'])
+                    || ["true", "false"].contains(value)
+                    || value.parse::<f64>().is_ok()
+            })
+            .count();
+        let toml_tables = lines.iter().any(|l| l.starts_with("[["));
+        return Some(if toml_tables || typed * 10 >= assignments.len() * 8 {
+            "toml"
+        } else {
+            "ini"
+        });
+    }
+    if mappings * 10 >= lines.len() * 9 && mappings >= 2 {
+        return Some("yaml");
+    }
+    None
+}
+
+/// Gets extension id of text file without extension from shebang, modelines or structure.
+pub fn get_text_id(head: &str, tail: &str) -> Option<&'static str> {
+    let mut lines = head.lines();
+    let first = lines.next()?;
+    if let Some(id) = get_shebang_id(first) {
+        return Some(id);
+    }
+    // Emacs looks at the first line or the second one after shebang, Vim at first and last lines.
+    let last_lines: Vec<&str> = tail.lines().rev().take(MODELINE_LINES).collect();
+    std::iter::once(first)
+        .chain(lines.take(MODELINE_LINES - 1))
+        .chain(last_lines)
+        .find_map(get_modeline_id)
+        .or_else(|| get_structure_id(head))
+}
+
+/// Gets extension of text file without extension (scripts and structured text).
+pub fn get_text_extension(args: &Arguments, input: &Input) -> Option<String> {
+    let data = input.bytes();
+    let head = &data[..data.len().min(SNIFF_LENGTH)];
+    if !is_text(head) {
+        return None;
+    }
+    // Modelines can be at the end of longer files.
+    let tail = if head.len() == SNIFF_LENGTH {
+        &data[data.len().saturating_sub(TAIL_LENGTH)..]
+    } else {
+        head
+    };
+    let id = get_text_id(
+        &String::from_utf8_lossy(head),
+        &String::from_utf8_lossy(tail),
+    )?;
+    get_extension_from_id(args, id.to_string()).ok()
+}
+
+/// Gets specified text info about file, language is guessed for source code with given extension.
+pub fn get_text_info(
+    _args: &Arguments,
+    input: &Input,
+    code_extension: Option<&str>,
+    report: &mut Report,
+) {
+    // Binary files are skipped by their beginning only.
+    let data = input.bytes();
+    if code_extension.is_none() && !is_text(&data[..data.len().min(SNIFF_LENGTH)]) {
+        return;
+    }
+    writeln!(report, "## Text information");
+    let info = get_text_structure(data);
+    write!(report, "# Encoding: {}", info.encoding);
+    if info.has_bom {
+        write!(report, " (with BOM)");
+    }
+    writeln!(report);
+    if let Some((offset, count)) = info.invalid_utf8 {
+        writeln!(
+            report,
+            "# Invalid UTF-8: {} sequences, first at offset {}",
+            count, offset
+        );
+    }
+    writeln!(
+        report,
+        "# Lines: {}, words: {}, characters: {}",
+        info.lines, info.words, info.chars
+    );
+    write!(report, "# Line endings: {}", info.line_ending());
+    if !info.final_newline && info.lines > 0 {
+        write!(report, ", no newline at end of file");
+    }
+    writeln!(report);
+    writeln!(
+        report,
+        "# Longest line: {} characters (line {})",
+        info.longest_line.0, info.longest_line.1
+    );
+    writeln!(
+        report,
+        "# Lines with trailing whitespace: {}",
+        info.trailing_whitespace
+    );
+    write!(
+        report,
+        "# Indentation: {} lines with tabs, {} with spaces",
+        info.tab_indented, info.space_indented
+    );
+    if let Some(width) = info.indent_width {
+        write!(report, " (width {})", width);
+    }
+    if info.mixed_indented > 0 {
+        write!(report, ", {} mixed", info.mixed_indented);
+    }
+    writeln!(report);
+    if let Some(extension) = code_extension.filter(|_| !is_timed_out()) {
+        match get_language(&String::from_utf8_lossy(data), extension) {
+            Some((language, score)) => {
+                writeln!(
+                    report,
+                    "# Language: {} ({} keyword matches)",
+                    language, score
+                )
+            }
+            None => writeln!(report, "# Language: unknown"),
+        }
+    }
+}
@@ -1,35 +1,51 @@
-use crate::Arguments;
+use crate::input::Input;
+use crate::limits::allow_entry;
+use crate::report::Report;
 use unrar::{ListSplit, VolumeInfo};
 
 /// Gets specified rar info about file.
-pub fn get_rar_info(args: &Arguments) {
-    println!("## RAR information");
+pub fn get_rar_info(input: &Input, report: &mut Report) {
+    writeln!(report, "## RAR information");
+    // Unrar library reads archive by itself, it would get whole file.
+    if input.is_truncated() {
+        writeln!(
+            report,
+            "Error: RAR archive can't be read only up to --max-read"
+        );
+        return;
+    }
     let mut option = None;
-    match unrar::Archive::new(&args.file_path).break_open::<ListSplit>(Some(&mut option)) {
+    match unrar::Archive::new(&input.path).break_open::<ListSplit>(Some(&mut option)) {
         // Looks like I need to write my own implementations of rar lib
         Ok(archive) => {
             if archive.has_comment() {
-                println!("# Comment: currently not supported",)
+                writeln!(report, "# Comment: currently not supported",)
             }
             if archive.volume_info() != VolumeInfo::None {
-                println!("# This is multi-part archive, it is not supported for now.");
+                writeln!(
+                    report,
+                    "# This is multi-part archive, it is not supported for now."
+                );
                 return;
             }
             if let Some(error) = option {
                 // If the error's data field holds an OpenArchive, an error occurred while opening,
                 // the archive is partly broken (e.g. broken header), but is still readable from.
                 // So we continue reading
-                println!("Error: {}, continuing.", error);
+                writeln!(report, "Error: {}, continuing.", error);
             }
-            for entry in archive {
+            for (i, entry) in archive.enumerate() {
+                if !allow_entry(i) {
+                    break;
+                }
                 match entry {
-                    Ok(e) => println!("{}", e),
-                    Err(err) => println!("Error: {}", err),
+                    Ok(e) => writeln!(report, "{}", e),
+                    Err(err) => writeln!(report, "Error: {}", err),
                 }
             }
         }
         Err(e) => {
-            println!("Error: {}", e);
+            writeln!(report, "Error: {}", e);
         }
     }
 }
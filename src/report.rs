@@ -0,0 +1,125 @@
+use crate::components::Section;
+use std::any::Any;
+use std::fmt::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+
+/// Where text of report goes.
+enum Output {
+    Stdout,
+    Collected(String),
+    Dropped,
+}
+
+/// Report about one file, analyzers write to it with `write!` and `writeln!`.
+/// It's printed right away, or collected so reports of files analyzed in parallel are printed whole.
+pub struct Report {
+    output: Output,
+    /// Values of noted fields, `None` when fields are not selected.
+    fields: Option<Vec<(String, String)>>,
+}
+
+impl Report {
+    /// Report printed to stdout as it's written.
+    pub fn stdout() -> Report {
+        Report {
+            output: Output::Stdout,
+            fields: None,
+        }
+    }
+
+    /// Report collected into string.
+    pub fn collected() -> Report {
+        Report {
+            output: Output::Collected(String::new()),
+            fields: None,
+        }
+    }
+
+    /// Report with everything written to it dropped.
+    pub fn dropped() -> Report {
+        Report {
+            output: Output::Dropped,
+            fields: None,
+        }
+    }
+
+    /// Report which only collects values of noted fields.
+    pub fn fields() -> Report {
+        Report {
+            output: Output::Dropped,
+            fields: Some(Vec::new()),
+        }
+    }
+
+    /// Writes formatted text, used by `write!` and `writeln!`.
+    /// Writing to string can't fail and stdout fails like `print!` does.
+    pub fn write_fmt(&mut self, text: fmt::Arguments) {
+        match &mut self.output {
+            Output::Stdout => print!("{}", text),
+            Output::Collected(report) => {
+                let _ = report.write_fmt(text);
+            }
+            Output::Dropped => (),
+        }
+    }
+
+    /// Notes value of field, so it can be selected by `--fields` as `section.key`.
+    pub fn note_field(&mut self, section: Section, key: &str, value: &str) {
+        if let Some(fields) = &mut self.fields {
+            let section: &str = section.into();
+            fields.push((format!("{}.{}", section, key), value.to_string()));
+        }
+    }
+
+    /// Prints field as `# Label: value` and notes its value.
+    pub fn print_field(
+        &mut self,
+        section: Section,
+        key: &str,
+        label: &str,
+        value: impl fmt::Display,
+    ) {
+        let value = value.to_string();
+        self.note_field(section, key, &value);
+        writeln!(self, "# {}: {}", label, value);
+    }
+
+    /// Gets collected text, empty if report wasn't collected.
+    pub fn into_text(self) -> String {
+        match self.output {
+            Output::Collected(report) => report,
+            _ => String::new(),
+        }
+    }
+
+    /// Gets values of noted fields.
+    pub fn into_fields(self) -> Vec<(String, String)> {
+        self.fields.unwrap_or_default()
+    }
+}
+
+/// Gets message of panic, it's usually string.
+fn get_panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown error"
+    }
+}
+
+/// Runs function and collects everything it writes to report.
+/// Panic is caught and noted at the end of report, so other files can be still analyzed.
+pub fn collect_report(f: impl FnOnce(&mut Report)) -> String {
+    let mut report = Report::collected();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| f(&mut report)));
+    if let Err(payload) = result {
+        writeln!(
+            report,
+            "Error: analysis failed: {}",
+            get_panic_message(&*payload)
+        );
+    }
+    report.into_text()
+}
@@ -0,0 +1,103 @@
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Error, ErrorKind, Read, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Path meaning standard input.
+pub const STDIN_PATH: &str = "-";
+
+/// Stream copied to temporary file, as analyzers open input by path, many times and with seeking.
+/// File is removed when dropped.
+pub struct Spool {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Is path standard input, pipe, socket or character device, which can be read only once.
+pub fn is_stream(path: &Path) -> bool {
+    if path == Path::new(STDIN_PATH) {
+        return true;
+    }
+    #[cfg(unix)]
+    if let Ok(metadata) = fs::metadata(path) {
+        let file_type = metadata.file_type();
+        return file_type.is_fifo() || file_type.is_socket() || file_type.is_char_device();
+    }
+    false
+}
+
+/// Describes source of stream, like `standard input`.
+pub fn describe_stream(path: &Path) -> String {
+    if path == Path::new(STDIN_PATH) {
+        "standard input".to_string()
+    } else {
+        format!("stream {}", path.to_string_lossy())
+    }
+}
+
+/// Creates new temporary file only readable by user.
+fn create_temp_file() -> Result<(PathBuf, File), Error> {
+    let dir = env::temp_dir();
+    let nanoseconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    for attempt in 0..100 {
+        let path = dir.join(format!(
+            "fat-{}-{}-{}.spool",
+            process::id(),
+            nanoseconds,
+            attempt
+        ));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(Error::new(
+        ErrorKind::AlreadyExists,
+        "could not create temporary file",
+    ))
+}
+
+/// Copies stream to temporary file, failing if it's longer than limit.
+pub fn spool(reader: impl Read, limit: u64) -> Result<Spool, Error> {
+    let (path, file) = create_temp_file()?;
+    let mut spool = Spool { path, size: 0 };
+    let mut writer = BufWriter::new(file);
+    spool.size = io::copy(&mut reader.take(limit.saturating_add(1)), &mut writer)?;
+    if spool.size > limit {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "input is larger than spool limit of {} bytes (see --spool-limit)",
+                limit
+            ),
+        ));
+    }
+    writer.flush()?;
+    Ok(spool)
+}
+
+/// Copies standard input or other stream to temporary file.
+pub fn spool_stream(path: &Path, limit: u64) -> Result<Spool, Error> {
+    if path == Path::new(STDIN_PATH) {
+        spool(io::stdin().lock(), limit)
+    } else {
+        spool(File::open(path)?, limit)
+    }
+}
@@ -0,0 +1,175 @@
+use std::io::{Error, ErrorKind, Read};
+
+const BLOCK_SIZE: usize = 512;
+
+/// File header of tar archive, pax and GNU long names already applied.
+pub struct TarEntry {
+    pub name: String,
+    pub size: u64,
+    /// `0` regular file, `5` directory, `2` symbolic link...
+    pub kind: u8,
+}
+
+impl TarEntry {
+    pub fn is_file(&self) -> bool {
+        matches!(self.kind, b'0' | b'\0' | b'7')
+    }
+}
+
+/// Is block a tar header (checked by checksum).
+pub fn is_tar_header(block: &[u8]) -> bool {
+    if block.len() < BLOCK_SIZE || block[..BLOCK_SIZE].iter().all(|b| *b == 0) {
+        return false;
+    }
+    let Some(checksum) = parse_number(&block[148..156]) else {
+        return false;
+    };
+    // Checksum field itself is counted as spaces.
+    let sum: u64 = block[..BLOCK_SIZE]
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            if (148..156).contains(&i) {
+                0x20
+            } else {
+                *b as u64
+            }
+        })
+        .sum();
+    sum == checksum
+}
+
+/// Parses octal number, or base-256 number used by GNU tar for large values.
+fn parse_number(field: &[u8]) -> Option<u64> {
+    if field.first().is_some_and(|b| b & 0x80 != 0) {
+        return Some(
+            field[1..]
+                .iter()
+                .fold(0u64, |n, b| n.wrapping_shl(8) | *b as u64),
+        );
+    }
+    let text = std::str::from_utf8(field).ok()?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+fn parse_string(field: &[u8]) -> String {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).to_string()
+}
+
+/// Parses pax extended header records like `30 path=some/long/name\n`.
+fn parse_pax(data: &[u8]) -> Vec<(String, String)> {
+    let mut records = Vec::new();
+    let mut rest = data;
+    while let Some(space) = rest.iter().position(|b| *b == b' ') {
+        let Some(length) = std::str::from_utf8(&rest[..space])
+            .ok()
+            .and_then(|l| l.parse::<usize>().ok())
+        else {
+            break;
+        };
+        if length <= space || length > rest.len() {
+            break;
+        }
+        let record = String::from_utf8_lossy(&rest[space + 1..length]);
+        if let Some((key, value)) = record.trim_end_matches('\n').split_once('=') {
+            records.push((key.to_string(), value.to_string()));
+        }
+        rest = &rest[length..];
+    }
+    records
+}
+
+fn read_data<R: Read>(reader: &mut R, size: u64) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    reader.by_ref().take(size).read_to_end(&mut data)?;
+    if (data.len() as u64) < size {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "truncated tar entry"));
+    }
+    skip_padding(reader, size)?;
+    Ok(data)
+}
+
+fn skip_padding<R: Read>(reader: &mut R, size: u64) -> Result<(), Error> {
+    let padding = (BLOCK_SIZE as u64 - size % BLOCK_SIZE as u64) % BLOCK_SIZE as u64;
+    std::io::copy(&mut reader.by_ref().take(padding), &mut std::io::sink())?;
+    Ok(())
+}
+
+/// Calls function for every entry of tar archive with reader of its data.
+pub fn for_each_tar_entry<R, F>(mut reader: R, mut f: F) -> Result<(), Error>
+where
+    R: Read,
+    F: FnMut(&TarEntry, &mut dyn Read),
+{
+    let mut long_name: Option<String> = None;
+    let mut pax: Vec<(String, String)> = Vec::new();
+    let mut header = [0u8; BLOCK_SIZE];
+    loop {
+        // End of archive is marked by zero blocks, some writers omit them.
+        match reader.read_exact(&mut header) {
+            Ok(()) => (),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        }
+        if header.iter().all(|b| *b == 0) {
+            return Ok(());
+        }
+        if !is_tar_header(&header) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "invalid tar header checksum",
+            ));
+        }
+        let mut entry = TarEntry {
+            name: parse_string(&header[0..100]),
+            size: parse_number(&header[124..136]).unwrap_or(0),
+            kind: header[156],
+        };
+        match entry.kind {
+            // GNU long name of next entry
+            b'L' => {
+                let data = read_data(&mut reader, entry.size)?;
+                long_name = Some(parse_string(&data));
+                continue;
+            }
+            b'x' => {
+                pax = parse_pax(&read_data(&mut reader, entry.size)?);
+                continue;
+            }
+            b'g' | b'K' => {
+                read_data(&mut reader, entry.size)?;
+                continue;
+            }
+            _ => (),
+        }
+        if &header[257..262] == b"ustar" && header[345] != 0 {
+            entry.name = format!("{}/{}", parse_string(&header[345..500]), entry.name);
+        }
+        if let Some(name) = long_name.take() {
+            entry.name = name;
+        }
+        for (key, value) in pax.drain(..) {
+            match key.as_str() {
+                "path" => entry.name = value,
+                "size" => entry.size = value.parse().unwrap_or(entry.size),
+                _ => (),
+            }
+        }
+        // Only regular files have data, size of links is their target length in some writers.
+        let size = if matches!(entry.kind, b'1' | b'2' | b'3' | b'4' | b'5' | b'6') {
+            0
+        } else {
+            entry.size
+        };
+        let mut data = reader.by_ref().take(size);
+        f(&entry, &mut data);
+        // Skip data not read by function.
+        std::io::copy(&mut data, &mut std::io::sink())?;
+        skip_padding(&mut reader, size)?;
+    }
+}
@@ -15,10 +15,20 @@ mod tests {
             extensions_path,
             gui: false,
             is_debug: true,
-            is_human: false,
-            only_general: false,
-            ignore_general: false,
-            extension_info: false,
+            units: SizeUnits::Plain,
+            size_precision: 1,
+            sections: DEFAULT_SECTIONS.to_vec(),
+            fields: Vec::new(),
+            line: None,
+            brief: false,
+            entropy_block_size: 4096,
+            hashes: Vec::new(),
+            hash_sums: false,
+            time_format: TimeFormat::Default,
+            time_zone: TimeZone::utc(),
+            name: None,
+            stream_path: None,
+            limits: Limits::default(),
         }
     }
 
@@ -55,11 +65,1650 @@ mod tests {
         let mut file_path = once_fixture.file_path.clone();
         file_path.push(format!("{}.zip", extension));
 
-        let buf_reader: BufReader<fs::File> = BufReader::new(fs::File::open(file_path).unwrap());
+        let input = Input::open(&file_path).unwrap();
 
         assert_eq!(
-            crate::zip::get_complex_zip_extension(&once_fixture, buf_reader).unwrap(),
+            crate::zip::get_complex_zip_extension(&once_fixture, &input, &mut Report::dropped())
+                .unwrap(),
             extension
         );
     }
+
+    /// Builds minimal baseline JPEG with standard (quality 50) luminance table and trailing data.
+    fn sample_jpeg() -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        data.extend([0xFF, 0xE0, 0x00, 0x10]);
+        data.extend(b"JFIF\0\x01\x02\x01\x00\x48\x00\x48\x00\x00");
+        data.extend([0xFF, 0xDB, 0x00, 0x43, 0x00]);
+        data.extend([
+            16, 11, 12, 14, 12, 10, 16, 14, 13, 14, 18, 17, 16, 19, 24, 40, 26, 24, 22, 22, 24, 49,
+            35, 37, 29, 40, 58, 51, 61, 60, 57, 51, 56, 55, 64, 72, 92, 78, 64, 68, 87, 69, 55, 56,
+            80, 109, 81, 87, 95, 98, 103, 104, 103, 62, 77, 113, 121, 112, 100, 120, 92, 101, 103,
+            99,
+        ]);
+        data.extend([
+            0xFF, 0xC2, 0x00, 0x0B, 0x08, 0x00, 0x08, 0x00, 0x10, 0x01, 0x01, 0x11, 0x00,
+        ]);
+        data.extend([0xFF, 0xDA, 0x00, 0x08, 0x01, 0x01, 0x00, 0x00, 0x3F, 0x00]);
+        data.extend([0x12, 0xFF, 0x00, 0x34, 0xFF, 0xD0, 0x56]);
+        data.extend([0xFF, 0xD9]);
+        data.extend(b"PK\x03\x04");
+        data
+    }
+
+    #[test]
+    fn jpeg_structure_test() {
+        let data = sample_jpeg();
+        let info = crate::jpeg::get_jpeg_structure(&data).unwrap();
+        let markers: Vec<String> = info
+            .segments
+            .iter()
+            .map(|s| crate::jpeg::marker_name(s.marker))
+            .collect();
+        assert_eq!(markers, ["SOI", "APP0", "DQT", "SOF2", "SOS", "EOI"]);
+
+        let frame = info.frame.unwrap();
+        assert_eq!(
+            crate::jpeg::frame_type(frame.marker),
+            "Progressive DCT (Huffman)"
+        );
+        assert_eq!((frame.width, frame.height), (16, 8));
+        assert_eq!(
+            crate::jpeg::estimate_quality(&info.quantization_tables[0]),
+            50
+        );
+        assert!(info.jfif.unwrap().starts_with("version 1.02"));
+        assert_eq!(info.end_offset, Some(data.len() - 4));
+
+        // Broken file can have more ICC chunks than fits in their count.
+        let mut data = sample_jpeg();
+        let mut chunks = Vec::new();
+        for i in 0..300 {
+            chunks.extend([0xFF, 0xE2, 0x00, 0x11]);
+            chunks.extend(b"ICC_PROFILE\0");
+            chunks.extend([(i % 256) as u8, 255, b'x']);
+        }
+        data.splice(2..2, chunks);
+        let icc = crate::jpeg::get_jpeg_structure(&data).unwrap().icc.unwrap();
+        assert_eq!((icc.chunks, icc.expected_chunks), (300, 255));
+    }
+
+    #[test]
+    fn mp3_structure_test() {
+        let mut data = b"ID3\x03\x00\x00\x00\x00\x00\x10".to_vec();
+        data.extend(b"TIT2\x00\x00\x00\x06\x00\x00\x00Hello");
+        // MPEG-1 Layer III, 128 kbit/s, 44100 Hz, 417 bytes per frame.
+        for _ in 0..5 {
+            data.extend([0xFF, 0xFB, 0x90, 0x00]);
+            data.extend([0; 413]);
+        }
+
+        let tag = crate::audio::get_id3v2(&data).unwrap();
+        assert_eq!(tag.version, (3, 0));
+        assert_eq!(tag.frames[0].id, "TIT2");
+        assert_eq!(tag.frames[0].value.as_deref(), Some("Hello"));
+
+        let stream = crate::audio::get_mpeg_stream(&data, tag.size).unwrap();
+        assert_eq!(stream.first_offset, 26);
+        assert_eq!(stream.frames, 5);
+        assert_eq!((stream.min_bitrate, stream.max_bitrate), (128, 128));
+        assert!((stream.duration() - 5.0 * 1152.0 / 44100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn mp3_truncated_xing_test() {
+        // File ends right after Xing tag of first frame, before its flags.
+        let mut data = vec![0xFF, 0xFB, 0x90, 0x00];
+        data.extend([0; 32]);
+        data.extend(b"Xing");
+        let stream = crate::audio::get_mpeg_stream(&data, 0).unwrap();
+        assert_eq!(stream.vbr_header, None);
+    }
+
+    #[test]
+    fn vorbis_comment_test() {
+        let mut data = Vec::new();
+        data.extend(6u32.to_le_bytes());
+        data.extend(b"vendor");
+        data.extend(2u32.to_le_bytes());
+        for comment in ["title=Song", "ARTIST=Band"] {
+            data.extend((comment.len() as u32).to_le_bytes());
+            data.extend(comment.as_bytes());
+        }
+        let comment = crate::audio::get_vorbis_comment(&data).unwrap();
+        assert_eq!(comment.vendor, "vendor");
+        assert_eq!(
+            comment.comments,
+            [
+                ("TITLE".to_string(), "Song".to_string()),
+                ("ARTIST".to_string(), "Band".to_string())
+            ]
+        );
+    }
+
+    fn bmff_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut data = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        data.extend(kind);
+        data.extend(payload);
+        data
+    }
+
+    #[test]
+    fn isobmff_structure_test() {
+        let mut mvhd = vec![0; 20];
+        mvhd[12..16].copy_from_slice(&1000u32.to_be_bytes());
+        mvhd[16..20].copy_from_slice(&5000u32.to_be_bytes());
+        let mut tkhd = vec![0; 84];
+        tkhd[3] = 1;
+        tkhd[12..16].copy_from_slice(&1u32.to_be_bytes());
+        tkhd[76..80].copy_from_slice(&(640u32 << 16).to_be_bytes());
+        tkhd[80..84].copy_from_slice(&(480u32 << 16).to_be_bytes());
+        let mut mdhd = vec![0; 24];
+        mdhd[12..16].copy_from_slice(&90000u32.to_be_bytes());
+        mdhd[16..20].copy_from_slice(&450000u32.to_be_bytes());
+        // "eng" packed as 5-bit letters.
+        mdhd[20..22].copy_from_slice(&0x15C7u16.to_be_bytes());
+        let mut hdlr = vec![0; 24];
+        hdlr[8..12].copy_from_slice(b"vide");
+        let mut stsd = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stsd.extend(bmff_box(b"avc1", &[0; 78]));
+        let stbl = bmff_box(b"stbl", &bmff_box(b"stsd", &stsd));
+        let mut mdia = bmff_box(b"mdhd", &mdhd);
+        mdia.extend(bmff_box(b"hdlr", &hdlr));
+        mdia.extend(bmff_box(b"minf", &stbl));
+        let mut trak = bmff_box(b"tkhd", &tkhd);
+        trak.extend(bmff_box(b"mdia", &mdia));
+        let location = b"+37.7858-122.4064/";
+        let mut xyz = (location.len() as u16).to_be_bytes().to_vec();
+        xyz.extend([0x15, 0xC7]);
+        xyz.extend(location);
+        let mut moov = bmff_box(b"mvhd", &mvhd);
+        moov.extend(bmff_box(b"trak", &trak));
+        moov.extend(bmff_box(
+            b"udta",
+            &bmff_box(&[0xA9, b'x', b'y', b'z'], &xyz),
+        ));
+
+        let mut data = bmff_box(b"ftyp", b"isom\x00\x00\x02\x00isomavc1");
+        data.extend(bmff_box(b"moov", &moov));
+        data.extend(bmff_box(b"mdat", &[0; 16]));
+
+        let (major, _, compatible) = crate::isobmff::get_brands(&data).unwrap();
+        assert_eq!(crate::isobmff::get_bmff_id(&major, &compatible), "mp4");
+        assert_eq!(
+            crate::isobmff::get_bmff_id("mif1", &["mif1".to_string(), "heic".to_string()]),
+            "heic"
+        );
+
+        let boxes = crate::isobmff::get_boxes(&data, 0, data.len(), 0);
+        let kinds: Vec<String> = boxes.iter().map(|b| b.kind()).collect();
+        assert_eq!(kinds, ["ftyp", "moov", "mdat"]);
+        // Box with largesize near u64::MAX is cut at the end of data.
+        let mut huge = data.clone();
+        huge.extend(b"\x00\x00\x00\x01free\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xF0");
+        let boxes = crate::isobmff::get_boxes(&huge, 0, huge.len(), 0);
+        assert_eq!(boxes[3].kind(), "free");
+        assert_eq!(boxes[3].size, 16);
+
+        let tracks = crate::isobmff::get_tracks(&data, &boxes[1]);
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].handler, "vide");
+        assert_eq!(tracks[0].codec.as_deref(), Some("avc1"));
+        assert_eq!(tracks[0].dimensions, Some((640, 480)));
+        assert_eq!(tracks[0].duration, Some(5.0));
+        assert_eq!(tracks[0].language.as_deref(), Some("eng"));
+
+        let tags = crate::isobmff::get_tags(&data, &boxes[1]);
+        assert_eq!(
+            tags,
+            [("GPS location".to_string(), "+37.7858-122.4064/".to_string())]
+        );
+    }
+
+    fn ebml_element(id: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut data = id.to_vec();
+        data.extend((0x4000 | payload.len() as u16).to_be_bytes());
+        data.extend(payload);
+        data
+    }
+
+    #[test]
+    fn matroska_structure_test() {
+        let mut data = ebml_element(
+            &[0x1A, 0x45, 0xDF, 0xA3],
+            &ebml_element(&[0x42, 0x82], b"webm"),
+        );
+        let mut info = ebml_element(&[0x2A, 0xD7, 0xB1], &1_000_000u32.to_be_bytes());
+        info.extend(ebml_element(&[0x44, 0x89], &2500.0f64.to_be_bytes()));
+        info.extend(ebml_element(&[0x7B, 0xA9], b"Sample"));
+        let mut video = ebml_element(&[0xB0], &[0x05, 0x00]);
+        video.extend(ebml_element(&[0xBA], &[0x02, 0xD0]));
+        let mut entry = ebml_element(&[0xD7], &[1]);
+        entry.extend(ebml_element(&[0x83], &[1]));
+        entry.extend(ebml_element(&[0x86], b"V_VP9"));
+        entry.extend(ebml_element(&[0x55, 0xAA], &[1]));
+        entry.extend(ebml_element(&[0xE0], &video));
+        let mut file = ebml_element(&[0x46, 0x6E], b"font.ttf");
+        file.extend(ebml_element(&[0x46, 0x60], b"font/ttf"));
+        file.extend(ebml_element(&[0x46, 0x5C], &[0; 32]));
+
+        // Segment and cluster have unknown size, like in live streams.
+        let mut segment = ebml_element(&[0x15, 0x49, 0xA9, 0x66], &info);
+        segment.extend(ebml_element(
+            &[0x16, 0x54, 0xAE, 0x6B],
+            &ebml_element(&[0xAE], &entry),
+        ));
+        segment.extend(ebml_element(
+            &[0x19, 0x41, 0xA4, 0x69],
+            &ebml_element(&[0x61, 0xA7], &file),
+        ));
+        segment.extend([
+            0x1F, 0x43, 0xB6, 0x75, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        ]);
+        segment.extend(ebml_element(&[0xE7], &[0]));
+        segment.extend(ebml_element(
+            &[0x1C, 0x53, 0xBB, 0x6B],
+            &ebml_element(&[0xBB], &[]),
+        ));
+        data.extend([
+            0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        ]);
+        data.extend(segment);
+
+        let info = crate::matroska::get_matroska_structure(&data).unwrap();
+        assert_eq!(info.doc_type, "webm");
+        assert_eq!(info.title.as_deref(), Some("Sample"));
+        assert_eq!(info.duration, Some(2.5));
+        assert_eq!(info.tracks.len(), 1);
+        let track = &info.tracks[0];
+        assert_eq!(crate::matroska::track_type(track.kind), "video");
+        assert_eq!(track.codec_id, "V_VP9");
+        assert_eq!(track.dimensions, Some((1280, 720)));
+        assert!(track.default && track.forced);
+        assert_eq!(info.attachments[0].name, "font.ttf");
+        assert_eq!(info.attachments[0].size, 32);
+        assert_eq!(info.clusters, 1);
+        assert_eq!(info.cue_points, Some(1));
+    }
+
+    #[test]
+    fn matroska_nesting_test() {
+        // Chapter atoms nested deeper than stack allows, all of unknown size.
+        let mut data = ebml_element(
+            &[0x1A, 0x45, 0xDF, 0xA3],
+            &ebml_element(&[0x42, 0x82], b"matroska"),
+        );
+        data.extend([
+            0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        ]);
+        data.extend([0x10, 0x43, 0xA7, 0x70, 0xFF, 0x45, 0xB9, 0xFF]);
+        data.extend([0xB6, 0xFF].repeat(1_000_000));
+
+        crate::limits::start(Limits::default());
+        let info = crate::matroska::get_matroska_structure(&data).unwrap();
+        assert_eq!(info.chapters.len(), 33);
+    }
+
+    /// Builds PDF from object bodies with correct xref table.
+    fn sample_pdf(objects: &[&str], trailer: &str) -> Vec<u8> {
+        let mut data = b"%PDF-1.7\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(data.len());
+            data.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+        let xref = data.len();
+        data.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            data.extend(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        data.extend(format!("trailer\n{}\nstartxref\n{}\n%%EOF\n", trailer, xref).as_bytes());
+        data
+    }
+
+    #[test]
+    fn pdf_structure_test() {
+        use crate::pdf::{
+            decode_text, format_pdf_date, get_suspicious_keys, get_xref_chain, Pdf, PdfObject,
+        };
+
+        let data = sample_pdf(
+            &[
+                "<< /Type /Catalog /Pages 2 0 R /Open#41ction 4 0 R >>",
+                "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+                "<< /Type /Page /Parent 2 0 R >>",
+                "<< /S /JavaScript /JS (app.alert\\(1\\)) >>",
+                "<< /Title <FEFF00480069> /CreationDate (D:20240102030405+01'00') >>",
+                "<< /Length 5 >>\nstream\nhello\nendstream",
+            ],
+            "<< /Size 7 /Root 1 0 R /Info 5 0 R >>",
+        );
+        let pdf = Pdf::new(&data);
+        assert_eq!(pdf.objects.len(), 6);
+        assert_eq!(pdf.escaped_names, 1);
+        assert_eq!(
+            pdf.objects[&6],
+            PdfObject::Stream(
+                vec![("Length".to_string(), PdfObject::Number(5.0))],
+                data.windows(5).position(|w| w == b"hello").unwrap(),
+                5
+            )
+        );
+
+        let (sections, trailer, warning) = get_xref_chain(&pdf);
+        assert!(warning.is_none());
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].entries, 7);
+        let trailer = PdfObject::Dictionary(trailer);
+        let catalog = pdf.get(&trailer, "Root").unwrap();
+        let count = pdf.get(catalog, "Pages").and_then(|p| pdf.get(p, "Count"));
+        assert_eq!(count, Some(&PdfObject::Number(1.0)));
+
+        let info = pdf.get(&trailer, "Info").unwrap();
+        let Some(PdfObject::String(title)) = pdf.get(info, "Title") else {
+            panic!()
+        };
+        assert_eq!(decode_text(title), "Hi");
+        let mut args = once_fixture();
+        assert_eq!(
+            format_pdf_date(&args, "D:20240102030405+01'00'"),
+            "2024-01-02 02:04:05 UTC"
+        );
+        args.time_format = TimeFormat::Unix;
+        assert_eq!(format_pdf_date(&args, "D:19700101000100Z"), "60");
+        assert_eq!(format_pdf_date(&args, "D:bad"), "D:bad");
+
+        let suspicious = get_suspicious_keys(&pdf);
+        assert_eq!(suspicious.get("OpenAction"), Some(&1));
+        assert_eq!(suspicious.get("JavaScript"), Some(&1));
+    }
+
+    #[test]
+    fn elf_structure_test() {
+        use crate::elf::get_elf_structure;
+
+        // ELF64 little endian PIE with interpreter and dynamic section, no section headers.
+        let (interp, strtab, dynamic) = (400u64, 416u64, 440u64);
+        let mut data = vec![0u8; 64];
+        data[..8].copy_from_slice(&[0x7F, b'E', b'L', b'F', 2, 1, 1, 3]);
+        data[16..18].copy_from_slice(&3u16.to_le_bytes());
+        data[18..20].copy_from_slice(&62u16.to_le_bytes());
+        data[24..32].copy_from_slice(&0x1040u64.to_le_bytes());
+        data[32..40].copy_from_slice(&64u64.to_le_bytes());
+        data[54..56].copy_from_slice(&56u16.to_le_bytes());
+        data[56..58].copy_from_slice(&6u16.to_le_bytes());
+        let segments: [(u32, u32, u64, u64); 6] = [
+            (6, 4, 64, 336),
+            (1, 5, 0, 520),
+            (3, 4, interp, 11),
+            (2, 6, dynamic, 80),
+            (0x6474E551, 6, 0, 0),
+            (0x6474E552, 4, dynamic, 80),
+        ];
+        for (kind, flags, offset, size) in segments {
+            data.extend(kind.to_le_bytes());
+            data.extend(flags.to_le_bytes());
+            data.extend(offset.to_le_bytes());
+            data.extend(offset.to_le_bytes());
+            data.extend(offset.to_le_bytes());
+            data.extend(size.to_le_bytes());
+            data.extend(size.to_le_bytes());
+            data.extend(8u64.to_le_bytes());
+        }
+        data.extend(b"/lib/ld.so\0");
+        data.resize(strtab as usize, 0);
+        data.extend(b"\0libc.so.6\0/opt/lib\0");
+        data.resize(dynamic as usize, 0);
+        for (tag, value) in [
+            (1u64, 1u64),
+            (29, 11),
+            (5, strtab),
+            (0x6FFFFFFB, 0x08000001),
+            (0, 0),
+        ] {
+            data.extend(tag.to_le_bytes());
+            data.extend(value.to_le_bytes());
+        }
+
+        let info = get_elf_structure(&data).unwrap();
+        assert!(info.is_64 && !info.big_endian);
+        assert_eq!(info.machine, 62);
+        assert_eq!(info.entry, 0x1040);
+        assert_eq!(info.segments.len(), 6);
+        assert_eq!(info.interpreter.as_deref(), Some("/lib/ld.so"));
+        assert_eq!(info.needed, vec!["libc.so.6".to_string()]);
+        assert_eq!(info.runpath.as_deref(), Some("/opt/lib"));
+        assert!(info.bind_now && info.pie_flag);
+        assert!(info.sections.is_empty() && info.symbols.is_none());
+        assert!(get_elf_structure(b"\x7FELF").is_err());
+
+        // Offsets near u64::MAX are out of file, not overflowing.
+        let mut hostile = data.clone();
+        hostile[40..48].copy_from_slice(&(u64::MAX - 8).to_le_bytes());
+        hostile[58..60].copy_from_slice(&64u16.to_le_bytes());
+        hostile[60..62].copy_from_slice(&3u16.to_le_bytes());
+        // GNU_STACK becomes note segment at the end of address space.
+        hostile[288..292].copy_from_slice(&4u32.to_le_bytes());
+        hostile[296..304].copy_from_slice(&(u64::MAX - 4).to_le_bytes());
+        hostile[320..328].copy_from_slice(&100u64.to_le_bytes());
+        let needed = dynamic as usize + 8;
+        hostile[needed..needed + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        let info = get_elf_structure(&hostile).unwrap();
+        assert!(info.sections.is_empty() && info.needed.is_empty());
+    }
+
+    #[test]
+    fn pe_structure_test() {
+        use crate::pe::get_pe_structure;
+
+        // PE32+ console executable with one section holding import table, and overlay.
+        let mut data = vec![0u8; 0x200];
+        data[..2].copy_from_slice(b"MZ");
+        data[0x3C..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        data[0x40..0x4D].copy_from_slice(b"This program ");
+        data[0x80..0x84].copy_from_slice(b"PE\0\0");
+        data[0x84..0x86].copy_from_slice(&0x8664u16.to_le_bytes());
+        data[0x86..0x88].copy_from_slice(&1u16.to_le_bytes());
+        data[0x94..0x96].copy_from_slice(&240u16.to_le_bytes());
+        data[0x96..0x98].copy_from_slice(&0x22u16.to_le_bytes());
+        let optional = 0x98;
+        data[optional..optional + 2].copy_from_slice(&0x20Bu16.to_le_bytes());
+        data[optional + 16..optional + 20].copy_from_slice(&0x1000u32.to_le_bytes());
+        data[optional + 60..optional + 64].copy_from_slice(&0x200u32.to_le_bytes());
+        data[optional + 68..optional + 70].copy_from_slice(&3u16.to_le_bytes());
+        data[optional + 70..optional + 72].copy_from_slice(&0x4160u16.to_le_bytes());
+        data[optional + 108..optional + 112].copy_from_slice(&16u32.to_le_bytes());
+        // Import directory
+        data[optional + 120..optional + 124].copy_from_slice(&0x1000u32.to_le_bytes());
+        let section = optional + 240;
+        data[section..section + 6].copy_from_slice(b".idata");
+        for (at, value) in [
+            (8, 0x200u32),
+            (12, 0x1000),
+            (16, 0x200),
+            (20, 0x200),
+            (36, 0xC000_0040),
+        ] {
+            data[section + at..section + at + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        // Descriptor at 0x1000, lookup table at 0x1040, hint/name at 0x1060, library name at 0x1080.
+        let mut idata = vec![0u8; 0x200];
+        idata[..4].copy_from_slice(&0x1040u32.to_le_bytes());
+        idata[12..16].copy_from_slice(&0x1080u32.to_le_bytes());
+        idata[0x40..0x48].copy_from_slice(&0x1060u64.to_le_bytes());
+        idata[0x48..0x50].copy_from_slice(&(0x8000_0000_0000_0000u64 | 7).to_le_bytes());
+        idata[0x62..0x6D].copy_from_slice(b"ExitProcess");
+        idata[0x80..0x8C].copy_from_slice(b"KERNEL32.dll");
+        data.extend(idata);
+        data.extend([0xAAu8; 16]);
+
+        let info = get_pe_structure(&data).unwrap();
+        assert_eq!(info.machine, 0x8664);
+        assert!(info.is_pe32_plus);
+        assert_eq!(info.subsystem, 3);
+        assert_eq!(info.entry_point, 0x1000);
+        assert_eq!(info.dos_message.as_deref(), Some("This program "));
+        assert_eq!(info.sections.len(), 1);
+        assert_eq!(info.sections[0].name, ".idata");
+        assert_eq!(info.imports.len(), 1);
+        assert_eq!(info.imports[0].library, "KERNEL32.dll");
+        assert_eq!(info.imports[0].functions, vec!["ExitProcess", "#7"]);
+        assert_eq!(info.overlay, Some((0x400, 16)));
+        assert!(info.certificate_type.is_none() && info.clr.is_none());
+        assert!(get_pe_structure(b"MZ").is_err());
+
+        // Section data past 4 GiB is out of file, imports can't be read.
+        data[section + 20..section + 24].copy_from_slice(&0xFFFF_FFF8u32.to_le_bytes());
+        data[optional + 120..optional + 124].copy_from_slice(&0x1010u32.to_le_bytes());
+        let info = get_pe_structure(&data).unwrap();
+        assert!(info.imports.is_empty());
+    }
+
+    /// Appends Mach-O load command with little endian u32 fields and raw tail, padded to 8 bytes.
+    fn macho_command(data: &mut Vec<u8>, command: u32, fields: &[u32], tail: &[u8]) {
+        let size = (8 + fields.len() * 4 + tail.len()).next_multiple_of(8);
+        data.extend(command.to_le_bytes());
+        data.extend((size as u32).to_le_bytes());
+        fields.iter().for_each(|f| data.extend(f.to_le_bytes()));
+        data.extend(tail);
+        data.resize(data.len() + size - 8 - fields.len() * 4 - tail.len(), 0);
+    }
+
+    #[test]
+    fn macho_structure_test() {
+        use crate::macho::{cpu_name, get_fat_slices, get_macho_structure, get_plist_entries};
+
+        let mut commands = Vec::new();
+        macho_command(&mut commands, 0x1B, &[], &[0xAB; 16]);
+        macho_command(&mut commands, 0x32, &[2, 0x000E_0000, 0x0011_0200, 0], &[]);
+        macho_command(
+            &mut commands,
+            0xC,
+            &[24, 2, 0x0001_0203, 0x0001_0000],
+            b"/usr/lib/libSystem.B.dylib\0",
+        );
+        macho_command(
+            &mut commands,
+            0x8000_001C,
+            &[12],
+            b"@executable_path/Frameworks\0",
+        );
+        let mut segment = b"__TEXT\0\0\0\0\0\0\0\0\0\0".to_vec();
+        for value in [0x1_0000_0000u64, 0x4000, 0, 0x4000] {
+            segment.extend(value.to_le_bytes());
+        }
+        for value in [5u32, 5, 1, 0] {
+            segment.extend(value.to_le_bytes());
+        }
+        segment.extend(b"__text\0\0\0\0\0\0\0\0\0\0__TEXT\0\0\0\0\0\0\0\0\0\0");
+        segment.extend(0x1_0000_0400u64.to_le_bytes());
+        segment.extend(0x10u64.to_le_bytes());
+        segment.extend([0u8; 32]);
+        macho_command(&mut commands, 0x19, &[], &segment);
+        let signature_offset = 32 + commands.len() as u32 + 16;
+
+        // Code directory with identifier and team, and XML entitlements.
+        let mut directory = Vec::new();
+        for value in [0xFADE0C02u32, 79, 0x20200, 0x10000, 0, 52, 0, 0, 0] {
+            directory.extend(value.to_be_bytes());
+        }
+        directory.extend([32, 2, 0, 12]);
+        directory.extend([0u32, 0, 68].iter().flat_map(|v| v.to_be_bytes()));
+        directory.extend(b"com.example.app\0ABCDE12345\0");
+        let plist = b"<plist><dict><key>get-task-allow</key><true/><key>keychain-access-groups</key><array><string>A.b</string><string>A.c</string></array></dict></plist>";
+        let mut entitlements = 0xFADE7171u32.to_be_bytes().to_vec();
+        entitlements.extend((8 + plist.len() as u32).to_be_bytes());
+        entitlements.extend(plist);
+        let mut blob = Vec::new();
+        let total = 28 + directory.len() + entitlements.len();
+        for value in [
+            0xFADE0CC0u32,
+            total as u32,
+            2,
+            0,
+            28,
+            5,
+            28 + directory.len() as u32,
+        ] {
+            blob.extend(value.to_be_bytes());
+        }
+        blob.extend(directory);
+        blob.extend(entitlements);
+        macho_command(
+            &mut commands,
+            0x1D,
+            &[signature_offset, blob.len() as u32],
+            &[],
+        );
+
+        let mut data = Vec::new();
+        for value in [
+            0xFEEDFACFu32,
+            0x0100_000C,
+            0,
+            2,
+            6,
+            commands.len() as u32,
+            0x200085,
+            0,
+        ] {
+            data.extend(value.to_le_bytes());
+        }
+        data.extend(commands);
+        data.extend(blob);
+
+        let info = get_macho_structure(&data).unwrap();
+        assert!(info.is_64 && !info.big_endian);
+        assert_eq!(cpu_name(info.cpu_type, info.cpu_subtype), "arm64");
+        assert_eq!(info.file_type, 2);
+        assert_eq!(
+            info.uuid.as_deref(),
+            Some("ABABABAB-ABAB-ABAB-ABAB-ABABABABABAB")
+        );
+        let build = info.build_version.as_ref().unwrap();
+        assert_eq!(
+            (
+                build.platform,
+                build.minimal_os.as_str(),
+                build.sdk.as_str()
+            ),
+            ("iOS", "14.0.0", "17.2.0")
+        );
+        assert_eq!(info.dylibs.len(), 1);
+        assert_eq!(info.dylibs[0].name, "/usr/lib/libSystem.B.dylib");
+        assert_eq!(info.dylibs[0].current_version, "1.2.3");
+        assert_eq!(info.rpaths, vec!["@executable_path/Frameworks".to_string()]);
+        assert_eq!(info.segments.len(), 1);
+        assert_eq!(info.segments[0].sections[0].name, "__text");
+        let signature = info.code_signature.as_ref().unwrap();
+        assert_eq!(signature.identifier.as_deref(), Some("com.example.app"));
+        assert_eq!(signature.team_id.as_deref(), Some("ABCDE12345"));
+        assert!(!signature.has_cms);
+        let entries = get_plist_entries(signature.entitlements.as_ref().unwrap());
+        assert_eq!(
+            entries[0],
+            ("get-task-allow".to_string(), "true".to_string())
+        );
+        assert_eq!(entries[1].1, "A.b, A.c");
+
+        // Same file wrapped in universal binary.
+        let mut fat = Vec::new();
+        for value in [
+            0xCAFEBABEu32,
+            1,
+            0x0100_000C,
+            0,
+            4096,
+            data.len() as u32,
+            12,
+        ] {
+            fat.extend(value.to_be_bytes());
+        }
+        let slices = get_fat_slices(&fat).unwrap();
+        assert_eq!(
+            (slices[0].offset, slices[0].size),
+            (4096, data.len() as u64)
+        );
+        assert!(get_fat_slices(&data).is_none());
+    }
+
+    /// Builds WebAssembly section with LEB128 size (payloads here are under 128 bytes).
+    fn wasm_section(id: u8, payload: &[u8]) -> Vec<u8> {
+        let mut section = vec![id, payload.len() as u8];
+        section.extend(payload);
+        section
+    }
+
+    #[test]
+    fn wasm_structure_test() {
+        use crate::report::collect_report;
+        use crate::spool::spool;
+        use crate::wasm::{get_wasm_info, get_wasm_structure};
+
+        let mut data = b"\0asm\x01\0\0\0".to_vec();
+        // (i32, i32) -> (i32) and () -> ()
+        data.extend(wasm_section(
+            1,
+            &[2, 0x60, 2, 0x7F, 0x7F, 1, 0x7F, 0x60, 0, 0],
+        ));
+        data.extend(wasm_section(2, b"\x01\x03env\x03log\x00\x01"));
+        data.extend(wasm_section(3, &[2, 0, 1]));
+        data.extend(wasm_section(5, &[1, 0x01, 0x11, 0x80, 0x01]));
+        data.extend(wasm_section(7, b"\x02\x03add\x00\x01\x06memory\x02\x00"));
+        data.extend(wasm_section(8, &[2]));
+        data.extend(wasm_section(
+            0,
+            b"\x09producers\x01\x08language\x01\x04Rust\x061.80.0",
+        ));
+        data.extend(wasm_section(0, b"\x10sourceMappingURL\x0capp.wasm.map"));
+
+        let info = get_wasm_structure(&data).unwrap();
+        assert!(!info.is_component);
+        assert_eq!(info.sections.len(), 8);
+        assert_eq!(info.imports.len(), 1);
+        assert_eq!(info.imports[0].module, "env");
+        assert_eq!(info.imports[0].description, "func () -> ()");
+        assert_eq!(info.imported_functions, 1);
+        assert_eq!(info.functions.len(), 3);
+        assert_eq!(info.exports[0].name, "add");
+        assert_eq!(info.exports[0].description, "func (i32, i32) -> (i32)");
+        assert_eq!(info.exports[1].description, "memory 0");
+        assert_eq!(
+            (info.memories[0].min, info.memories[0].max),
+            (17, Some(128))
+        );
+        assert_eq!(info.start, Some(2));
+        assert_eq!(
+            info.producers,
+            vec![("language".to_string(), vec!["Rust 1.80.0".to_string()])]
+        );
+        assert_eq!(info.source_map.as_deref(), Some("app.wasm.map"));
+
+        let component = b"\0asm\x0d\0\x01\0";
+        assert!(get_wasm_structure(component).unwrap().is_component);
+        assert!(get_wasm_structure(b"\0asm\x02\0\0\0").is_err());
+
+        // Core modules of component are read, nested component and broken module are errors.
+        let mut core = b"\0asm\x01\0\0\0".to_vec();
+        core.extend(wasm_section(
+            5,
+            &[
+                1, 0x04, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x10,
+            ],
+        ));
+        let mut data = component.to_vec();
+        data.extend(wasm_section(1, &core));
+        data.extend(wasm_section(1, component));
+        data.extend(wasm_section(1, &core[..10]));
+        let info = get_wasm_structure(&data).unwrap();
+        assert_eq!(info.nested_modules.len(), 3);
+        assert_eq!(
+            info.nested_modules[0].as_ref().unwrap().memories[0].min,
+            1 << 60
+        );
+        assert!(info.nested_modules[1].is_err() && info.nested_modules[2].is_err());
+
+        // Size of huge memory64 saturates.
+        let file = spool(&data[..], u64::MAX).unwrap();
+        let input = Input::open(&file.path).unwrap();
+        let report = collect_report(|report| get_wasm_info(&once_fixture(), &input, report));
+        assert!(report.contains(", initial 18446744073709551615"));
+        assert!(report.contains("## Core module 1\nError: not a core module\n"));
+    }
+
+    fn java_utf8(text: &str) -> Vec<u8> {
+        let mut constant = vec![1];
+        constant.extend((text.len() as u16).to_be_bytes());
+        constant.extend(text.as_bytes());
+        constant
+    }
+
+    #[test]
+    fn java_class_structure_test() {
+        use crate::java::{
+            get_access_flags, get_class_structure, get_manifest_entries, java_release,
+        };
+
+        // Java 17 class file
+        let mut data = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 61, 0, 11];
+        data.extend(java_utf8("com/example/Main"));
+        data.extend([7, 0, 1]);
+        data.extend(java_utf8("java/lang/Object"));
+        data.extend([7, 0, 3]);
+        data.extend(java_utf8("java/lang/Runnable"));
+        data.extend([7, 0, 5]);
+        // Long takes two constant pool entries
+        data.extend([5, 0, 0, 0, 0, 0, 0, 0, 42]);
+        data.extend(java_utf8("SourceFile"));
+        data.extend(java_utf8("Main.java"));
+        // Flags, this, super, one interface, no fields, one method
+        data.extend([
+            0, 0x21, 0, 2, 0, 4, 0, 1, 0, 6, 0, 0, 0, 1, 0, 1, 0, 1, 0, 1, 0, 0,
+        ]);
+        data.extend([0, 1, 0, 9, 0, 0, 0, 2, 0, 10]);
+
+        let info = get_class_structure(&data).unwrap();
+        assert_eq!((info.major, info.minor), (61, 0));
+        assert_eq!(java_release(info.major), "17");
+        assert_eq!(java_release(48), "1.4");
+        assert_eq!(info.name, "com.example.Main");
+        assert_eq!(info.super_name.as_deref(), Some("java.lang.Object"));
+        assert_eq!(info.interfaces, vec!["java.lang.Runnable"]);
+        assert_eq!(get_access_flags(info.access_flags), vec!["public", "super"]);
+        assert_eq!((info.fields, info.methods), (0, 1));
+        assert_eq!(info.source_file.as_deref(), Some("Main.java"));
+        assert!(get_class_structure(&data[..40]).is_err());
+
+        let manifest = "Manifest-Version: 1.0\r\nMain-Class: com.example.\r\n Main\r\n\r\nName: a\r\nKey: b\r\n";
+        assert_eq!(
+            get_manifest_entries(manifest),
+            vec![
+                ("Manifest-Version".to_string(), "1.0".to_string()),
+                ("Main-Class".to_string(), "com.example.Main".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn entropy_structure_test() {
+        use crate::entropy::{get_entropy, get_entropy_structure, RegionKind};
+
+        let mut data = vec![0u8; 512];
+        data.extend(b"The quick brown fox jumps over the lazy dog.\n".repeat(12)[..512].to_vec());
+        // Every byte value exactly twice looks like compressed data.
+        data.extend((0..512).map(|i| (i % 256) as u8));
+        data.extend([0u8; 100]);
+
+        let info = get_entropy_structure(data.as_slice(), 256).unwrap();
+        assert_eq!(info.size, 1636);
+        assert_eq!(info.blocks.len(), 7);
+        assert_eq!(info.blocks[0], 0.0);
+        assert!((info.blocks[4] - 8.0).abs() < 1e-9);
+        let regions: Vec<(u64, u64, RegionKind)> = info
+            .regions
+            .iter()
+            .map(|r| (r.offset, r.length, r.kind))
+            .collect();
+        assert_eq!(
+            regions,
+            vec![
+                (0, 512, RegionKind::Padding),
+                (512, 512, RegionKind::Text),
+                (1024, 512, RegionKind::Compressed),
+                (1536, 100, RegionKind::Padding)
+            ]
+        );
+        assert_eq!(info.histogram[0], 614);
+        assert!((info.printable_ratio() - (512.0 + 196.0) / 1636.0).abs() < 1e-9);
+        assert_eq!(get_entropy(&[]), 0.0);
+        assert_eq!(get_entropy(&[1, 2]), 1.0);
+    }
+
+    #[test]
+    fn hash_test() {
+        use crate::components::HashAlgorithm;
+        use crate::hash::{format_sum_line, get_hash_info, get_hashes, get_sums_info};
+        use crate::report::collect_report;
+
+        let algorithms = [
+            HashAlgorithm::Md5,
+            HashAlgorithm::Sha1,
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Sha512,
+            HashAlgorithm::Blake3,
+            HashAlgorithm::Crc32,
+        ];
+        let hashes: Vec<String> = get_hashes(&b"abc"[..], &algorithms)
+            .unwrap()
+            .into_iter()
+            .map(|(_, hash)| hash)
+            .collect();
+        assert_eq!(
+            hashes,
+            vec![
+                "900150983cd24fb0d6963f7d28e17f72",
+                "a9993e364706816aba3e25717850c26c9cd0d89d",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+                "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+                "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+                "352441c2"
+            ]
+        );
+        // Official BLAKE3 test vector input crosses chunk boundary.
+        let data: Vec<u8> = (0..1025).map(|i| (i % 251) as u8).collect();
+        let hashes = get_hashes(
+            data.as_slice(),
+            &[HashAlgorithm::Sha256, HashAlgorithm::Blake3],
+        )
+        .unwrap();
+        assert_eq!(
+            hashes[0].1,
+            "bc0b6b10b89b9487a12fda2a8cc13194e7091c217aabf8b92846274026f4bcd0"
+        );
+        assert_eq!(
+            hashes[1].1,
+            "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444"
+        );
+        assert_eq!(format_sum_line("00", "a b"), "00  a b");
+        assert_eq!(format_sum_line("00", "a\nb"), "\\00  a\\nb");
+
+        // Digest of file truncated by --max-read is not printed.
+        let mut args = once_fixture();
+        args.file_path = PathBuf::from("Cargo.toml");
+        args.hashes = vec![HashAlgorithm::Sha256];
+        let report = collect_report(|report| get_sums_info(&args, report));
+        assert!(report.ends_with("  Cargo.toml\n"));
+        args.limits.max_read = Some(10);
+        assert_eq!(collect_report(|report| get_sums_info(&args, report)), "");
+        let mut input = Input::open(&args.file_path).unwrap();
+        input.truncate(10);
+        let report = collect_report(|report| get_hash_info(&args, &input, report));
+        assert!(report.starts_with("Error: file is read only up to --max-read"));
+    }
+
+    fn tar_header(name: &str, size: usize, kind: u8) -> Vec<u8> {
+        let mut header = vec![0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[148..156].fill(b' ');
+        let checksum: u32 = header.iter().map(|b| *b as u32).sum();
+        header[148..155].copy_from_slice(format!("{:06o}\0", checksum).as_bytes());
+        header
+    }
+
+    #[test]
+    fn tar_entries_test() {
+        use crate::tar::{for_each_tar_entry, is_tar_header};
+
+        let long_name = "dir/".repeat(30) + "file.txt";
+        let mut data = tar_header("dir/", 0, b'5');
+        data.extend(tar_header("././@LongLink", long_name.len() + 1, b'L'));
+        data.extend(long_name.as_bytes());
+        data.resize(data.len() + 512 - long_name.len(), 0);
+        data.extend(tar_header("short", 5, b'0'));
+        data.extend(b"hello");
+        data.resize(data.len() + 507, 0);
+        data.extend([0u8; 1024]);
+        assert!(is_tar_header(&data));
+
+        let mut entries = Vec::new();
+        for_each_tar_entry(data.as_slice(), |entry, reader| {
+            let mut content = String::new();
+            reader.read_to_string(&mut content).unwrap();
+            entries.push((entry.name.clone(), entry.is_file(), content));
+        })
+        .unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                ("dir/".to_string(), false, String::new()),
+                (long_name, true, "hello".to_string())
+            ]
+        );
+        data[0] = b'x';
+        assert!(!is_tar_header(&data));
+    }
+
+    #[test]
+    fn fuzzy_hash_test() {
+        use crate::components::HashAlgorithm;
+        use crate::fuzzy::{compare, compare_ssdeep, compare_tlsh};
+        use crate::hash::get_hashes;
+
+        let fuzzy = |data: &[u8]| {
+            let hashes = get_hashes(data, &[HashAlgorithm::Ssdeep, HashAlgorithm::Tlsh]).unwrap();
+            (hashes[0].1.clone(), hashes[1].1.clone())
+        };
+        // Pseudo-random words, so pieces of text differ.
+        let mut seed: u32 = 7;
+        let mut text = Vec::new();
+        while text.len() < 20000 {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            let length = 2 + (seed >> 16) % 8;
+            for i in 0..length {
+                text.push(b'a' + ((seed >> (i * 2)) % 26) as u8);
+            }
+            text.push(if seed.is_multiple_of(13) { b'\n' } else { b' ' });
+        }
+        let mut edited = text.clone();
+        edited[8000..8010].copy_from_slice(b"0123456789");
+
+        let (ssdeep, tlsh) = fuzzy(&text);
+        let (edited_ssdeep, edited_tlsh) = fuzzy(&edited);
+        assert!(ssdeep.starts_with("384:") || ssdeep.starts_with("192:"));
+        assert!(tlsh.starts_with("T1") && tlsh.len() == 72);
+        assert_eq!(compare_ssdeep(&ssdeep, &ssdeep), Some(100));
+        assert!(compare_ssdeep(&ssdeep, &edited_ssdeep).unwrap() > 80);
+        assert_eq!(compare_tlsh(&tlsh, &tlsh), Some(0));
+        assert!(compare_tlsh(&tlsh, &edited_tlsh).unwrap() < 30);
+
+        let (other_ssdeep, other_tlsh) = fuzzy(
+            &(0..20000u32)
+                .map(|i| (i * i % 251) as u8)
+                .collect::<Vec<u8>>(),
+        );
+        assert_eq!(compare_ssdeep(&ssdeep, &other_ssdeep), Some(0));
+        assert!(compare_tlsh(&tlsh, &other_tlsh).unwrap() > 100);
+
+        assert_eq!(fuzzy(b""), ("3::".to_string(), "TNULL".to_string()));
+        // Digest and score from documentation of python-ssdeep, which wraps reference libfuzzy.
+        let (ssdeep, _) =
+            fuzzy(b"Also called fuzzy hashes, Ctph can match inputs that have homologies.");
+        assert_eq!(ssdeep, "3:AXGBicFlgVNhBGcL6wCrFQEv:AXGHsNhxLsr2C");
+        let (other_ssdeep, _) =
+            fuzzy(b"Also called fuzzy hashes, CTPH can match inputs that have homologies.");
+        assert_eq!(compare_ssdeep(&ssdeep, &other_ssdeep), Some(22));
+        assert_eq!(compare_ssdeep("3:abc:def", "not a hash"), None);
+        assert_eq!(compare_tlsh("TNULL", &tlsh), None);
+
+        // Given file which can't be hashed fails comparison, it doesn't fall back to clustering.
+        let paths = [PathBuf::from("Cargo.toml"), PathBuf::from("missing file")];
+        let error = compare(&paths, HashAlgorithm::Ssdeep, 50).unwrap_err();
+        assert!(error.to_string().starts_with("missing file: "));
+    }
+
+    #[test]
+    fn text_structure_test() {
+        use crate::plaintext::{get_language, get_text_structure, is_text};
+
+        let info =
+            get_text_structure(b"fn main() {\n    let x = 1;  \r\n\tprintln!(\"{}\", x);\n}");
+        assert_eq!(info.encoding, "ASCII");
+        assert_eq!((info.lines, info.words, info.chars), (4, 10, 51));
+        assert_eq!((info.lf, info.crlf, info.cr), (2, 1, 0));
+        assert!(info.line_ending().starts_with("mixed"));
+        assert!(!info.final_newline);
+        assert_eq!(info.trailing_whitespace, 1);
+        assert_eq!(
+            (info.tab_indented, info.space_indented, info.indent_width),
+            (1, 1, Some(4))
+        );
+        assert_eq!(info.longest_line, (19, 3));
+        // Blank lines count in every line ending style.
+        for text in [
+            "a\n\nb\n\n\nlongest\n",
+            "a\r\n\r\nb\r\n\r\n\r\nlongest\r\n",
+            "a\r\rb\r\r\rlongest\r",
+        ] {
+            let info = get_text_structure(text.as_bytes());
+            assert_eq!((info.lines, info.longest_line), (6, (7, 6)));
+        }
+        assert_eq!(get_text_structure(b"\n\n\n").lines, 3);
+        assert_eq!(get_text_structure(b"\r\n\n\r").lines, 3);
+
+        let info = get_text_structure("\u{feff}żółw\n".as_bytes());
+        assert_eq!(
+            (info.encoding.as_str(), info.has_bom, info.chars),
+            ("UTF-8", true, 5)
+        );
+        let utf16: Vec<u8> = "hello\r\nworld\r\n"
+            .encode_utf16()
+            .flat_map(|u| u.to_le_bytes())
+            .collect();
+        assert!(is_text(&utf16));
+        let info = get_text_structure(&utf16);
+        assert_eq!(
+            (info.encoding.as_str(), info.lines, info.line_ending()),
+            ("UTF-16LE", 2, "CRLF (Windows)".to_string())
+        );
+        // "Привет мир" in Windows-1251 and café in Windows-1252 with curly quotes.
+        assert!(get_text_structure(b"\xCF\xF0\xE8\xE2\xE5\xF2 \xEC\xE8\xF0")
+            .encoding
+            .starts_with("Windows-1251"));
+        assert!(get_text_structure(b"\x93caf\xE9\x94")
+            .encoding
+            .starts_with("Windows-1252"));
+        assert!(!is_text(b"\x7FELF\x02\x01\x01\x00\x00\x00"));
+        assert!(!is_text(&[0u8; 4096]));
+
+        assert_eq!(
+            get_language(
+                "use std::io;\npub fn main() {\n    let mut x = Some(1).unwrap();\n}",
+                ""
+            )
+            .unwrap()
+            .0,
+            "Rust"
+        );
+        assert_eq!(
+            get_language("println!(\"a\");\nprintln!(\"b\");\n", ""),
+            Some(("Rust", 2))
+        );
+        assert_eq!(
+            get_language(
+                "def main():\n    print(None)\n\nif True:\n    main()\n",
+                "txt"
+            )
+            .unwrap()
+            .0,
+            "Python"
+        );
+        assert_eq!(
+            get_language(
+                "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tx := 1\n\tfmt.Println(x)\n}\n",
+                ""
+            )
+            .unwrap()
+            .0,
+            "Go"
+        );
+        // Headers are shared by C and C++, extension decides close calls.
+        assert_eq!(
+            get_language("#include <stdio.h>\nint f(void);\n", "h")
+                .unwrap()
+                .0,
+            "C"
+        );
+        assert_eq!(get_language("1 2 3", "rs"), None);
+    }
+
+    #[test]
+    fn text_id_test() {
+        use crate::plaintext::{get_modeline_id, get_shebang_id, get_structure_id, get_text_id};
+
+        assert_eq!(get_shebang_id("#!/bin/sh"), Some("sh"));
+        assert_eq!(get_shebang_id("#! /usr/bin/python3.12 -u"), Some("py"));
+        assert_eq!(get_shebang_id("#!/usr/bin/env ruby"), Some("rb"));
+        assert_eq!(
+            get_shebang_id("#!/usr/bin/env -S deno run --allow-net"),
+            Some("ts")
+        );
+        assert_eq!(get_shebang_id("#!/usr/bin/env -Sperl -w"), Some("pl"));
+        assert_eq!(
+            get_shebang_id("#!/usr/bin/env -u HOME LANG=C node"),
+            Some("js")
+        );
+        assert_eq!(get_shebang_id("#!/usr/bin/awk -f"), Some("awk"));
+        assert_eq!(get_shebang_id("#!/usr/bin/unknown"), None);
+        assert_eq!(get_shebang_id("# comment"), None);
+
+        assert_eq!(
+            get_modeline_id("# -*- mode: python; coding: utf-8 -*-"),
+            Some("py")
+        );
+        assert_eq!(get_modeline_id("/* -*- C++ -*- */"), Some("cpp"));
+        assert_eq!(get_modeline_id("# vim: set ft=sh ts=4 :"), Some("sh"));
+        assert_eq!(get_modeline_id("-- vi:syntax=lua"), Some("lua"));
+        assert_eq!(get_modeline_id("plain line"), None);
+
+        assert_eq!(
+            get_structure_id("<?xml version=\"1.0\"?>\n<root/>"),
+            Some("xml")
+        );
+        assert_eq!(
+            get_structure_id("\n<!DOCTYPE html>\n<html></html>"),
+            Some("html")
+        );
+        assert_eq!(get_structure_id("{\n  \"a\": [1, 2]\n}\n"), Some("json"));
+        assert_eq!(get_structure_id("[{\"a\": 1}]"), Some("json"));
+        assert_eq!(
+            get_structure_id(
+                "[package]\nname = \"fat\"\nversion = \"0.1.0\"\n\n[[bin]]\npath = \"main.rs\"\n"
+            ),
+            Some("toml")
+        );
+        assert_eq!(
+            get_structure_id("; settings\n[General]\nName=Some app\nPath=C:\\app\n"),
+            Some("ini")
+        );
+        assert_eq!(
+            get_structure_id("name: build\non:\n  push:\n    branches: main\njobs:\n  - run\n"),
+            Some("yaml")
+        );
+        assert_eq!(
+            get_structure_id("Just some prose: with a colon.\nAnd another line of text.\n"),
+            None
+        );
+
+        // Shebang wins, modelines are found in the last lines too.
+        assert_eq!(
+            get_text_id("#!/bin/bash\n# vim: ft=python\n", ""),
+            Some("sh")
+        );
+        assert_eq!(
+            get_text_id("print('hi')\n", "print('hi')\n# vim: ft=python\n"),
+            Some("py")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_metadata_test() {
+        use crate::unix::{format_mode, get_special_bits, get_unix_metadata};
+        use std::os::unix::fs::MetadataExt;
+        assert_eq!(format_mode(0o100644), "-rw-r--r--");
+        assert_eq!(format_mode(0o104755), "-rwsr-xr-x");
+        assert_eq!(format_mode(0o042770), "drwxrws---");
+        assert_eq!(format_mode(0o041777), "drwxrwxrwt");
+        assert_eq!(format_mode(0o101644), "-rw-r--r-T");
+        assert_eq!(format_mode(0o120777), "lrwxrwxrwx");
+        assert_eq!(get_special_bits(0o6755), vec!["setuid", "setgid"]);
+        assert!(get_special_bits(0o755).is_empty());
+
+        let metadata = get_unix_metadata(std::path::Path::new("Cargo.toml")).unwrap();
+        let std_metadata = std::fs::metadata("Cargo.toml").unwrap();
+        assert_eq!(
+            (metadata.inode, metadata.size, metadata.mode),
+            (std_metadata.ino(), std_metadata.len(), std_metadata.mode())
+        );
+        assert_eq!(metadata.modified.unwrap().seconds, std_metadata.mtime());
+        assert!(metadata.changed.is_some());
+        // Same `major:minor` form whether statx or stat was used.
+        let device = std_metadata.dev() as libc::dev_t;
+        let (major, minor) = unsafe { (libc::major(device), libc::minor(device)) };
+        assert_eq!(metadata.device, format!("{}:{}", major, minor));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn xattr_decode_test() {
+        use crate::xattr::{decode_acl, decode_capabilities, format_xattr_value};
+
+        let le = |values: &[u32]| {
+            values
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect::<Vec<u8>>()
+        };
+        // cap_net_bind_service and cap_net_raw permitted and effective, like `setcap cap_net_raw,cap_net_bind_service+ep`.
+        let capabilities = le(&[0x02000001, 1 << 10 | 1 << 13, 0, 0, 0]);
+        assert_eq!(
+            decode_capabilities(&capabilities).unwrap(),
+            "cap_net_bind_service,cap_net_raw=ep"
+        );
+        let capabilities = le(&[0x02000000, 1 << 21, 1 << 12, 1 << 6, 0]);
+        assert_eq!(
+            decode_capabilities(&capabilities).unwrap(),
+            "cap_net_admin=i cap_sys_admin,cap_perfmon=p"
+        );
+        let capabilities = le(&[0x03000001, 1, 0, 0, 0, 1000]);
+        assert_eq!(
+            decode_capabilities(&capabilities).unwrap(),
+            "cap_chown=ep [rootid=1000]"
+        );
+        assert_eq!(decode_capabilities(&[1, 2]), None);
+
+        let mut acl = le(&[2]);
+        for (tag, permissions, id) in [
+            (0x01u16, 6u16, u32::MAX),
+            (0x02, 4, 4000000000),
+            (0x04, 4, u32::MAX),
+            (0x10, 5, u32::MAX),
+            (0x20, 0, u32::MAX),
+        ] {
+            acl.extend(tag.to_le_bytes());
+            acl.extend(permissions.to_le_bytes());
+            acl.extend(id.to_le_bytes());
+        }
+        assert_eq!(
+            decode_acl(&acl).unwrap(),
+            vec![
+                "user::rw-",
+                "user:4000000000:r--",
+                "group::r--",
+                "mask::r-x",
+                "other::---"
+            ]
+        );
+        assert_eq!(decode_acl(&acl[..10]), None);
+
+        assert_eq!(
+            format_xattr_value("security.selinux", b"system_u:object_r:bin_t:s0\0"),
+            "\"system_u:object_r:bin_t:s0\""
+        );
+        assert_eq!(
+            format_xattr_value("user.xdg.origin.url", b"https://example.com/a.zip"),
+            "\"https://example.com/a.zip\""
+        );
+        assert_eq!(format_xattr_value("security.ima", &[4, 0, 255]), "0x0400ff");
+    }
+
+    #[test]
+    fn datetime_test() {
+        use crate::datetime::{
+            format_relative, format_timestamp, parse_iso8601, TimeZone, Timestamp,
+        };
+        use crate::pdf::parse_pdf_date;
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let before = UNIX_EPOCH - Duration::new(1, 250_000_000);
+        assert_eq!(
+            Timestamp::from_system_time(before),
+            Timestamp {
+                seconds: -2,
+                nanoseconds: 750_000_000
+            }
+        );
+
+        let mut args = once_fixture();
+        assert_eq!(
+            format_timestamp(
+                &args,
+                Timestamp {
+                    seconds: 0,
+                    nanoseconds: 5
+                }
+            ),
+            "1970-01-01 00:00:00.000000005 UTC"
+        );
+        // 2024-07-01 12:00:00 and 2024-01-01 12:00:00 UTC
+        let (summer, winter) = (
+            Timestamp::from_seconds(1719835200),
+            Timestamp::from_seconds(1704110400),
+        );
+        args.time_format = TimeFormat::Rfc3339;
+        assert_eq!(format_timestamp(&args, summer), "2024-07-01T12:00:00Z");
+        args.time_format = TimeFormat::Unix;
+        assert_eq!(format_timestamp(&args, summer), "1719835200");
+
+        args.time_zone = TimeZone::from_name("Europe/Prague").unwrap();
+        args.time_format = TimeFormat::Default;
+        assert_eq!(format_timestamp(&args, summer), "2024-07-01 14:00:00 CEST");
+        assert_eq!(format_timestamp(&args, winter), "2024-01-01 13:00:00 CET");
+        args.time_format = TimeFormat::Iso8601;
+        assert_eq!(format_timestamp(&args, summer), "2024-07-01T14:00:00+02:00");
+        // Last Sunday of March 2024 is 31st, change is at 01:00 UTC.
+        let at =
+            |args: &Arguments, seconds| format_timestamp(args, Timestamp::from_seconds(seconds));
+        assert_eq!(at(&args, 1711846799), "2024-03-31T01:59:59+01:00");
+        assert_eq!(at(&args, 1711846800), "2024-03-31T03:00:00+02:00");
+        // Back at 01:00 UTC on October 27th.
+        assert_eq!(at(&args, 1729990799), "2024-10-27T02:59:59+02:00");
+        assert_eq!(at(&args, 1729990800), "2024-10-27T02:00:00+01:00");
+        args.time_zone = TimeZone::from_name("Australia/Sydney").unwrap();
+        args.time_format = TimeFormat::Default;
+        assert_eq!(format_timestamp(&args, winter), "2024-01-01 23:00:00 AEDT");
+        assert_eq!(format_timestamp(&args, summer), "2024-07-01 22:00:00 AEST");
+        assert!(TimeZone::from_name("../etc/passwd").is_err());
+
+        assert_eq!(format_relative(0, 5), "just now");
+        assert_eq!(format_relative(0, 3 * 86400), "3 days ago");
+        assert_eq!(format_relative(7200, 0), "in 2 hours");
+
+        let (time, offset) = parse_iso8601("2024-01-02T03:04:05.5+01:30").unwrap();
+        assert_eq!(
+            (time.hour(), time.second(), offset.unwrap().whole_minutes()),
+            (3, 5, 90)
+        );
+        assert_eq!(
+            parse_iso8601("2024-01-02T03:04Z")
+                .unwrap()
+                .1
+                .unwrap()
+                .whole_seconds(),
+            0
+        );
+        assert_eq!(parse_iso8601("2024-01-02").unwrap().1, None);
+        assert!(parse_iso8601("2024-13-02").is_none());
+        let (time, offset) = parse_pdf_date("D:20240102030405-05'00'").unwrap();
+        assert_eq!((time.day(), offset.unwrap().whole_hours()), (2, -5));
+    }
+
+    #[test]
+    fn spool_test() {
+        use crate::spool::{is_stream, spool};
+        use std::path::Path;
+
+        let data = b"PK\x03\x04 stream data";
+        let spooled = spool(&data[..], data.len() as u64).unwrap();
+        assert_eq!(spooled.size, data.len() as u64);
+        assert_eq!(std::fs::read(&spooled.path).unwrap(), data);
+        let path = spooled.path.clone();
+        drop(spooled);
+        assert!(!path.exists());
+        assert!(spool(&data[..], 4).is_err());
+
+        assert!(is_stream(Path::new("-")));
+        assert!(!is_stream(Path::new("Cargo.toml")));
+        #[cfg(unix)]
+        assert!(is_stream(Path::new("/dev/null")));
+
+        let mut args = once_fixture();
+        args.stream_path = Some(PathBuf::from("-"));
+        assert_eq!(args.name_path(), Path::new("-"));
+        args.name = Some("download.zip".to_string());
+        assert_eq!(args.name_path().extension().unwrap(), "zip");
+    }
+
+    #[test]
+    fn input_test() {
+        use crate::archive::{get_archive_kind, ArchiveKind};
+        use crate::spool::spool;
+        use std::io::{Seek, SeekFrom};
+
+        let input = Input::open(std::path::Path::new("Cargo.toml")).unwrap();
+        assert_eq!(input.bytes(), std::fs::read("Cargo.toml").unwrap());
+        assert_eq!(input.size(), input.bytes().len() as u64);
+        let mut reader = input.reader();
+        reader.seek(SeekFrom::End(-1)).unwrap();
+        assert_eq!(reader.position() + 1, input.size());
+
+        let empty = spool(&b""[..], 0).unwrap();
+        let input = Input::open(&empty.path).unwrap();
+        assert!(input.bytes().is_empty());
+        assert!(!input.is_mapped());
+
+        assert_eq!(
+            get_archive_kind(b"Rar!\x1a\x07\x01\x00"),
+            Some(ArchiveKind::Rar)
+        );
+        assert_eq!(get_archive_kind(b"plain text"), None);
+    }
+
+    #[test]
+    fn batch_test() {
+        use crate::batch::collect_files;
+        use crate::report::collect_report;
+
+        let report = collect_report(|report| {
+            writeln!(report, "# Size: {}", 5);
+            write!(report, "a");
+        });
+        assert_eq!(report, "# Size: 5\na");
+        let report = collect_report(|report| {
+            writeln!(report, "## PDF information");
+            panic!("broken file");
+        });
+        assert_eq!(
+            report,
+            "## PDF information\nError: analysis failed: broken file\n"
+        );
+
+        let files = collect_files(&[PathBuf::from("samples"), PathBuf::from("Cargo.toml")]);
+        assert_eq!(
+            files.first().unwrap(),
+            &PathBuf::from("samples/empty_sample.zip")
+        );
+        assert_eq!(files.last().unwrap(), &PathBuf::from("Cargo.toml"));
+        assert!(files.contains(&PathBuf::from("samples/recognition/zip/apk.zip")));
+        assert!(files.iter().all(|file| file.is_file()));
+    }
+
+    #[test]
+    fn limits_test() {
+        use crate::limits::*;
+        use std::io::Read;
+        use std::time::Duration;
+
+        let limits = Limits {
+            max_entries: 2,
+            max_decompressed: 10,
+            max_depth: 3,
+            ..Limits::default()
+        };
+        start(limits);
+        assert!(allow_entry(0) && allow_entry(1));
+        assert!(!allow_entry(2));
+        assert!(allow_depth(2));
+        assert!(!allow_depth(3));
+        assert_eq!(get_reached(), vec![Limit::Entries, Limit::Depth]);
+
+        // Budget is shared by all readers of one file.
+        start(limits);
+        let mut data = Vec::new();
+        limit_decompressed(&[1u8; 6][..])
+            .read_to_end(&mut data)
+            .unwrap();
+        assert!(get_reached().is_empty());
+        limit_decompressed(&[2u8; 4][..])
+            .read_to_end(&mut data)
+            .unwrap();
+        assert!(get_reached().is_empty());
+        limit_decompressed(&[3u8; 4][..])
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data.len(), 10);
+        assert_eq!(get_reached(), vec![Limit::Decompressed]);
+        assert_eq!(
+            describe(&limits, Limit::Decompressed),
+            "max-decompressed (10 bytes)"
+        );
+        start(limits);
+        assert!(allow_decompressed(10));
+        assert!(get_reached().is_empty());
+        assert!(!allow_decompressed(11));
+        assert_eq!(get_reached(), vec![Limit::Decompressed]);
+
+        start(Limits {
+            timeout: Some(Duration::ZERO),
+            ..Limits::default()
+        });
+        assert!(is_timed_out());
+        assert!(!allow_entry(0));
+        assert_eq!(get_reached(), vec![Limit::Timeout]);
+        assert_eq!(describe(&get(), Limit::Timeout), "timeout (0 s)");
+        // Long loops stop too, partial digest would look valid.
+        let hashes = crate::hash::get_hashes(&[0u8; 16][..], &[HashAlgorithm::Sha256]);
+        assert_eq!(hashes.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+        let entropy = crate::entropy::get_entropy_structure(&[0u8; 16][..], 16).unwrap();
+        assert_eq!(entropy.size, 0);
+        assert_eq!(crate::plaintext::get_text_structure(b"a\nb\n").lines, 0);
+
+        let mut input = Input::open(std::path::Path::new("Cargo.toml")).unwrap();
+        assert!(input.truncate(5));
+        assert_eq!(input.bytes(), b"[pack");
+        assert!(!input.truncate(100));
+        assert!(input.is_truncated());
+
+        // Unrar reads file by path, so truncated archive is refused.
+        let mut input = Input::open(std::path::Path::new("samples/sample.rar")).unwrap();
+        assert!(!input.is_truncated());
+        input.truncate(100);
+        let mut report = Report::dropped();
+        let result = crate::archive::for_each_entry(
+            &input,
+            ArchiveKind::Rar,
+            &mut report,
+            |_, _, _| panic!(),
+        );
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn size_format_test() {
+        let mut args = once_fixture();
+        assert_eq!(format_size(&args, 1234567), "1234567");
+        args.units = SizeUnits::Bytes;
+        assert_eq!(format_size(&args, 1234567), "1,234,567 B");
+        assert_eq!(format_size(&args, 999), "999 B");
+        assert_eq!(format_size(&args, 1000), "1,000 B");
+        args.units = SizeUnits::Si;
+        assert_eq!(format_size(&args, 999), "999 B");
+        assert_eq!(format_size(&args, 1234567), "1.2 MB");
+        args.units = SizeUnits::Iec;
+        args.size_precision = 3;
+        assert_eq!(format_size(&args, 1536), "1.500 KiB");
+        assert_eq!(format_size(&args, u64::MAX), "16.000 EiB");
+
+        assert_eq!(format_ratio(250, 1000), "25.00%, 4.00:1");
+        assert_eq!(format_ratio(0, 0), "empty");
+        assert_eq!(format_ratio(2, 0), "empty");
+        assert_eq!(format_ratio(0, 10), "0.00%");
+        assert_eq!(format_ratio(102, 100), "102.00%, 0.98:1");
+    }
+
+    #[test]
+    fn sections_fields_test() {
+        use crate::report::collect_report;
+
+        assert_eq!(Section::of_field("general.size"), Some(Section::General));
+        assert_eq!(Section::of_field("type.mime"), Some(Section::Type));
+        assert_eq!(Section::of_field("general."), None);
+        assert_eq!(Section::of_field("size"), None);
+        assert_eq!(Section::of_field("bogus.size"), None);
+        assert_eq!(Section::of_field("general.sise"), None);
+        assert_eq!(Section::of_field("hashes.sha256"), Some(Section::Hashes));
+        assert_eq!(
+            Section::check_field("metadata.title"),
+            Err("section metadata has no fields".to_string())
+        );
+        assert!(Section::check_field("general.sise")
+            .unwrap_err()
+            .contains("general are name, source, size"));
+        // Every noted field can be selected.
+        for algorithm in [
+            "md5", "sha1", "sha256", "sha512", "blake3", "crc32", "ssdeep", "tlsh",
+        ] {
+            assert!(HashAlgorithm::from_str(algorithm).is_ok());
+            assert!(Section::Hashes.keys().contains(&algorithm));
+        }
+
+        let mut args = once_fixture();
+        assert!(args.has_section(Section::General));
+        assert!(!args.has_section(Section::Entropy));
+        args.sections = vec![Section::Type];
+        assert!(!args.has_section(Section::General));
+
+        // Fields are noted only when selected, printed text is dropped.
+        let mut report = Report::collected();
+        report.note_field(Section::General, "size", "1");
+        assert_eq!(report.into_fields(), vec![]);
+        let mut report = Report::fields();
+        report.print_field(Section::General, "size", "Size", 10);
+        report.note_field(Section::Type, "extension", "zip");
+        assert_eq!(
+            report.into_fields(),
+            vec![
+                ("general.size".to_string(), "10".to_string()),
+                ("type.extension".to_string(), "zip".to_string())
+            ]
+        );
+        let report = collect_report(|report| {
+            report.print_field(Section::General, "size", "Size", 10);
+        });
+        assert_eq!(report, "# Size: 10\n");
+    }
+
+    #[test]
+    fn line_output_test() {
+        use crate::plaintext::{get_charset, get_text_encoding};
+
+        assert_eq!(get_text_encoding(b"hello\n").as_deref(), Some("ASCII"));
+        assert_eq!(
+            get_text_encoding("héllo\n".as_bytes()).as_deref(),
+            Some("UTF-8")
+        );
+        assert_eq!(get_text_encoding(b"\x00\x01\x02\xFF\xFE"), None);
+        assert_eq!(get_charset("ASCII"), "us-ascii");
+        assert_eq!(get_charset("UTF-8 (with invalid sequences)"), "utf-8");
+        assert_eq!(get_charset("UTF-16LE"), "utf-16le");
+        assert_eq!(get_charset("Windows-1252 (guessed)"), "windows-1252");
+
+        // Errors of detection are written to dropped report, they would break one-line output.
+        let mut report = Report::dropped();
+        writeln!(report, "## Unreadable zip file");
+        assert_eq!(report.into_text(), "");
+    }
+
+    #[test]
+    fn der_time_test() {
+        use crate::pe::format_der_time;
+
+        assert_eq!(format_der_time(0x17, b"240102030405Z"), "2024-01-02");
+        assert_eq!(format_der_time(0x17, b"990102030405Z"), "1999-01-02");
+        assert_eq!(format_der_time(0x18, b"20240102030405Z"), "2024-01-02");
+        assert_eq!(format_der_time(0x17, b"2401"), "2401");
+        // Non-ASCII bytes are not sliced in the middle of replacement character.
+        assert_eq!(
+            format_der_time(0x17, b"\xFF\xFF0102"),
+            "\u{FFFD}\u{FFFD}0102"
+        );
+        assert_eq!(format_der_time(0x18, b"20\xFF40102"), "20\u{FFFD}40102");
+    }
+
+    #[test]
+    fn pdf_predictor_test() {
+        use crate::pdf::Pdf;
+
+        // Two rows of 2 bytes with Up filter: [1, 2] and [1+3, 2+4].
+        let stream = |parameters: &str| {
+            format!(
+                "<< /Length 14 /Filter /ASCIIHexDecode /DecodeParms << /Predictor 12 {} >> >>\nstream\n02010202030400\nendstream",
+                parameters
+            )
+        };
+        let objects = [
+            stream("/Columns 2"),
+            stream("/Columns 1e15"),
+            stream("/Columns 2 /Colors 1e300 /BitsPerComponent 1e300"),
+            stream("/Columns -5"),
+        ];
+        let objects: Vec<&str> = objects.iter().map(|o| o.as_str()).collect();
+        let data = sample_pdf(&objects, "<< /Size 5 >>");
+        let pdf = Pdf::new(&data);
+        assert_eq!(pdf.decode_stream(&pdf.objects[&1]), Some(vec![1, 2, 4, 6]));
+        for number in 2..=4 {
+            assert_eq!(pdf.decode_stream(&pdf.objects[&number]), None);
+        }
+    }
+
+    #[test]
+    fn pdf_malformed_test() {
+        use crate::pdf::{get_xmp_value, get_xref_chain, Pdf};
+
+        // Offset of compressed object past the end of address space is skipped.
+        let data = sample_pdf(
+            &["<< /Type /ObjStm /N 1 /First 4 >>\nstream\n5 99999999999999999999999\nendstream"],
+            "<< /Size 2 >>",
+        );
+        let pdf = Pdf::new(&data);
+        assert!(!pdf.objects.contains_key(&5));
+
+        // Entries of table can't be skipped past the end.
+        let data = b"%PDF-1.7\nxref\n0 1000000000000000000\ntrailer\n<< >>\nstartxref\n9\n%%EOF\n";
+        let (sections, _, _) = get_xref_chain(&Pdf::new(data));
+        assert_eq!(sections[0].entries, 0);
+
+        assert_eq!(
+            get_xmp_value("<pdf:Producer</pdf:Producer>", "pdf:Producer"),
+            None
+        );
+        assert_eq!(
+            get_xmp_value("<pdf:Producer>fat</pdf:Producer>", "pdf:Producer").as_deref(),
+            Some("fat")
+        );
+    }
 }
@@ -0,0 +1,303 @@
+use crate::components::Section;
+use crate::datetime::{format_timestamp, Timestamp};
+use crate::report::Report;
+use crate::{format_size, Arguments};
+use std::ffi::{CStr, CString};
+use std::fs::{self, Metadata};
+use std::io::Error;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// Size of block counted by `st_blocks`, independent of filesystem block size.
+const STAT_BLOCK_SIZE: u64 = 512;
+
+/// Filesystem metadata of Unix file, `None` marks fields not provided by filesystem.
+pub struct UnixMetadata {
+    pub inode: u64,
+    pub device: String,
+    pub links: u64,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u64,
+    /// Count of 512-byte blocks allocated on disk.
+    pub blocks: u64,
+    pub block_size: u64,
+    pub accessed: Option<Timestamp>,
+    pub modified: Option<Timestamp>,
+    pub changed: Option<Timestamp>,
+    pub born: Option<Timestamp>,
+    /// Flags like immutable or append-only, empty if unknown.
+    pub attributes: Vec<&'static str>,
+}
+
+impl UnixMetadata {
+    pub fn allocated(&self) -> u64 {
+        self.blocks * STAT_BLOCK_SIZE
+    }
+
+    /// Are there holes in file, allocated space is smaller than its size.
+    pub fn is_sparse(&self) -> bool {
+        self.allocated() < self.size
+    }
+}
+
+/// Gets metadata with `statx`, which also gives birth time and file attributes.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn get_statx(path: &Path) -> Result<UnixMetadata, Error> {
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let mut buf: libc::statx = unsafe { std::mem::zeroed() };
+    let result = unsafe {
+        libc::statx(
+            libc::AT_FDCWD,
+            c_path.as_ptr(),
+            libc::AT_STATX_SYNC_AS_STAT,
+            libc::STATX_BASIC_STATS | libc::STATX_BTIME,
+            &mut buf,
+        )
+    };
+    if result != 0 {
+        return Err(Error::last_os_error());
+    }
+    let time = |flag: libc::c_uint, timestamp: libc::statx_timestamp| {
+        (buf.stx_mask & flag != 0).then_some(Timestamp {
+            seconds: timestamp.tv_sec,
+            nanoseconds: timestamp.tv_nsec,
+        })
+    };
+    let attributes = [
+        (libc::STATX_ATTR_IMMUTABLE, "immutable"),
+        (libc::STATX_ATTR_APPEND, "append-only"),
+        (libc::STATX_ATTR_COMPRESSED, "compressed"),
+        (libc::STATX_ATTR_ENCRYPTED, "encrypted"),
+        (libc::STATX_ATTR_NODUMP, "no dump"),
+        (libc::STATX_ATTR_VERITY, "fs-verity"),
+        (libc::STATX_ATTR_DAX, "DAX"),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| buf.stx_attributes_mask & buf.stx_attributes & *flag as u64 != 0)
+    .map(|(_, name)| name)
+    .collect();
+    Ok(UnixMetadata {
+        inode: buf.stx_ino,
+        device: format!("{}:{}", buf.stx_dev_major, buf.stx_dev_minor),
+        links: buf.stx_nlink as u64,
+        mode: buf.stx_mode as u32,
+        uid: buf.stx_uid,
+        gid: buf.stx_gid,
+        size: buf.stx_size,
+        blocks: buf.stx_blocks,
+        block_size: buf.stx_blksize as u64,
+        accessed: time(libc::STATX_ATIME, buf.stx_atime),
+        modified: time(libc::STATX_MTIME, buf.stx_mtime),
+        changed: time(libc::STATX_CTIME, buf.stx_ctime),
+        born: time(libc::STATX_BTIME, buf.stx_btime),
+        attributes,
+    })
+}
+
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+fn get_statx(_path: &Path) -> Result<UnixMetadata, Error> {
+    Err(Error::from(std::io::ErrorKind::Unsupported))
+}
+
+/// Gets metadata from `stat`, birth time only where standard library provides it.
+fn get_stat(metadata: &Metadata) -> UnixMetadata {
+    let time = |seconds: i64, nanoseconds: i64| {
+        Some(Timestamp {
+            seconds,
+            nanoseconds: nanoseconds as u32,
+        })
+    };
+    let device = metadata.dev() as libc::dev_t;
+    let (major, minor) = unsafe { (libc::major(device), libc::minor(device)) };
+    UnixMetadata {
+        inode: metadata.ino(),
+        device: format!("{}:{}", major, minor),
+        links: metadata.nlink(),
+        mode: metadata.mode(),
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+        size: metadata.size(),
+        blocks: metadata.blocks(),
+        block_size: metadata.blksize(),
+        accessed: time(metadata.atime(), metadata.atime_nsec()),
+        modified: time(metadata.mtime(), metadata.mtime_nsec()),
+        changed: time(metadata.ctime(), metadata.ctime_nsec()),
+        born: metadata.created().ok().map(Timestamp::from_system_time),
+        attributes: Vec::new(),
+    }
+}
+
+/// Gets metadata of file (symbolic links are followed).
+pub fn get_unix_metadata(path: &Path) -> Result<UnixMetadata, Error> {
+    // Old kernels and other libcs have no statx.
+    match get_statx(path) {
+        Ok(metadata) => Ok(metadata),
+        Err(_) => Ok(get_stat(&fs::metadata(path)?)),
+    }
+}
+
+/// Formats mode like `ls -l`, e.g. `-rwsr-xr-x`.
+pub fn format_mode(mode: u32) -> String {
+    let kind = match mode & 0o170000 {
+        0o140000 => 's',
+        0o120000 => 'l',
+        0o100000 => '-',
+        0o060000 => 'b',
+        0o040000 => 'd',
+        0o020000 => 'c',
+        0o010000 => 'p',
+        _ => '?',
+    };
+    let mut text = String::from(kind);
+    // Special bit replaces execute bit of user, group or others.
+    for (shift, special, letter) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = (mode >> shift) & 0o7;
+        text.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        text.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        text.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => letter,
+            (false, true) => letter.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    text
+}
+
+/// Gets names of special mode bits.
+pub fn get_special_bits(mode: u32) -> Vec<&'static str> {
+    [(0o4000, "setuid"), (0o2000, "setgid"), (0o1000, "sticky")]
+        .into_iter()
+        .filter(|(bit, _)| mode & bit != 0)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Calls reentrant passwd or group lookup, growing buffer while it's too small.
+fn lookup_name<T>(
+    lookup: impl Fn(*mut T, &mut Vec<libc::c_char>, *mut *mut T) -> libc::c_int,
+    name: impl Fn(&T) -> *const libc::c_char,
+) -> Option<String> {
+    let mut buffer: Vec<libc::c_char> = vec![0; 1024];
+    loop {
+        let mut entry: T = unsafe { std::mem::zeroed() };
+        let mut result: *mut T = std::ptr::null_mut();
+        match lookup(&mut entry, &mut buffer, &mut result) {
+            0 if result.is_null() => return None,
+            0 => {
+                let name = unsafe { CStr::from_ptr(name(&entry)) };
+                return Some(name.to_string_lossy().to_string());
+            }
+            libc::ERANGE if buffer.len() < 1 << 20 => buffer.resize(buffer.len() * 2, 0),
+            _ => return None,
+        }
+    }
+}
+
+/// Gets user name of uid.
+pub fn get_user_name(uid: u32) -> Option<String> {
+    lookup_name(
+        |entry: *mut libc::passwd, buffer, result| unsafe {
+            libc::getpwuid_r(uid, entry, buffer.as_mut_ptr(), buffer.len(), result)
+        },
+        |entry| entry.pw_name,
+    )
+}
+
+/// Gets group name of gid.
+pub fn get_group_name(gid: u32) -> Option<String> {
+    lookup_name(
+        |entry: *mut libc::group, buffer, result| unsafe {
+            libc::getgrgid_r(gid, entry, buffer.as_mut_ptr(), buffer.len(), result)
+        },
+        |entry| entry.gr_name,
+    )
+}
+
+fn format_time(args: &Arguments, timestamp: Option<Timestamp>) -> String {
+    match timestamp {
+        Some(timestamp) => format_timestamp(args, timestamp),
+        None => "unavailable".to_string(),
+    }
+}
+
+/// Gets Unix filesystem info about file, like inode, owner and permissions.
+pub fn get_unix_info(args: &Arguments, report: &mut Report) {
+    if let Ok(target) = fs::read_link(&args.file_path) {
+        writeln!(report, "# Symbolic link to: {}", target.to_string_lossy());
+    }
+    let metadata = match get_unix_metadata(&args.file_path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
+    write!(
+        report,
+        "# Allocated: {} ({} blocks of {} bytes)",
+        format_size(args, metadata.allocated()),
+        metadata.blocks,
+        STAT_BLOCK_SIZE
+    );
+    if metadata.is_sparse() {
+        write!(report, ", sparse");
+    }
+    writeln!(report);
+    writeln!(
+        report,
+        "# Filesystem block size: {}",
+        format_size(args, metadata.block_size)
+    );
+    report.print_field(Section::General, "inode", "Inode", metadata.inode);
+    report.print_field(Section::General, "device", "Device", metadata.device);
+    report.print_field(Section::General, "links", "Links", metadata.links);
+    report.print_field(
+        Section::General,
+        "mode",
+        "Mode",
+        format!(
+            "{:04o} ({})",
+            metadata.mode & 0o7777,
+            format_mode(metadata.mode)
+        ),
+    );
+    let special_bits = get_special_bits(metadata.mode);
+    if !special_bits.is_empty() {
+        writeln!(report, "# Special bits: {}", special_bits.join(", "));
+    }
+    report.print_field(
+        Section::General,
+        "owner",
+        "Owner",
+        format!(
+            "{} ({})",
+            get_user_name(metadata.uid).unwrap_or("unknown".to_string()),
+            metadata.uid
+        ),
+    );
+    report.print_field(
+        Section::General,
+        "group",
+        "Group",
+        format!(
+            "{} ({})",
+            get_group_name(metadata.gid).unwrap_or("unknown".to_string()),
+            metadata.gid
+        ),
+    );
+    if !metadata.attributes.is_empty() {
+        writeln!(report, "# Attributes: {}", metadata.attributes.join(", "));
+    }
+    for (key, name, time) in [
+        ("created", "Created", metadata.born),
+        ("modified", "Last modified", metadata.modified),
+        ("accessed", "Last accessed", metadata.accessed),
+        ("changed", "Status changed", metadata.changed),
+    ] {
+        report.print_field(Section::General, key, name, format_time(args, time));
+    }
+}
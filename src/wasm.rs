@@ -0,0 +1,659 @@
+use crate::input::Input;
+use crate::report::Report;
+use crate::{format_size, Arguments};
+use std::io::{Error, ErrorKind};
+
+/// Size of WebAssembly memory page.
+const PAGE_SIZE: u64 = 65536;
+
+pub struct WasmSection {
+    pub id: u8,
+    /// Name of custom section, otherwise name of section id.
+    pub name: String,
+    pub offset: usize,
+    pub size: usize,
+}
+
+pub struct WasmImport {
+    pub module: String,
+    pub name: String,
+    pub description: String,
+}
+
+pub struct WasmExport {
+    pub name: String,
+    pub description: String,
+}
+
+/// Memory or table limits, memory in pages.
+pub struct Limits {
+    pub min: u64,
+    pub max: Option<u64>,
+    pub shared: bool,
+    pub is_64: bool,
+}
+
+#[derive(Default)]
+pub struct WasmInfo {
+    pub version: u16,
+    pub is_component: bool,
+    pub sections: Vec<WasmSection>,
+    pub types: Vec<String>,
+    pub imports: Vec<WasmImport>,
+    pub exports: Vec<WasmExport>,
+    /// Type index of every function, imported ones first.
+    pub functions: Vec<u32>,
+    pub imported_functions: usize,
+    pub memories: Vec<Limits>,
+    /// (element type, limits)
+    pub tables: Vec<(String, Limits)>,
+    pub globals: usize,
+    pub start: Option<u32>,
+    pub data_segments: usize,
+    pub module_name: Option<String>,
+    pub function_names: usize,
+    /// (field, "name version" list)
+    pub producers: Vec<(String, Vec<String>)>,
+    pub target_features: Vec<String>,
+    pub source_map: Option<String>,
+    pub external_debug_info: Option<String>,
+    /// Core modules nested in component, broken one doesn't hide the others.
+    pub nested_modules: Vec<Result<WasmInfo, Error>>,
+}
+
+/// Reads LEB128 numbers, names and bytes from section payload.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let b = *self.data.get(self.pos)?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..70).step_by(7) {
+            let b = self.byte()?;
+            if shift < 64 {
+                value |= ((b & 0x7F) as u64) << shift;
+            }
+            if b & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.u64().map(|v| v as u32)
+    }
+
+    /// Reads signed LEB128 (used for heap types), sign doesn't matter for us.
+    fn s33(&mut self) -> Option<i64> {
+        let mut value = 0i64;
+        let mut shift = 0;
+        loop {
+            let b = self.byte()?;
+            value |= ((b & 0x7F) as i64) << shift;
+            shift += 7;
+            if b & 0x80 == 0 {
+                if shift < 64 && b & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return Some(value);
+            }
+            if shift > 63 {
+                return None;
+            }
+        }
+    }
+
+    fn bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(length)?)?;
+        self.pos += length;
+        Some(bytes)
+    }
+
+    fn name(&mut self) -> Option<String> {
+        let length = self.u32()? as usize;
+        Some(String::from_utf8_lossy(self.bytes(length)?).to_string())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+}
+
+pub fn section_name(id: u8, is_component: bool) -> &'static str {
+    if is_component {
+        return match id {
+            0 => "custom",
+            1 => "core module",
+            2 => "core instance",
+            3 => "core type",
+            4 => "component",
+            5 => "instance",
+            6 => "alias",
+            7 => "type",
+            8 => "canon",
+            9 => "start",
+            10 => "import",
+            11 => "export",
+            12 => "value",
+            _ => "unknown",
+        };
+    }
+    match id {
+        0 => "custom",
+        1 => "type",
+        2 => "import",
+        3 => "function",
+        4 => "table",
+        5 => "memory",
+        6 => "global",
+        7 => "export",
+        8 => "start",
+        9 => "element",
+        10 => "code",
+        11 => "data",
+        12 => "data count",
+        13 => "tag",
+        _ => "unknown",
+    }
+}
+
+fn read_value_type(reader: &mut Reader) -> Option<String> {
+    let kind = reader.byte()?;
+    Some(
+        match kind {
+            0x7F => "i32",
+            0x7E => "i64",
+            0x7D => "f32",
+            0x7C => "f64",
+            0x7B => "v128",
+            0x70 => "funcref",
+            0x6F => "externref",
+            0x6E => "anyref",
+            0x69 => "exnref",
+            // (ref null? heaptype)
+            0x63 | 0x64 => {
+                let heap = reader.s33()?;
+                let nullable = if kind == 0x63 { "null " } else { "" };
+                return Some(format!("(ref {}{})", nullable, heap));
+            }
+            _ => "?",
+        }
+        .to_string(),
+    )
+}
+
+fn read_limits(reader: &mut Reader) -> Option<Limits> {
+    let flags = reader.byte()?;
+    let min = reader.u64()?;
+    let max = if flags & 0x1 != 0 {
+        Some(reader.u64()?)
+    } else {
+        None
+    };
+    Some(Limits {
+        min,
+        max,
+        shared: flags & 0x2 != 0,
+        is_64: flags & 0x4 != 0,
+    })
+}
+
+fn format_limits(limits: &Limits) -> String {
+    match limits.max {
+        Some(max) => format!("min {}, max {}", limits.min, max),
+        None => format!("min {}, no max", limits.min),
+    }
+}
+
+fn read_function_type(reader: &mut Reader) -> Option<String> {
+    // Only plain function types, GC proposal types are described by their form.
+    match reader.byte()? {
+        0x60 => {
+            let read_list = |reader: &mut Reader| -> Option<Vec<String>> {
+                let count = reader.u32()?;
+                (0..count).map(|_| read_value_type(reader)).collect()
+            };
+            let params = read_list(reader)?;
+            let results = read_list(reader)?;
+            Some(format!(
+                "({}) -> ({})",
+                params.join(", "),
+                results.join(", ")
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn read_import(reader: &mut Reader, info: &mut WasmInfo) -> Option<WasmImport> {
+    let module = reader.name()?;
+    let name = reader.name()?;
+    let description = match reader.byte()? {
+        0x00 => {
+            let index = reader.u32()?;
+            info.functions.push(index);
+            info.imported_functions += 1;
+            format!("func {}", get_type(info, Some(index)))
+        }
+        0x01 => {
+            let kind = read_value_type(reader)?;
+            format!("table {}, {}", kind, format_limits(&read_limits(reader)?))
+        }
+        0x02 => {
+            let limits = read_limits(reader)?;
+            let description = format!("memory {}", format_limits(&limits));
+            info.memories.push(limits);
+            description
+        }
+        0x03 => {
+            let kind = read_value_type(reader)?;
+            let mutable = reader.byte()? == 1;
+            info.globals += 1;
+            format!("global {}{}", if mutable { "mut " } else { "" }, kind)
+        }
+        0x04 => {
+            reader.byte()?;
+            format!("tag {}", get_type(info, Some(reader.u32()?)))
+        }
+        kind => format!("unknown kind {}", kind),
+    };
+    Some(WasmImport {
+        module,
+        name,
+        description,
+    })
+}
+
+fn get_type(info: &WasmInfo, index: Option<u32>) -> String {
+    index
+        .and_then(|i| info.types.get(i as usize))
+        .cloned()
+        .unwrap_or_else(|| "?".to_string())
+}
+
+fn read_custom_section(reader: &mut Reader, name: &str, info: &mut WasmInfo) -> Option<()> {
+    match name {
+        "name" => {
+            while !reader.is_empty() {
+                let kind = reader.byte()?;
+                let size = reader.u32()? as usize;
+                let mut subsection = Reader::new(reader.bytes(size)?);
+                match kind {
+                    0 => info.module_name = subsection.name(),
+                    1 => info.function_names = subsection.u32()? as usize,
+                    _ => (),
+                }
+            }
+        }
+        "producers" => {
+            for _ in 0..reader.u32()? {
+                let field = reader.name()?;
+                let mut values = Vec::new();
+                for _ in 0..reader.u32()? {
+                    let name = reader.name()?;
+                    let version = reader.name()?;
+                    values.push(format!("{} {}", name, version).trim().to_string());
+                }
+                info.producers.push((field, values));
+            }
+        }
+        "target_features" => {
+            for _ in 0..reader.u32()? {
+                let prefix = reader.byte()? as char;
+                let feature = reader.name()?;
+                info.target_features.push(format!("{}{}", prefix, feature));
+            }
+        }
+        "sourceMappingURL" => info.source_map = reader.name(),
+        "external_debug_info" => info.external_debug_info = reader.name(),
+        _ => (),
+    }
+    Some(())
+}
+
+/// Parses known section of core module.
+fn read_section(reader: &mut Reader, id: u8, info: &mut WasmInfo) -> Option<()> {
+    match id {
+        1 => {
+            for _ in 0..reader.u32()? {
+                let Some(kind) = read_function_type(reader) else {
+                    // Can't skip unknown type forms, stop reading types.
+                    break;
+                };
+                info.types.push(kind);
+            }
+        }
+        2 => {
+            for _ in 0..reader.u32()? {
+                let import = read_import(reader, info)?;
+                info.imports.push(import);
+            }
+        }
+        3 => {
+            for _ in 0..reader.u32()? {
+                info.functions.push(reader.u32()?);
+            }
+        }
+        4 => {
+            for _ in 0..reader.u32()? {
+                let kind = read_value_type(reader)?;
+                info.tables.push((kind, read_limits(reader)?));
+            }
+        }
+        5 => {
+            for _ in 0..reader.u32()? {
+                info.memories.push(read_limits(reader)?);
+            }
+        }
+        6 => info.globals += reader.u32()? as usize,
+        7 => {
+            for _ in 0..reader.u32()? {
+                let name = reader.name()?;
+                let kind = reader.byte()?;
+                let index = reader.u32()?;
+                let description = match kind {
+                    0x00 => format!(
+                        "func {}",
+                        get_type(info, info.functions.get(index as usize).copied())
+                    ),
+                    0x01 => format!("table {}", index),
+                    0x02 => format!("memory {}", index),
+                    0x03 => format!("global {}", index),
+                    0x04 => format!("tag {}", index),
+                    kind => format!("unknown kind {}", kind),
+                };
+                info.exports.push(WasmExport { name, description });
+            }
+        }
+        8 => info.start = Some(reader.u32()?),
+        11 => info.data_segments = reader.u32()? as usize,
+        _ => (),
+    }
+    Some(())
+}
+
+/// Reads component import or export name with its kind.
+fn read_component_extern(reader: &mut Reader, is_export: bool) -> Option<WasmExport> {
+    // 0x00 plain name, 0x01 name with version suffix (older encoding)
+    let form = reader.byte()?;
+    let name = reader.name()?;
+    if form == 0x01 {
+        reader.name()?;
+    }
+    let sort = |reader: &mut Reader| -> Option<&'static str> {
+        Some(match reader.byte()? {
+            0x00 => {
+                reader.byte()?;
+                "core module"
+            }
+            0x01 => "func",
+            0x02 => "value",
+            0x03 => "type",
+            0x04 => "component",
+            0x05 => "instance",
+            _ => "unknown",
+        })
+    };
+    let kind = sort(reader)?;
+    if is_export {
+        reader.u32()?;
+        // Optional type ascription
+        if reader.byte()? == 0x01 {
+            sort(reader)?;
+            reader.u32()?;
+        }
+    } else {
+        match kind {
+            // valtype is primitive type byte or type index
+            "value" => {
+                reader.s33()?;
+            }
+            // type bound: eq index or sub resource
+            "type" => {
+                if reader.byte()? == 0x00 {
+                    reader.u32()?;
+                }
+            }
+            _ => {
+                reader.u32()?;
+            }
+        }
+    }
+    Some(WasmExport {
+        name,
+        description: kind.to_string(),
+    })
+}
+
+/// Gets sections, types, imports, exports and custom sections of module or component.
+pub fn get_wasm_structure(data: &[u8]) -> Result<WasmInfo, Error> {
+    if !data.starts_with(b"\0asm") || data.len() < 8 {
+        return Err(Error::new(ErrorKind::InvalidData, "missing \\0asm magic"));
+    }
+    let mut info = WasmInfo {
+        version: u16::from_le_bytes([data[4], data[5]]),
+        is_component: u16::from_le_bytes([data[6], data[7]]) == 1,
+        ..Default::default()
+    };
+    if !info.is_component && info.version != 1 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("unknown module version {}", info.version),
+        ));
+    }
+    let mut reader = Reader::new(data);
+    reader.pos = 8;
+    while !reader.is_empty() {
+        let offset = reader.pos;
+        let (Some(id), Some(size)) = (reader.byte(), reader.u32()) else {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "truncated section header",
+            ));
+        };
+        let Some(payload) = reader.bytes(size as usize) else {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("section at offset {} is truncated", offset),
+            ));
+        };
+        let mut payload_reader = Reader::new(payload);
+        let mut name = section_name(id, info.is_component).to_string();
+        if id == 0 {
+            name = payload_reader.name().unwrap_or_default();
+            read_custom_section(&mut payload_reader, &name, &mut info);
+        } else if info.is_component {
+            match id {
+                1 => {
+                    // Only core modules are read, components are not nested by this section.
+                    let module = if payload.get(4..8) == Some(&[1, 0, 0, 0]) {
+                        get_wasm_structure(payload)
+                    } else {
+                        Err(Error::new(ErrorKind::InvalidData, "not a core module"))
+                    };
+                    info.nested_modules.push(module);
+                }
+                10 | 11 => {
+                    let is_export = id == 11;
+                    for _ in 0..payload_reader.u32().unwrap_or(0) {
+                        let Some(item) = read_component_extern(&mut payload_reader, is_export)
+                        else {
+                            break;
+                        };
+                        if is_export {
+                            info.exports.push(item);
+                        } else {
+                            info.imports.push(WasmImport {
+                                module: String::new(),
+                                name: item.name,
+                                description: item.description,
+                            });
+                        }
+                    }
+                }
+                _ => (),
+            }
+        } else {
+            read_section(&mut payload_reader, id, &mut info);
+        }
+        info.sections.push(WasmSection {
+            id,
+            name,
+            offset,
+            size: size as usize,
+        });
+    }
+    Ok(info)
+}
+
+fn print_module(args: &Arguments, info: &WasmInfo, report: &mut Report) {
+    writeln!(report, "# Sections: {}", info.sections.len());
+    for section in info.sections.iter() {
+        writeln!(
+            report,
+            "{}{} at offset {}, size {}",
+            section.name,
+            if section.id == 0 { " (custom)" } else { "" },
+            section.offset,
+            format_size(args, section.size as u64)
+        );
+    }
+    if let Some(name) = &info.module_name {
+        writeln!(report, "# Module name: {}", name);
+    }
+    if !info.is_component {
+        writeln!(
+            report,
+            "# Functions: {} imported, {} defined, {} type(s), {} global(s), {} data segment(s)",
+            info.imported_functions,
+            info.functions.len() - info.imported_functions,
+            info.types.len(),
+            info.globals,
+            info.data_segments
+        );
+    }
+    if info.function_names > 0 {
+        writeln!(report, "# Function names: {}", info.function_names);
+    }
+    for memory in info.memories.iter() {
+        write!(
+            report,
+            "# Memory: {} pages, {}",
+            format_limits(memory),
+            if memory.is_64 { "64-bit" } else { "32-bit" }
+        );
+        write!(
+            report,
+            ", initial {}",
+            format_size(args, memory.min.saturating_mul(PAGE_SIZE))
+        );
+        if let Some(max) = memory.max {
+            write!(
+                report,
+                ", maximum {}",
+                format_size(args, max.saturating_mul(PAGE_SIZE))
+            );
+        }
+        if memory.shared {
+            write!(report, ", shared");
+        }
+        writeln!(report);
+    }
+    for (kind, limits) in info.tables.iter() {
+        writeln!(report, "# Table: {}, {}", kind, format_limits(limits));
+    }
+    if let Some(start) = info.start {
+        writeln!(report, "# Start function: {}", start);
+    }
+    if !info.imports.is_empty() {
+        writeln!(report, "# Imports: {}", info.imports.len());
+        for import in info.imports.iter() {
+            if import.module.is_empty() {
+                writeln!(report, "{}: {}", import.name, import.description);
+            } else {
+                writeln!(
+                    report,
+                    "{}.{}: {}",
+                    import.module, import.name, import.description
+                );
+            }
+        }
+    }
+    if !info.exports.is_empty() {
+        writeln!(report, "# Exports: {}", info.exports.len());
+        for export in info.exports.iter() {
+            writeln!(report, "{}: {}", export.name, export.description);
+        }
+    }
+    for (field, values) in info.producers.iter() {
+        writeln!(report, "# Producers ({}): {}", field, values.join(", "));
+    }
+    if !info.target_features.is_empty() {
+        writeln!(
+            report,
+            "# Target features: {}",
+            info.target_features.join(", ")
+        );
+    }
+    if let Some(source_map) = &info.source_map {
+        writeln!(report, "# Source map: {}", source_map);
+    }
+    if let Some(debug_info) = &info.external_debug_info {
+        writeln!(report, "# External debug info: {}", debug_info);
+    }
+    if info.sections.iter().any(|s| s.name.starts_with(".debug_")) {
+        writeln!(report, "# DWARF debug info: yes");
+    }
+    if info.sections.iter().any(|s| s.name == "linking") {
+        writeln!(
+            report,
+            "# Relocatable object file (linking section present)"
+        );
+    }
+    if info.sections.iter().any(|s| s.name.starts_with("dylink")) {
+        writeln!(report, "# Dynamic library (dylink section present)");
+    }
+}
+
+/// Gets specified WebAssembly info about file.
+pub fn get_wasm_info(args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## WebAssembly information");
+    let data = input.bytes();
+    let info = match get_wasm_structure(data) {
+        Ok(info) => info,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
+    if info.is_component {
+        writeln!(
+            report,
+            "# Binary: component (version 0x{:x}, layer 1)",
+            info.version
+        );
+    } else {
+        writeln!(report, "# Binary: core module (version {})", info.version);
+    }
+    print_module(args, &info, report);
+    for (i, module) in info.nested_modules.iter().enumerate() {
+        writeln!(report, "## Core module {}", i);
+        match module {
+            Ok(module) => print_module(args, module, report),
+            Err(e) => writeln!(report, "Error: {}", e),
+        }
+    }
+}
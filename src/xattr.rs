@@ -0,0 +1,291 @@
+use crate::report::Report;
+use crate::unix::{get_group_name, get_user_name};
+use crate::Arguments;
+use std::ffi::CString;
+use std::io::Error;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// Capability names by bit number, as in `linux/capability.h`.
+const CAPABILITIES: [&str; 41] = [
+    "chown",
+    "dac_override",
+    "dac_read_search",
+    "fowner",
+    "fsetid",
+    "kill",
+    "setgid",
+    "setuid",
+    "setpcap",
+    "linux_immutable",
+    "net_bind_service",
+    "net_broadcast",
+    "net_admin",
+    "net_raw",
+    "ipc_lock",
+    "ipc_owner",
+    "sys_module",
+    "sys_rawio",
+    "sys_chroot",
+    "sys_ptrace",
+    "sys_pacct",
+    "sys_admin",
+    "sys_boot",
+    "sys_nice",
+    "sys_resource",
+    "sys_time",
+    "sys_tty_config",
+    "mknod",
+    "lease",
+    "audit_write",
+    "audit_control",
+    "setfcap",
+    "mac_override",
+    "mac_admin",
+    "syslog",
+    "wake_alarm",
+    "block_suspend",
+    "audit_read",
+    "perfmon",
+    "bpf",
+    "checkpoint_restore",
+];
+
+const VFS_CAP_REVISION_MASK: u32 = 0xFF000000;
+const VFS_CAP_REVISION_1: u32 = 0x01000000;
+const VFS_CAP_REVISION_2: u32 = 0x02000000;
+const VFS_CAP_REVISION_3: u32 = 0x03000000;
+const VFS_CAP_FLAGS_EFFECTIVE: u32 = 0x000001;
+
+const ACL_VERSION: u32 = 2;
+const ACL_USER_OBJ: u16 = 0x01;
+const ACL_USER: u16 = 0x02;
+const ACL_GROUP_OBJ: u16 = 0x04;
+const ACL_GROUP: u16 = 0x08;
+const ACL_MASK: u16 = 0x10;
+const ACL_OTHER: u16 = 0x20;
+
+/// Longest value shown in hex, longer ones are cut.
+const HEX_LENGTH: usize = 64;
+
+fn le_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Decodes `security.capability` like `getcap` does, e.g. `cap_net_raw=ep`.
+pub fn decode_capabilities(value: &[u8]) -> Option<String> {
+    let magic = le_u32(value, 0)?;
+    let words = match magic & VFS_CAP_REVISION_MASK {
+        VFS_CAP_REVISION_1 => 1,
+        VFS_CAP_REVISION_2 | VFS_CAP_REVISION_3 => 2,
+        _ => return None,
+    };
+    // Permitted and inheritable sets are interleaved by 32-bit words.
+    let mut permitted = 0u64;
+    let mut inheritable = 0u64;
+    for word in 0..words {
+        permitted |= (le_u32(value, 4 + word * 8)? as u64) << (word * 32);
+        inheritable |= (le_u32(value, 8 + word * 8)? as u64) << (word * 32);
+    }
+    let effective = magic & VFS_CAP_FLAGS_EFFECTIVE != 0;
+
+    // Capabilities with the same sets are grouped together.
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for bit in 0..64 {
+        let (p, i) = (permitted >> bit & 1 != 0, inheritable >> bit & 1 != 0);
+        if !p && !i {
+            continue;
+        }
+        let mut flags = String::new();
+        if effective {
+            flags.push('e');
+        }
+        if i {
+            flags.push('i');
+        }
+        if p {
+            flags.push('p');
+        }
+        let name = match CAPABILITIES.get(bit) {
+            Some(name) => format!("cap_{}", name),
+            None => format!("cap_{}", bit),
+        };
+        match groups.iter_mut().find(|(f, _)| *f == flags) {
+            Some((_, names)) => names.push(name),
+            None => groups.push((flags, vec![name])),
+        }
+    }
+    let mut text = groups
+        .iter()
+        .map(|(flags, names)| format!("{}={}", names.join(","), flags))
+        .collect::<Vec<String>>()
+        .join(" ");
+    if text.is_empty() {
+        text = "none".to_string();
+    }
+    // Namespaced capabilities apply only to user namespace with this root.
+    if magic & VFS_CAP_REVISION_MASK == VFS_CAP_REVISION_3 {
+        text += &format!(" [rootid={}]", le_u32(value, 20)?);
+    }
+    Some(text)
+}
+
+fn format_permissions(permissions: u16) -> String {
+    [(4, 'r'), (2, 'w'), (1, 'x')]
+        .iter()
+        .map(|(bit, letter)| if permissions & bit != 0 { *letter } else { '-' })
+        .collect()
+}
+
+/// Decodes POSIX ACL from `system.posix_acl_access` or `system.posix_acl_default` like `getfacl`.
+pub fn decode_acl(value: &[u8]) -> Option<Vec<String>> {
+    if le_u32(value, 0)? != ACL_VERSION || !(value.len() - 4).is_multiple_of(8) {
+        return None;
+    }
+    value[4..]
+        .chunks_exact(8)
+        .map(|entry| {
+            let tag = u16::from_le_bytes([entry[0], entry[1]]);
+            let permissions = format_permissions(u16::from_le_bytes([entry[2], entry[3]]));
+            let id = le_u32(entry, 4)?;
+            let (kind, qualifier) = match tag {
+                ACL_USER_OBJ => ("user", String::new()),
+                ACL_USER => ("user", format_id(id, get_user_name(id))),
+                ACL_GROUP_OBJ => ("group", String::new()),
+                ACL_GROUP => ("group", format_id(id, get_group_name(id))),
+                ACL_MASK => ("mask", String::new()),
+                ACL_OTHER => ("other", String::new()),
+                _ => return None,
+            };
+            Some(format!("{}:{}:{}", kind, qualifier, permissions))
+        })
+        .collect()
+}
+
+fn format_id(id: u32, name: Option<String>) -> String {
+    match name {
+        Some(name) => format!("{}({})", name, id),
+        None => id.to_string(),
+    }
+}
+
+/// Formats value of extended attribute, decoded for known ones, as text or hex otherwise.
+pub fn format_xattr_value(name: &str, value: &[u8]) -> String {
+    let decoded = match name {
+        "security.capability" => decode_capabilities(value),
+        "system.posix_acl_access" | "system.posix_acl_default" => {
+            decode_acl(value).map(|entries| entries.join(", "))
+        }
+        _ => None,
+    };
+    if let Some(decoded) = decoded {
+        return decoded;
+    }
+    // Strings like SELinux labels are often stored with terminating zero.
+    let text = value.strip_suffix(b"\0").unwrap_or(value);
+    match std::str::from_utf8(text) {
+        Ok(text) if !text.chars().any(char::is_control) => format!("\"{}\"", text),
+        _ => {
+            let hex: String = value
+                .iter()
+                .take(HEX_LENGTH)
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            if value.len() > HEX_LENGTH {
+                format!("0x{}... ({} bytes)", hex, value.len())
+            } else {
+                format!("0x{}", hex)
+            }
+        }
+    }
+}
+
+/// Calls `listxattr` or `getxattr` twice, first to get size of buffer.
+fn read_sized(call: impl Fn(*mut libc::c_void, usize) -> isize) -> Result<Vec<u8>, Error> {
+    loop {
+        let size = call(std::ptr::null_mut(), 0);
+        if size < 0 {
+            return Err(Error::last_os_error());
+        }
+        let mut buffer = vec![0u8; size as usize];
+        let read = call(buffer.as_mut_ptr() as *mut libc::c_void, buffer.len());
+        if read >= 0 {
+            buffer.truncate(read as usize);
+            return Ok(buffer);
+        }
+        // Attribute grew between calls.
+        let error = Error::last_os_error();
+        if error.raw_os_error() != Some(libc::ERANGE) {
+            return Err(error);
+        }
+    }
+}
+
+/// Gets names and values of all extended attributes readable by user.
+pub fn get_xattrs(path: &Path) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let names = read_sized(|buffer, size| unsafe {
+        libc::listxattr(c_path.as_ptr(), buffer as *mut libc::c_char, size)
+    })?;
+    let mut xattrs = Vec::new();
+    for name in names.split(|b| *b == 0).filter(|n| !n.is_empty()) {
+        let c_name = CString::new(name)?;
+        let value = read_sized(|buffer, size| unsafe {
+            libc::getxattr(c_path.as_ptr(), c_name.as_ptr(), buffer, size)
+        });
+        match value {
+            Ok(value) => xattrs.push((String::from_utf8_lossy(name).to_string(), value)),
+            // Removed after listing.
+            Err(e) if e.raw_os_error() == Some(libc::ENODATA) => (),
+            Err(e) => return Err(e),
+        }
+    }
+    xattrs.sort();
+    Ok(xattrs)
+}
+
+fn get_namespace(name: &str) -> &str {
+    name.split_once('.').map_or("", |(namespace, _)| namespace)
+}
+
+/// Gets extended attributes of file grouped by namespace.
+pub fn get_xattr_info(args: &Arguments, report: &mut Report) {
+    let xattrs = match get_xattrs(&args.file_path) {
+        Ok(xattrs) => xattrs,
+        Err(e) if e.raw_os_error() == Some(libc::ENOTSUP) => {
+            writeln!(report, "# Extended attributes: not supported by filesystem");
+            return;
+        }
+        Err(e) => {
+            writeln!(report, "# Extended attributes: Error: {}", e);
+            return;
+        }
+    };
+    if xattrs.is_empty() {
+        writeln!(report, "# Extended attributes: none");
+        return;
+    }
+    for group in xattrs.chunk_by(|(a, _), (b, _)| get_namespace(a) == get_namespace(b)) {
+        writeln!(
+            report,
+            "# Extended attributes ({}): {}",
+            get_namespace(&group[0].0),
+            group.len()
+        );
+        for (name, value) in group {
+            writeln!(report, "{}: {}", name, format_xattr_value(name, value));
+        }
+    }
+    // Browsers and download tools record where file came from.
+    for (name, label) in [
+        ("user.xdg.origin.url", "Downloaded from"),
+        ("user.xdg.referrer.url", "Download referrer"),
+    ] {
+        if let Some((_, value)) = xattrs.iter().find(|(n, _)| n == name) {
+            let value = value.strip_suffix(b"\0").unwrap_or(value);
+            writeln!(report, "# {}: {}", label, String::from_utf8_lossy(value));
+        }
+    }
+}
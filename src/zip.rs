@@ -1,24 +1,31 @@
-use crate::{get_extension_from_id, get_extension_name, Arguments};
-use bytesize::ByteSize;
-use std::io::{BufReader, Error};
-use std::{ffi::OsStr, fs, fs::File};
+use crate::components::Section;
+use crate::datetime::{format_local_datetime, format_timestamp, Timestamp};
+use crate::input::Input;
+use crate::limits::allow_entry;
+use crate::report::Report;
+use crate::{format_ratio, format_size, get_extension_from_id, get_extension_name, Arguments};
+use std::ffi::OsStr;
+use std::io::{Error, Read, Seek};
+use time::{Date, Month, PrimitiveDateTime, Time};
+use zip::read::ZipFile;
 use zip::CompressionMethod;
 use zip::DateTime;
+use zip::ExtraField;
 
 /// Is zip file is just a wrapper for other file format.
 /// If true, returns id of extension. If false, returns "zip" id.
 /// Does not return error, but can return in future.
-pub fn get_complex_zip_id(buf_reader: BufReader<File>) -> Result<String, Error> {
-    let mut archive = zip::ZipArchive::new(buf_reader).unwrap();
+pub fn get_complex_zip_id<R: Read + Seek>(reader: R, report: &mut Report) -> Result<String, Error> {
+    let mut archive = zip::ZipArchive::new(reader)?;
 
     // Jar and ear have both MANIFEST.mf file, if it has also application.xml then it's ear, if it does not it's jar
     let mut jar_ear_situation: bool = false;
-    for i in 0..archive.len() {
+    for i in (0..archive.len()).take_while(|i| allow_entry(*i)) {
         let file = match archive.by_index(i) {
             Ok(file) => file,
             Err(e) => {
                 // We can continue because it still can have some useful files.
-                println!("Error when scanning zip - {}", e);
+                writeln!(report, "Error when scanning zip - {}", e);
                 continue;
             }
         };
@@ -88,51 +95,84 @@ pub fn get_complex_zip_id(buf_reader: BufReader<File>) -> Result<String, Error>
 /// If scanning gives error, returns error.
 pub fn get_complex_zip_extension(
     args: &Arguments,
-    buf_reader: BufReader<File>,
+    input: &Input,
+    report: &mut Report,
 ) -> Result<String, Error> {
-    match get_complex_zip_id(buf_reader) {
+    match get_complex_zip_id(input.reader(), report) {
         Ok(id) => get_extension_from_id(args, id),
         Err(e) => Err(e),
     }
 }
 
+/// Formats modification time of entry, DOS time in header has no time zone.
+/// Extended timestamp extra field has real UTC time, so it's preferred.
+fn get_last_modified(args: &Arguments, file: &ZipFile) -> String {
+    let extended_time = file.extra_data_fields().find_map(|field| {
+        let ExtraField::ExtendedTimestamp(timestamp) = field;
+        timestamp.mod_time()
+    });
+    if let Some(seconds) = extended_time {
+        return format_timestamp(args, Timestamp::from_seconds(seconds as i64));
+    }
+    let dos_time = file.last_modified().and_then(|time: DateTime| {
+        let date = Date::from_calendar_date(
+            time.year() as i32,
+            Month::try_from(time.month()).ok()?,
+            time.day(),
+        );
+        let clock = Time::from_hms(time.hour(), time.minute(), time.second());
+        Some(PrimitiveDateTime::new(date.ok()?, clock.ok()?))
+    });
+    match dos_time {
+        Some(time) => format_local_datetime(args, time),
+        None => "unknown".to_string(),
+    }
+}
+
 /// Gets specified zip info about file.
-pub fn get_zip_info(args: &Arguments, buf_reader: BufReader<File>) {
-    println!("## ZIP information");
-    let mut archive = zip::ZipArchive::new(buf_reader).unwrap();
+pub fn get_zip_info(args: &Arguments, input: &Input, report: &mut Report) {
+    writeln!(report, "## ZIP information");
+    let mut archive = match zip::ZipArchive::new(input.reader()) {
+        Ok(archive) => archive,
+        Err(e) => {
+            writeln!(report, "Error: {}", e);
+            return;
+        }
+    };
     if !archive.comment().is_empty() {
-        println!(
+        writeln!(
+            report,
             "# Comment: {:?}",
             std::str::from_utf8(archive.comment()).unwrap()
         );
     }
 
-    print!("# Compressed size: ");
-    let size: u64 = fs::metadata(args.file_path.clone()).unwrap().len();
-    let decompressed_size: u64 = archive.decompressed_size().unwrap_or(0).try_into().unwrap();
-    let mut percent = (size as f32 / decompressed_size as f32) * 100.;
-    if percent > 100. {
-        percent = 100.
-    }
-    if args.is_human {
-        println!(
-            "{}/{} ({:.2}%)",
-            ByteSize(size).to_string_as(true),
-            ByteSize(decompressed_size).to_string_as(true),
-            percent
-        )
-    } else {
-        println!("{}/{} ({:.2}%)", size, decompressed_size, percent);
-    }
+    let size: u64 = input.size();
+    let decompressed_size: u64 = archive
+        .decompressed_size()
+        .unwrap_or(0)
+        .try_into()
+        .unwrap_or(u64::MAX);
+    report.print_field(
+        Section::Archive,
+        "compressed-size",
+        "Compressed size",
+        format!(
+            "{}/{} ({})",
+            format_size(args, size),
+            format_size(args, decompressed_size),
+            format_ratio(size, decompressed_size)
+        ),
+    );
 
     // While we gather zip file information, gather also used compression methods
     let mut compression_methods: Vec<CompressionMethod> = Vec::new();
-    println!("# Zip file contains:");
-    for i in 0..archive.len() {
+    writeln!(report, "# Zip file contains:");
+    for i in (0..archive.len()).take_while(|i| allow_entry(*i)) {
         let file = match archive.by_index(i) {
             Ok(file) => file,
             Err(e) => {
-                println!("Error (most likely encrypted file): {}", e);
+                writeln!(report, "Error (most likely encrypted file): {}", e);
                 continue;
             }
         };
@@ -144,7 +184,7 @@ pub fn get_zip_info(args: &Arguments, buf_reader: BufReader<File>) {
         let outpath = match file.enclosed_name() {
             Some(path) => path,
             None => {
-                println!("File {} has a suspicious path", file.name());
+                writeln!(report, "File {} has a suspicious path", file.name());
                 continue;
             }
         };
@@ -154,39 +194,21 @@ pub fn get_zip_info(args: &Arguments, buf_reader: BufReader<File>) {
             let comment = file.comment();
             let name = file.name();
             if !comment.is_empty() {
-                println!("File {name} has comment: {comment}");
+                writeln!(report, "File {name} has comment: {comment}");
             }
         }
 
         if file.is_dir() {
-            println!("\"{}\"", outpath.display());
+            writeln!(report, "\"{}\"", outpath.display());
         } else {
-            let last_modified: DateTime = file.last_modified().unwrap_or_default();
-            let percent = format!(
-                "{:.prec$}",
-                ((file.compressed_size() as f32 / file.size() as f32) * 100.).min(100.),
-                prec = 2
-            )
-            .to_string();
-            let file_size: String = if args.is_human {
-                ByteSize(file.compressed_size()).to_string_as(true)
-                    + "/"
-                    + &ByteSize(file.size()).to_string_as(true)
-                    + ") ("
-                    + &percent
-                    + "%"
-            } else {
-                file.compressed_size().to_string()
-                    + "/"
-                    + &file.size().to_string()
-                    + ") ("
-                    + &percent
-                    + "%"
-            };
-            print!(
-                "\"{}\" ({}) ({}) (last modified: {}) ({})",
+            let last_modified = get_last_modified(args, &file);
+            write!(
+                report,
+                "\"{}\" ({}/{}) ({}) ({}) (last modified: {}) ({})",
                 outpath.display(),
-                file_size,
+                format_size(args, file.compressed_size()),
+                format_size(args, file.size()),
+                format_ratio(file.compressed_size(), file.size()),
                 get_extension_name(
                     args,
                     file.mangled_name().extension().unwrap_or(OsStr::new(""))
@@ -197,14 +219,14 @@ pub fn get_zip_info(args: &Arguments, buf_reader: BufReader<File>) {
 
             // Unreachable for now
             if file.encrypted() {
-                print!(" (encrypted)");
+                write!(report, " (encrypted)");
             }
-            println!();
+            writeln!(report);
         }
     }
-    print!("# Compression methods used: ");
+    write!(report, "# Compression methods used: ");
     for method in compression_methods.iter_mut() {
-        print!("{} ", method);
+        write!(report, "{} ", method);
     }
-    println!()
+    writeln!(report)
 }